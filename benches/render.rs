@@ -0,0 +1,59 @@
+use std::hint::black_box;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ipnet::IpNet;
+use wireguard_conf::prelude::*;
+
+/// Build server interface with `count` peers, which are holding private keys.
+fn server_interface(count: u32) -> Interface {
+    let peers: Vec<Peer> = (0..count)
+        .map(|i| {
+            let ip = Ipv4Addr::from(u32::from(Ipv4Addr::new(10, 0, 0, 2)) + i);
+
+            PeerBuilder::new()
+                .allowed_ips([IpNet::new_assert(IpAddr::V4(ip), 32)])
+                .private_key(PrivateKey::random())
+                .build()
+        })
+        .collect();
+
+    InterfaceBuilder::new()
+        .address([IpNet::new_assert(Ipv4Addr::new(10, 0, 0, 1).into(), 8)])
+        .listen_port(51820)
+        .peers(peers)
+        .build()
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+
+    for count in [100, 1000, 5000] {
+        let interface = server_interface(count);
+
+        group.bench_with_input(
+            BenchmarkId::new("interface_to_string", count),
+            &interface,
+            |b, interface| b.iter(|| black_box(interface).to_string()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("interface_write_to", count),
+            &interface,
+            |b, interface| b.iter(|| black_box(interface).write_to(&mut io::sink())),
+        );
+    }
+
+    group.finish();
+}
+
+fn to_peer(c: &mut Criterion) {
+    let interface = server_interface(0);
+
+    c.bench_function("interface_to_peer", |b| {
+        b.iter(|| black_box(&interface).to_peer().to_string());
+    });
+}
+
+criterion_group!(benches, render, to_peer);
+criterion_main!(benches);
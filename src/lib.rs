@@ -2,13 +2,44 @@
 //!
 //! - Use [`InterfaceBuilder`] and [`PeerBuilder`] for interface/peers creation.
 //! - Use [`Interface`]'s and [`Peer`]'s [`std::fmt::Display`] for exporting  Wireguard config (`.to_string()`, [`write!()`], etc).
+//! - Use [`Interface::write_to`] or [`ConfigWriter`] for streaming large configs into [`std::io::Write`].
+//! - Use [`FormatOptions`] with [`Interface::display_with`] for customizing output format.
+//! - Use [`Interface::to_public`] for getting public-only view of config, that is safe to publish.
 //! - Use [`PrivateKey`], [`PublicKey`] and [`PresharedKey`] for generating, importing and
-//!   exporting keys.
-//! - Use [`AmneziaSettings`] for generating/using AmneziaWG obfuscation values.
+//!   exporting keys (base64, PEM/DER, hex, raw bytes and `wg genkey` key files).
+//! - Use [`Interface::rotate_key`] and [`Peer::rotate_key`] for key rotation, and [`KeyMetadata`]
+//!   for enforcing maximum key age.
+//! - Use [`KeySource`] (or your own [`KeyProvider`]) for loading keys from files, environment,
+//!   commands or kernel keyring, and [`FormatOptions::private_key_source`] for referring to them
+//!   in configs instead of embedding.
+//! - Use [`PacketCodec`] for classifying, decoding and encoding WireGuard/AmneziaWG packets.
+//! - Use [`AmneziaSettings`] (or [`AmneziaSettingsBuilder`] with [`AmneziaPreset`]) for
+//!   generating/using AmneziaWG obfuscation values, [`SignaturePacket`] for AmneziaWG 1.5 signature packets (`I1..I5`, `J1..J3`)
+//!   and [`MagicHeader`] for AmneziaWG 2.0 header ranges. [`AmneziaSettings::diagnose()`] lists
+//!   every protocol violation and not recommended value.
 //!
 //! # Features
 //!
 //! - `amneziawg` -- adds AmneziaWG obfuscation values support [(see)](https://docs.amnezia.org/documentation/amnezia-wg/).
+//! - `vanity` -- adds multi-threaded search of keys with recognisable public keys
+//!   ([`VanitySearch`]).
+//! - `rosenpass` -- adds generation of [Rosenpass](https://rosenpass.eu) configs for
+//!   post-quantum preshared keys ([`Interface::to_rosenpass`]).
+//! - `keyring` -- adds loading keys from Linux kernel keyring ([`KeySource::Keyring`]).
+//! - `handshake` -- adds offline WireGuard handshake simulation between two configs
+//!   ([`Interface::simulate_handshake`]).
+//! - `pcap` -- adds analysis of pcap/pcapng captures against a config
+//!   ([`Interface::analyze_capture`]).
+//! - `amneziavpn` -- adds export/import of client configs as [AmneziaVPN](https://amnezia.org)
+//!   `vpn://` import strings ([`Interface::to_amnezia_vpn`]). Implies `amneziawg`.
+//! - `singbox` -- adds export/import of [sing-box](https://sing-box.sagernet.org) WireGuard
+//!   endpoints ([`Interface::to_sing_box`]).
+//! - `clash` -- adds export/import of [Clash/mihomo](https://wiki.metacubex.one) WireGuard
+//!   proxies ([`Interface::to_clash`]).
+//! - `sharelink` -- adds export/import of client configs as `wireguard://` share links
+//!   ([`Interface::to_share_link`]).
+//! - `mobileconfig` -- adds generation of Apple configuration profiles (`.mobileconfig`) with
+//!   WireGuard VPN payload for MDM ([`Interface::to_mobileconfig`]).
 //! - `serde` -- adds implementions of [`serde::Serialize`] and [`serde::Deserialize`] for all
 //!   structs.
 //!
@@ -43,6 +74,7 @@
 
 mod macros;
 mod models;
+mod protocol;
 mod utils;
 
 pub mod prelude;
@@ -50,4 +82,5 @@ pub mod prelude;
 pub use ipnet;
 
 pub use models::*;
+pub use protocol::*;
 pub use utils::*;
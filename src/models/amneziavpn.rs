@@ -0,0 +1,315 @@
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use base64::prelude::*;
+use either::Either;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use ipnet::IpNet;
+use serde_json::{json, Map, Value};
+
+use crate::prelude::*;
+
+/// Scheme of AmneziaVPN import strings.
+const SCHEME: &str = "vpn://";
+
+/// AmneziaVPN container with AmneziaWG.
+const CONTAINER: &str = "amnezia-awg";
+
+/// Upper bound of decompressed envelope size, so malicious strings can't exhaust memory.
+const MAX_ENVELOPE_LEN: usize = 1 << 20;
+
+impl Interface {
+    /// Export client config as AmneziaVPN `vpn://` import string.
+    ///
+    /// Interface must be client config with [`Interface::amnezia_settings`] and single peer with
+    /// endpoint (f.e. created by [`Peer::to_interface()`]). `description` is shown in app as
+    /// server name.
+    ///
+    /// String is zlib-compressed (Qt's `qCompress` format), base64url-encoded JSON, so it can be
+    /// shared as QR code.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaVpn`] -- interface isn't AmneziaWG client config.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .endpoint("vpn.example.com")
+    ///     .listen_port(51820)
+    ///     .amnezia_settings(AmneziaSettings::random())
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    /// let client = server.peers[0].to_interface(&server, ToInterfaceOptions::new())?;
+    ///
+    /// let link = client.to_amnezia_vpn("My server")?;
+    /// assert!(link.starts_with("vpn://"));
+    ///
+    /// let imported = Interface::from_amnezia_vpn(&link)?;
+    /// assert_eq!(imported.private_key, client.private_key);
+    /// assert_eq!(imported.amnezia_settings, client.amnezia_settings);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_amnezia_vpn(&self, description: &str) -> WireguardResult<String> {
+        let invalid = |reason: &str| WireguardError::InvalidAmneziaVpn(reason.to_string());
+
+        let settings = self
+            .amnezia_settings
+            .as_ref()
+            .ok_or_else(|| invalid("interface has no AmneziaWG settings"))?;
+        let [server] = self.peers.as_slice() else {
+            return Err(invalid("client config must have exactly one peer"));
+        };
+        let (host, port) = server
+            .split_endpoint()
+            .ok_or_else(|| invalid("peer has no endpoint with port"))?;
+        let address = self
+            .address
+            .first()
+            .ok_or_else(|| invalid("interface has no address"))?;
+
+        let mut awg = amnezia_values(settings);
+        let mut last_config = awg.clone();
+        last_config.extend([
+            (
+                "allowed_ips".to_string(),
+                json!(server
+                    .allowed_ips
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()),
+            ),
+            (
+                "clientId".to_string(),
+                json!(self.private_key.public_key().to_string()),
+            ),
+            ("client_ip".to_string(), json!(address.addr().to_string())),
+            (
+                "client_priv_key".to_string(),
+                json!(self.private_key.to_string()),
+            ),
+            (
+                "client_pub_key".to_string(),
+                json!(self.private_key.public_key().to_string()),
+            ),
+            ("config".to_string(), json!(self.to_string())),
+            ("hostName".to_string(), json!(host)),
+            (
+                "persistent_keep_alive".to_string(),
+                json!(server.persistent_keepalive.to_string()),
+            ),
+            ("port".to_string(), json!(port)),
+            (
+                "psk_key".to_string(),
+                json!(server
+                    .preshared_key
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default()),
+            ),
+            (
+                "server_pub_key".to_string(),
+                json!(server.public_key().to_string()),
+            ),
+        ]);
+        if let Some(mtu) = self.mtu {
+            last_config.insert("mtu".to_string(), json!(mtu.to_string()));
+        }
+        awg.extend([
+            (
+                "last_config".to_string(),
+                json!(Value::Object(last_config).to_string()),
+            ),
+            ("port".to_string(), json!(port.to_string())),
+            ("transport_proto".to_string(), json!("udp")),
+        ]);
+
+        let mut envelope = json!({
+            "containers": [{ "awg": awg, "container": CONTAINER }],
+            "defaultContainer": CONTAINER,
+            "description": description,
+            "hostName": host,
+        });
+        for (key, dns) in ["dns1", "dns2"].into_iter().zip(&self.dns) {
+            envelope[key] = json!(dns);
+        }
+
+        Ok(format!(
+            "{SCHEME}{}",
+            BASE64_URL_SAFE_NO_PAD.encode(compress(envelope.to_string().as_bytes()))
+        ))
+    }
+
+    /// Import client config from AmneziaVPN `vpn://` import string.
+    ///
+    /// Only AmneziaWG container is supported. Interface address is taken from `client_ip` (single
+    /// address, with `/32` or `/128` prefix, if it isn't set).
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaVpn`] -- string is malformed or has no AmneziaWG
+    ///   container.
+    /// - [`WireguardError::InvalidPrivateKey`], [`WireguardError::InvalidPublicKey`],
+    ///   [`WireguardError::InvalidPresharedKey`] -- key is malformed.
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- AmneziaWG settings are invalid.
+    pub fn from_amnezia_vpn(link: &str) -> WireguardResult<Interface> {
+        let invalid = |reason: &str| WireguardError::InvalidAmneziaVpn(reason.to_string());
+
+        let encoded = link
+            .trim()
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| invalid("missing `vpn://` scheme"))?
+            .trim_end_matches('=');
+        let data = BASE64_URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| invalid("malformed base64"))?;
+
+        // old app versions don't compress envelope
+        let data = if data.first() == Some(&b'{') {
+            data
+        } else {
+            decompress(&data).ok_or_else(|| invalid("malformed compressed data"))?
+        };
+        let envelope: Value =
+            serde_json::from_slice(&data).map_err(|_| invalid("malformed JSON"))?;
+
+        let containers = envelope["containers"]
+            .as_array()
+            .ok_or_else(|| invalid("missing containers"))?;
+        let awg = containers
+            .iter()
+            .find(|container| container["container"] == CONTAINER)
+            .and_then(|container| container["awg"].as_object())
+            .ok_or_else(|| invalid("missing AmneziaWG container"))?;
+        let last_config: Value = awg
+            .get("last_config")
+            .and_then(Value::as_str)
+            .and_then(|config| serde_json::from_str(config).ok())
+            .ok_or_else(|| invalid("missing `last_config`"))?;
+
+        let field = |key: &str| string(&last_config[key]);
+        let required = |key: &str| field(key).ok_or_else(|| invalid(&format!("missing `{key}`")));
+
+        let address = required("client_ip")?;
+        let address =
+            Peer::parse_network(&address).ok_or_else(|| invalid("malformed `client_ip`"))?;
+
+        let host = field("hostName")
+            .or_else(|| string(&envelope["hostName"]))
+            .ok_or_else(|| invalid("missing `hostName`"))?;
+        let port = field("port")
+            .or_else(|| awg.get("port").and_then(string))
+            .ok_or_else(|| invalid("missing `port`"))?;
+        let port: u16 = port.parse().map_err(|_| invalid("malformed `port`"))?;
+        let endpoint = Peer::join_endpoint(&host, port);
+
+        let allowed_ips = match last_config["allowed_ips"].as_array() {
+            Some(allowed_ips) => allowed_ips
+                .iter()
+                .map(|allowed_ip| allowed_ip.as_str()?.parse().ok())
+                .collect::<Option<Vec<IpNet>>>()
+                .ok_or_else(|| invalid("malformed `allowed_ips`"))?,
+            None => Peer::default_allowed_ips(),
+        };
+        let preshared_key = match field("psk_key") {
+            Some(key) if !key.is_empty() => Some(PresharedKey::try_from(key)?),
+            _ => None,
+        };
+        let server = Peer {
+            endpoint: Some(endpoint),
+            allowed_ips,
+            persistent_keepalive: number(&last_config, "persistent_keep_alive")?.unwrap_or(0),
+            key: Either::Right(PublicKey::try_from(required("server_pub_key")?)?),
+            preshared_key,
+            #[cfg(feature = "rosenpass")]
+            external_preshared_key: false,
+        };
+
+        Ok(Interface {
+            address: vec![address],
+            listen_port: None,
+            private_key: PrivateKey::try_from(required("client_priv_key")?)?,
+            dns: ["dns1", "dns2"]
+                .into_iter()
+                .filter_map(|key| string(&envelope[key]))
+                .filter(|dns| !dns.is_empty())
+                .collect(),
+            endpoint: None,
+            table: None,
+            mtu: number(&last_config, "mtu")?,
+            amnezia_settings: Some(AmneziaSettings::read_keys(|key| {
+                string(&last_config[key]).or_else(|| awg.get(key).and_then(string))
+            })?),
+            pre_up: vec![],
+            pre_down: vec![],
+            post_up: vec![],
+            post_down: vec![],
+            peers: vec![server],
+        })
+    }
+}
+
+/// Render settings as AmneziaVPN's JSON values (strings, same as config values).
+fn amnezia_values(settings: &AmneziaSettings) -> Map<String, Value> {
+    settings
+        .keys()
+        .map(|(key, value)| (key.to_string(), json!(value.to_string())))
+        .collect()
+}
+
+/// Parse optional numeric field of `last_config`.
+fn number<T: FromStr>(last_config: &Value, key: &str) -> WireguardResult<Option<T>> {
+    string(&last_config[key])
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| WireguardError::InvalidAmneziaVpn(format!("malformed `{key}`")))
+        })
+        .transpose()
+}
+
+/// Get string or number as string.
+fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Compress in Qt's `qCompress` format: big-endian length of uncompressed data and zlib stream.
+fn compress(data: &[u8]) -> Vec<u8> {
+    #[allow(clippy::cast_possible_truncation)]
+    let mut output = (data.len() as u32).to_be_bytes().to_vec();
+
+    let mut encoder = ZlibEncoder::new(&mut output, Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|()| encoder.finish().map(|_| ()))
+        .unwrap_or_else(|_| unreachable!());
+
+    output
+}
+
+/// Decompress Qt's `qCompress` format.
+fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    let (len, stream) = data.split_first_chunk::<4>()?;
+    let len = u32::from_be_bytes(*len) as usize;
+    if len > MAX_ENVELOPE_LEN {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(len);
+    ZlibDecoder::new(stream)
+        .take(MAX_ENVELOPE_LEN as u64)
+        .read_to_end(&mut output)
+        .ok()?;
+
+    Some(output)
+}
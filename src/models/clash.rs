@@ -0,0 +1,300 @@
+use base64::prelude::*;
+use either::Either;
+use serde_yaml_ng::{Mapping, Value};
+
+use crate::prelude::*;
+
+impl Interface {
+    /// Export interface as [Clash/mihomo](https://wiki.metacubex.one/en/config/proxies/wg/)
+    /// `type: wireguard` proxy with `name`, which can be put into `proxies:` list.
+    ///
+    /// Clash supports only one IPv4 and one IPv6 address (`ip` and `ipv6`), so other addresses
+    /// are omitted. Single peer is exported in top-level fields, multiple (or no) peers -- in
+    /// `peers` list. Keepalive is per-proxy, so largest one of peers' is used. `reserved` bytes
+    /// are set for every peer (Cloudflare WARP uses them for client id).
+    ///
+    /// If interface has [`Interface::amnezia_settings`], they are exported as
+    /// `amnezia-wg-option` block, supported by mihomo.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .endpoint("vpn.example.com")
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    /// let client = server.peers[0].to_interface(&server, ToInterfaceOptions::new())?;
+    ///
+    /// let proxy = client.to_clash("wg", None);
+    /// assert_eq!(proxy["type"], "wireguard");
+    /// assert_eq!(proxy["server"], "vpn.example.com");
+    /// assert_eq!(proxy["ip"], "10.0.0.2/24");
+    ///
+    /// let imported = Interface::from_clash(&proxy)?;
+    /// assert_eq!(imported.private_key, client.private_key);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn to_clash(&self, name: &str, reserved: Option<[u8; 3]>) -> Value {
+        let mut proxy = Mapping::new();
+        proxy.insert("name".into(), name.into());
+        proxy.insert("type".into(), "wireguard".into());
+        proxy.insert("private-key".into(), self.private_key.to_string().into());
+
+        let ipv4 = self.address.iter().find(|address| address.addr().is_ipv4());
+        let ipv6 = self.address.iter().find(|address| address.addr().is_ipv6());
+        for (key, address) in [("ip", ipv4), ("ipv6", ipv6)] {
+            if let Some(address) = address {
+                proxy.insert(key.into(), address.to_string().into());
+            }
+        }
+
+        if let [peer] = self.peers.as_slice() {
+            proxy.extend(peer_to_clash(peer, reserved));
+        } else {
+            let peers = self
+                .peers
+                .iter()
+                .map(|peer| peer_to_clash(peer, reserved).into())
+                .collect::<Vec<Value>>();
+            proxy.insert("peers".into(), peers.into());
+        }
+
+        proxy.insert("udp".into(), true.into());
+        if let Some(mtu) = self.mtu {
+            proxy.insert("mtu".into(), mtu.into());
+        }
+        if !self.dns.is_empty() {
+            proxy.insert("dns".into(), self.dns.clone().into());
+            proxy.insert("remote-dns-resolve".into(), true.into());
+        }
+        if let Some(keepalive) = self
+            .peers
+            .iter()
+            .map(|peer| peer.persistent_keepalive)
+            .max()
+            .filter(|keepalive| *keepalive != 0)
+        {
+            proxy.insert("persistent-keepalive".into(), keepalive.into());
+        }
+
+        #[cfg(feature = "amneziawg")]
+        if let Some(settings) = &self.amnezia_settings {
+            proxy.insert("amnezia-wg-option".into(), amnezia_to_clash(settings));
+        }
+
+        proxy.into()
+    }
+
+    /// Import interface from [Clash/mihomo](https://wiki.metacubex.one/en/config/proxies/wg/)
+    /// `type: wireguard` proxy.
+    ///
+    /// Addresses without prefix get `/32` or `/128`. `persistent-keepalive` is set for every
+    /// peer. `reserved` bytes aren't part of WireGuard config, so they're checked, but dropped.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidClash`] -- value isn't WireGuard proxy, or field is malformed.
+    /// - [`WireguardError::InvalidPrivateKey`], [`WireguardError::InvalidPublicKey`],
+    ///   [`WireguardError::InvalidPresharedKey`] -- key is malformed.
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- AmneziaWG settings are invalid.
+    pub fn from_clash(value: &Value) -> WireguardResult<Interface> {
+        if value["type"] != "wireguard" {
+            return Err(invalid("type isn't `wireguard`"));
+        }
+
+        let mut address = Vec::new();
+        for key in ["ip", "ipv6"] {
+            if let Some(ip) = optional_string(value, key)? {
+                address.push(
+                    Peer::parse_network(ip)
+                        .ok_or_else(|| invalid(&format!("malformed `{key}`")))?,
+                );
+            }
+        }
+        if address.is_empty() {
+            return Err(invalid("missing `ip` and `ipv6`"));
+        }
+
+        let persistent_keepalive = number(value, "persistent-keepalive")?.unwrap_or(0);
+        let peers = match value.get("peers") {
+            Some(Value::Sequence(peers)) => peers
+                .iter()
+                .map(|peer| peer_from_clash(peer, persistent_keepalive))
+                .collect::<WireguardResult<_>>()?,
+            None | Some(Value::Null) => vec![peer_from_clash(value, persistent_keepalive)?],
+            Some(_) => return Err(invalid("malformed `peers`")),
+        };
+
+        let dns = match value.get("dns") {
+            Some(Value::Sequence(dns)) => dns
+                .iter()
+                .map(|dns| dns.as_str().map(str::to_string))
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid("malformed `dns`"))?,
+            Some(_) => return Err(invalid("malformed `dns`")),
+            None => vec![],
+        };
+
+        Ok(Interface {
+            address,
+            listen_port: None,
+            private_key: PrivateKey::try_from(string(value, "private-key")?)?,
+            dns,
+            endpoint: None,
+            table: None,
+            mtu: number(value, "mtu")?,
+            #[cfg(feature = "amneziawg")]
+            amnezia_settings: value
+                .get("amnezia-wg-option")
+                .map(amnezia_from_clash)
+                .transpose()?,
+            pre_up: vec![],
+            pre_down: vec![],
+            post_up: vec![],
+            post_down: vec![],
+            peers,
+        })
+    }
+}
+
+fn peer_to_clash(peer: &Peer, reserved: Option<[u8; 3]>) -> Mapping {
+    let mut value = Mapping::new();
+    if let Some((host, port)) = peer.split_endpoint() {
+        value.insert("server".into(), host.into());
+        value.insert("port".into(), port.into());
+    }
+    value.insert("public-key".into(), peer.public_key().to_string().into());
+    if let Some(preshared_key) = peer.exported_preshared_key() {
+        value.insert("pre-shared-key".into(), preshared_key.to_string().into());
+    }
+    let allowed_ips = peer
+        .allowed_ips
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    value.insert("allowed-ips".into(), allowed_ips.into());
+    if let Some(reserved) = reserved {
+        value.insert("reserved".into(), reserved.to_vec().into());
+    }
+
+    value
+}
+
+fn peer_from_clash(value: &Value, persistent_keepalive: u16) -> WireguardResult<Peer> {
+    let endpoint = match optional_string(value, "server")? {
+        Some(host) => {
+            let port: u16 = number(value, "port")?.ok_or_else(|| invalid("missing `port`"))?;
+
+            Some(Peer::join_endpoint(host, port))
+        }
+        None => None,
+    };
+
+    let allowed_ips = match value.get("allowed-ips") {
+        Some(Value::Sequence(allowed_ips)) => allowed_ips
+            .iter()
+            .map(|allowed_ip| Peer::parse_network(allowed_ip.as_str()?))
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid("malformed `allowed-ips`"))?,
+        Some(_) => return Err(invalid("malformed `allowed-ips`")),
+        None => Peer::default_allowed_ips(),
+    };
+
+    check_reserved(value)?;
+
+    Ok(Peer {
+        endpoint,
+        allowed_ips,
+        persistent_keepalive,
+        key: Either::Right(PublicKey::try_from(string(value, "public-key")?)?),
+        preshared_key: optional_string(value, "pre-shared-key")?
+            .filter(|key| !key.is_empty())
+            .map(PresharedKey::try_from)
+            .transpose()?,
+        #[cfg(feature = "rosenpass")]
+        external_preshared_key: false,
+    })
+}
+
+/// Check, that `reserved` is 3 bytes: list of numbers or base64 string, as mihomo accepts.
+fn check_reserved(value: &Value) -> WireguardResult<()> {
+    let is_valid = match value.get("reserved") {
+        None => true,
+        Some(Value::Sequence(bytes)) => {
+            bytes.len() == 3
+                && bytes
+                    .iter()
+                    .all(|byte| byte.as_u64().is_some_and(|byte| u8::try_from(byte).is_ok()))
+        }
+        Some(Value::String(bytes)) => BASE64_STANDARD
+            .decode(bytes)
+            .is_ok_and(|bytes| bytes.len() == 3),
+        Some(_) => false,
+    };
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(invalid("malformed `reserved`"))
+    }
+}
+
+/// Render settings with lowercase keys, as mihomo expects. Numeric values are numbers, header
+/// ranges and signature packets are strings.
+#[cfg(feature = "amneziawg")]
+fn amnezia_to_clash(settings: &AmneziaSettings) -> Value {
+    settings
+        .keys()
+        .map(|(key, value)| {
+            let value = value.to_string();
+            let value = value
+                .parse::<u64>()
+                .map_or_else(|_| Value::from(value), Value::from);
+
+            (key.to_lowercase().into(), value)
+        })
+        .collect::<Mapping>()
+        .into()
+}
+
+#[cfg(feature = "amneziawg")]
+fn amnezia_from_clash(value: &Value) -> WireguardResult<AmneziaSettings> {
+    AmneziaSettings::read_keys(|key| match &value[key.to_lowercase().as_str()] {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+fn invalid(reason: &str) -> WireguardError {
+    WireguardError::InvalidClash(reason.to_string())
+}
+
+fn optional_string<'a>(value: &'a Value, key: &str) -> WireguardResult<Option<&'a str>> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(invalid(&format!("malformed `{key}`"))),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> WireguardResult<&'a str> {
+    optional_string(value, key)?.ok_or_else(|| invalid(&format!("missing `{key}`")))
+}
+
+fn number<T: TryFrom<u64>>(value: &Value, key: &str) -> WireguardResult<Option<T>> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .and_then(|value| T::try_from(value).ok())
+            .map(Some)
+            .ok_or_else(|| invalid(&format!("malformed `{key}`"))),
+    }
+}
@@ -0,0 +1,199 @@
+use rand::Rng;
+
+use crate::prelude::*;
+
+/// Sign, that converted configs need attention.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConversionWarning {
+    /// Settings change packet format, so vanilla WireGuard and AmneziaWG sides can't talk to each
+    /// other. Server has to run separate compatible listener (interface with other port) for
+    /// clients, that weren't converted.
+    ///
+    /// `Jc`, `Jmin`, `Jmax` and signature packets only add packets, which vanilla WireGuard
+    /// ignores, so they stay compatible.
+    IncompatibleSettings {
+        /// Names of incompatible settings, f.e. `S1` or `H2`.
+        settings: Vec<String>,
+    },
+
+    /// Peer has no private key, so its client config can't be generated and has to be updated
+    /// manually.
+    ManualClient {
+        /// Peer's public key.
+        public_key: PublicKey,
+    },
+}
+
+/// Converted interface and client configs, derived from it.
+///
+/// Created by [`Interface::to_vanilla()`] and [`Interface::to_amneziawg()`].
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    /// Converted interface.
+    pub interface: Interface,
+
+    /// Client configs of [`Interface::peers`] with private keys (in same order), generated via
+    /// [`Peer::to_interface()`].
+    pub clients: Vec<Interface>,
+
+    /// Things to take care of. Empty, if converted configs can be deployed as is.
+    pub warnings: Vec<ConversionWarning>,
+}
+
+impl Conversion {
+    fn new(interface: Interface, options: ToInterfaceOptions) -> WireguardResult<Self> {
+        let mut clients = Vec::new();
+        let mut warnings = Vec::new();
+
+        for peer in &interface.peers {
+            match peer.to_interface(&interface, options) {
+                Ok(client) => clients.push(client),
+                Err(WireguardError::NoPrivateKeyProvided) => {
+                    warnings.push(ConversionWarning::ManualClient {
+                        public_key: peer.public_key(),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self {
+            interface,
+            clients,
+            warnings,
+        })
+    }
+}
+
+impl AmneziaSettings {
+    /// Get names of settings, which make AmneziaWG incompatible with vanilla WireGuard: non-zero
+    /// `S1..S4` and `H1..H4`, that differ from vanilla message types.
+    pub fn incompatible_settings(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let paddings = [
+            ("S1", self.s1),
+            ("S2", self.s2),
+            ("S3", self.s3),
+            ("S4", self.s4),
+        ]
+        .into_iter()
+        .filter(|(_, padding)| *padding != 0)
+        .map(|(name, _)| name);
+
+        let headers = self
+            .headers()
+            .into_iter()
+            .zip(1..)
+            .filter(|((_, header), message_type)| *header != MagicHeader::from(*message_type))
+            .map(|((name, _), _)| name);
+
+        paddings.chain(headers)
+    }
+
+    /// Check, if vanilla WireGuard peers can talk to AmneziaWG peers with these settings.
+    #[must_use]
+    pub fn is_vanilla_compatible(&self) -> bool {
+        self.incompatible_settings().next().is_none()
+    }
+}
+
+impl Interface {
+    /// Strip AmneziaWG obfuscation from interface and generate vanilla client configs.
+    ///
+    /// Warns with [`ConversionWarning::IncompatibleSettings`], if interface's settings changed
+    /// packet format: AmneziaWG side has to keep compatible listener for converted clients.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::NoAssignedIP`] -- client config can't be generated for some peer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .amnezia_settings(AmneziaSettings::random())
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    ///
+    /// let conversion = server.to_vanilla(ToInterfaceOptions::new())?;
+    ///
+    /// assert_eq!(conversion.interface.amnezia_settings, None);
+    /// assert_eq!(conversion.clients[0].amnezia_settings, None);
+    /// assert!(matches!(
+    ///     conversion.warnings[0],
+    ///     ConversionWarning::IncompatibleSettings { .. }
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_vanilla(&self, options: ToInterfaceOptions) -> WireguardResult<Conversion> {
+        let mut interface = self.clone();
+        let settings = interface.amnezia_settings.take();
+
+        let mut conversion = Conversion::new(interface, options)?;
+        if let Some(settings) = settings {
+            warn_incompatible(&mut conversion, &settings);
+        }
+
+        Ok(conversion)
+    }
+
+    /// Upgrade interface to AmneziaWG with settings, generated by `builder`, and generate client
+    /// configs with same settings.
+    ///
+    /// Existing [`Interface::amnezia_settings`] are replaced. Warns with
+    /// [`ConversionWarning::IncompatibleSettings`], if generated settings change packet format:
+    /// clients, that can't be upgraded, need separate vanilla listener.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- `builder` can't generate settings.
+    /// - [`WireguardError::NoAssignedIP`] -- client config can't be generated for some peer.
+    pub fn to_amneziawg(
+        &self,
+        builder: &AmneziaSettingsBuilder,
+        options: ToInterfaceOptions,
+    ) -> WireguardResult<Conversion> {
+        self.to_amneziawg_with_rng(builder, options, &mut rand::rng())
+    }
+
+    /// Same as [`Interface::to_amneziawg()`], but uses provided `rng` for generating settings.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- `builder` can't generate settings.
+    /// - [`WireguardError::NoAssignedIP`] -- client config can't be generated for some peer.
+    pub fn to_amneziawg_with_rng<R: Rng + ?Sized>(
+        &self,
+        builder: &AmneziaSettingsBuilder,
+        options: ToInterfaceOptions,
+        rng: &mut R,
+    ) -> WireguardResult<Conversion> {
+        let settings = builder.build_with_rng(rng)?;
+
+        let mut interface = self.clone();
+        interface.amnezia_settings = Some(settings.clone());
+
+        let mut conversion = Conversion::new(interface, options)?;
+        warn_incompatible(&mut conversion, &settings);
+
+        Ok(conversion)
+    }
+}
+
+fn warn_incompatible(conversion: &mut Conversion, settings: &AmneziaSettings) {
+    let settings: Vec<String> = settings
+        .incompatible_settings()
+        .map(str::to_string)
+        .collect();
+
+    if !settings.is_empty() {
+        conversion
+            .warnings
+            .insert(0, ConversionWarning::IncompatibleSettings { settings });
+    }
+}
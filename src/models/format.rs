@@ -0,0 +1,474 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+
+use zeroize::Zeroizing;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::writer::{write_interface, write_peer_section, NewlineTrimmer};
+
+/// Key of `[Interface]` section. Used for [`FormatOptions::interface_key_order()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum InterfaceKey {
+    /// `Address = ...`
+    Address,
+    /// `ListenPort = ...`
+    ListenPort,
+    /// `PrivateKey = ...`
+    PrivateKey,
+    /// `DNS = ...`
+    Dns,
+    /// `Table = ...`
+    Table,
+    /// `MTU = ...`
+    Mtu,
+}
+
+impl InterfaceKey {
+    /// Default order of keys.
+    pub const DEFAULT_ORDER: [InterfaceKey; 6] = [
+        InterfaceKey::Address,
+        InterfaceKey::ListenPort,
+        InterfaceKey::PrivateKey,
+        InterfaceKey::Dns,
+        InterfaceKey::Table,
+        InterfaceKey::Mtu,
+    ];
+}
+
+/// Key of `[Peer]` section. Used for [`FormatOptions::peer_key_order()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum PeerKey {
+    /// `Endpoint = ...`
+    Endpoint,
+    /// `AllowedIPs = ...`
+    AllowedIPs,
+    /// `PublicKey = ...`
+    PublicKey,
+    /// `PresharedKey = ...`
+    PresharedKey,
+    /// `PersistentKeepalive = ...`
+    PersistentKeepalive,
+}
+
+impl PeerKey {
+    /// Default order of keys.
+    pub const DEFAULT_ORDER: [PeerKey; 5] = [
+        PeerKey::Endpoint,
+        PeerKey::AllowedIPs,
+        PeerKey::PublicKey,
+        PeerKey::PresharedKey,
+        PeerKey::PersistentKeepalive,
+    ];
+}
+
+/// Options for formatting configs.
+///
+/// Default options produce exactly the same output as [`Interface`]'s and [`Peer`]'s
+/// [`fmt::Display`].
+///
+/// Use with [`Interface::display_with()`], [`Peer::display_with()`] or
+/// [`ConfigWriter::with_options()`].
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// # use wireguard_conf::as_ipnet;
+/// let interface = InterfaceBuilder::new()
+///     .address([as_ipnet!("10.0.0.1/32"), as_ipnet!("fd00::1/128")])
+///     .build();
+///
+/// let options = FormatOptions::new()
+///     .list_separator(", ")
+///     .interface_key_order([InterfaceKey::PrivateKey, InterfaceKey::Address])
+///     .strip_host_prefix(false)
+///     .trailing_newline(false);
+///
+/// assert_eq!(
+///     interface.display_with(&options).to_string(),
+///     format!(
+///         "[Interface]\nPrivateKey = {}\nAddress = 10.0.0.1/32, fd00::1/128",
+///         interface.private_key
+///     )
+/// );
+/// ```
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FormatOptions {
+    pub(crate) list_separator: Cow<'static, str>,
+    pub(crate) key_value_separator: Cow<'static, str>,
+    pub(crate) interface_key_order: Vec<InterfaceKey>,
+    pub(crate) peer_key_order: Vec<PeerKey>,
+    pub(crate) header_comment: Option<Cow<'static, str>>,
+    pub(crate) name_comment: bool,
+    pub(crate) strip_host_prefix: bool,
+    pub(crate) section_spacing: bool,
+    pub(crate) blank_line_after_peer: bool,
+    pub(crate) trailing_newline: bool,
+    pub(crate) redact_secrets: bool,
+    pub(crate) private_key_source: Option<KeySource>,
+    pub(crate) preshared_key_sources: Vec<(PublicKey, KeySource)>,
+}
+
+impl FormatOptions {
+    /// Create new [`FormatOptions`] with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            list_separator: Cow::Borrowed(","),
+            key_value_separator: Cow::Borrowed(" = "),
+            interface_key_order: Vec::new(),
+            peer_key_order: Vec::new(),
+            header_comment: None,
+            name_comment: true,
+            strip_host_prefix: true,
+            section_spacing: true,
+            blank_line_after_peer: true,
+            trailing_newline: true,
+            redact_secrets: false,
+            private_key_source: None,
+            preshared_key_sources: Vec::new(),
+        }
+    }
+
+    /// Create new [`FormatOptions`] for logging: same as default, but with
+    /// [`FormatOptions::redact_secrets()`] enabled.
+    #[must_use]
+    pub const fn redacted() -> Self {
+        let mut options = Self::new();
+        options.redact_secrets = true;
+        options
+    }
+
+    /// Sets separator of list values (`Address`, `DNS`, `AllowedIPs`).
+    ///
+    /// Default: `","`
+    #[must_use]
+    pub fn list_separator(mut self, value: impl Into<Cow<'static, str>>) -> Self {
+        self.list_separator = value.into();
+        self
+    }
+
+    /// Sets separator between key and value.
+    ///
+    /// Default: `" = "`
+    #[must_use]
+    pub fn key_value_separator(mut self, value: impl Into<Cow<'static, str>>) -> Self {
+        self.key_value_separator = value.into();
+        self
+    }
+
+    /// Sets order of keys in `[Interface]` section.
+    ///
+    /// Keys, that aren't listed, are written afterwards in default order
+    /// ([`InterfaceKey::DEFAULT_ORDER`]). Repeated keys are written only once.
+    #[must_use]
+    pub fn interface_key_order(mut self, value: impl Into<Vec<InterfaceKey>>) -> Self {
+        self.interface_key_order = value.into();
+        self
+    }
+
+    /// Sets order of keys in `[Peer]` sections.
+    ///
+    /// Keys, that aren't listed, are written afterwards in default order
+    /// ([`PeerKey::DEFAULT_ORDER`]). Repeated keys are written only once.
+    #[must_use]
+    pub fn peer_key_order(mut self, value: impl Into<Vec<PeerKey>>) -> Self {
+        self.peer_key_order = value.into();
+        self
+    }
+
+    /// Sets comment, that will be written at the top of the config. Every line is prefixed
+    /// with `# `.
+    ///
+    /// Default: no comment.
+    #[must_use]
+    pub fn header_comment(mut self, value: impl Into<Cow<'static, str>>) -> Self {
+        self.header_comment = Some(value.into());
+        self
+    }
+
+    /// Sets whether to write `# Name = <endpoint>` comment in `[Interface]` section.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn name_comment(mut self, value: bool) -> Self {
+        self.name_comment = value;
+        self
+    }
+
+    /// Sets whether to write `/32` and `/128` addresses as regular ips
+    /// (f.e. `1.2.3.4/32` -> `1.2.3.4`) in `Address = ...`.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn strip_host_prefix(mut self, value: bool) -> Self {
+        self.strip_host_prefix = value;
+        self
+    }
+
+    /// Sets whether to separate scripts, AmneziaWG values and `[Peer]` sections by blank lines.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn section_spacing(mut self, value: bool) -> Self {
+        self.section_spacing = value;
+        self
+    }
+
+    /// Sets whether to write blank line after every `[Peer]` section.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn blank_line_after_peer(mut self, value: bool) -> Self {
+        self.blank_line_after_peer = value;
+        self
+    }
+
+    /// Sets whether output should end with line break(s). If `false`, trailing line breaks are
+    /// trimmed.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn trailing_newline(mut self, value: bool) -> Self {
+        self.trailing_newline = value;
+        self
+    }
+
+    /// Sets whether to write `[REDACTED]` instead of `PrivateKey` and `PresharedKey`.
+    ///
+    /// Useful for logging configs. Output with redacted secrets isn't a valid config.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn redact_secrets(mut self, value: bool) -> Self {
+        self.redact_secrets = value;
+        self
+    }
+
+    /// Sets source of interface's private key. Instead of embedding `PrivateKey = ...`, key
+    /// is set by `PostUp = wg set %i private-key <path>` (see [`KeySource::shell_path()`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// let interface = InterfaceBuilder::new().build();
+    ///
+    /// let options = FormatOptions::new()
+    ///     .private_key_source(KeySource::File("/etc/wireguard/%i.key".into()));
+    /// let config = interface.display_with(&options).to_string();
+    ///
+    /// assert!(!config.contains("PrivateKey"));
+    /// assert!(config.contains("PostUp = wg set %i private-key /etc/wireguard/%i.key\n"));
+    /// ```
+    #[must_use]
+    pub fn private_key_source(mut self, source: KeySource) -> Self {
+        self.private_key_source = Some(source);
+        self
+    }
+
+    /// Sets source of preshared key for peer with given public key. Instead of embedding
+    /// `PresharedKey = ...`, key is set by
+    /// `PostUp = wg set %i peer <public key> preshared-key <path>` in `[Interface]` section.
+    #[must_use]
+    pub fn preshared_key_source(mut self, public_key: PublicKey, source: KeySource) -> Self {
+        self.preshared_key_sources
+            .retain(|(existing, _)| *existing != public_key);
+        self.preshared_key_sources.push((public_key, source));
+        self
+    }
+
+    /// Get source of peer's preshared key.
+    pub(crate) fn preshared_key_source_of(&self, public_key: &PublicKey) -> Option<&KeySource> {
+        self.preshared_key_sources
+            .iter()
+            .find(|(existing, _)| existing == public_key)
+            .map(|(_, source)| source)
+    }
+
+    /// Get order of `[Interface]` keys, including unlisted keys.
+    pub(crate) fn interface_keys(&self) -> impl Iterator<Item = InterfaceKey> + '_ {
+        key_order(&self.interface_key_order, InterfaceKey::DEFAULT_ORDER)
+    }
+
+    /// Get order of `[Peer]` keys, including unlisted keys.
+    pub(crate) fn peer_keys(&self) -> impl Iterator<Item = PeerKey> + '_ {
+        key_order(&self.peer_key_order, PeerKey::DEFAULT_ORDER)
+    }
+}
+
+/// Get listed keys (repeated ones only once), followed by unlisted keys in `default` order.
+fn key_order<K: Copy + PartialEq, const N: usize>(
+    order: &[K],
+    default: [K; N],
+) -> impl Iterator<Item = K> + '_ {
+    let listed = order
+        .iter()
+        .enumerate()
+        .filter(|&(i, key)| !order[..i].contains(key))
+        .map(|(_, key)| *key);
+
+    listed.chain(default.into_iter().filter(move |key| !order.contains(key)))
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Helper struct for formatting [`Interface`] with [`FormatOptions`].
+///
+/// Created by [`Interface::display_with()`].
+#[must_use]
+#[derive(Clone, Copy, Debug)]
+pub struct InterfaceDisplay<'a> {
+    interface: &'a Interface,
+    options: &'a FormatOptions,
+}
+
+impl fmt::Display for InterfaceDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.trailing_newline {
+            write_interface(f, self.interface, self.options)
+        } else {
+            let mut pending_newlines = 0;
+            let mut f = NewlineTrimmer::new(f, &mut pending_newlines);
+
+            write_interface(&mut f, self.interface, self.options)
+        }
+    }
+}
+
+/// Helper struct for formatting [`Peer`] with [`FormatOptions`].
+///
+/// Created by [`Peer::display_with()`].
+#[must_use]
+#[derive(Clone, Copy, Debug)]
+pub struct PeerDisplay<'a> {
+    peer: &'a Peer,
+    options: &'a FormatOptions,
+}
+
+impl fmt::Display for PeerDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.trailing_newline {
+            write_peer_section(f, self.peer, self.options)
+        } else {
+            let mut pending_newlines = 0;
+            let mut f = NewlineTrimmer::new(f, &mut pending_newlines);
+
+            write_peer_section(&mut f, self.peer, self.options)
+        }
+    }
+}
+
+/// Render `value` into [`Zeroizing<String>`].
+///
+/// Length is computed beforehand, so string is never reallocated and no copies of secrets are
+/// left in memory.
+fn to_zeroizing_string(value: &impl fmt::Display) -> Zeroizing<String> {
+    struct Counter(usize);
+
+    impl fmt::Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    write!(counter, "{value}").unwrap_or_else(|_| unreachable!());
+
+    let mut output = Zeroizing::new(String::with_capacity(counter.0));
+    write!(output, "{value}").unwrap_or_else(|_| unreachable!());
+    output
+}
+
+impl InterfaceDisplay<'_> {
+    /// Render into [`Zeroizing<String>`]. See [`Interface::to_zeroizing_string()`].
+    #[must_use]
+    pub fn to_zeroizing_string(&self) -> Zeroizing<String> {
+        to_zeroizing_string(self)
+    }
+}
+
+impl PeerDisplay<'_> {
+    /// Render into [`Zeroizing<String>`]. See [`Interface::to_zeroizing_string()`].
+    #[must_use]
+    pub fn to_zeroizing_string(&self) -> Zeroizing<String> {
+        to_zeroizing_string(self)
+    }
+}
+
+impl Interface {
+    /// Render config into [`Zeroizing<String>`], which is zeroized on drop.
+    ///
+    /// Prefer it over `.to_string()`, as config contains private and preshared keys.
+    #[must_use]
+    pub fn to_zeroizing_string(&self) -> Zeroizing<String> {
+        to_zeroizing_string(self)
+    }
+
+    /// Format interface for logs: same as [`fmt::Display`], but `PrivateKey` and `PresharedKey`
+    /// are replaced with `[REDACTED]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// let interface = InterfaceBuilder::new().build();
+    ///
+    /// assert!(interface.display_redacted().to_string().contains("PrivateKey = [REDACTED]"));
+    /// ```
+    pub fn display_redacted(&self) -> InterfaceDisplay<'_> {
+        const REDACTED: &FormatOptions = &FormatOptions::redacted();
+
+        self.display_with(REDACTED)
+    }
+
+    /// Format interface with custom [`FormatOptions`].
+    ///
+    /// Returned value implements [`fmt::Display`], so use it with `.to_string()`, `write!()`, etc.
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> InterfaceDisplay<'a> {
+        InterfaceDisplay {
+            interface: self,
+            options,
+        }
+    }
+}
+
+impl Peer {
+    /// Render `[Peer]` section into [`Zeroizing<String>`], which is zeroized on drop.
+    #[must_use]
+    pub fn to_zeroizing_string(&self) -> Zeroizing<String> {
+        to_zeroizing_string(self)
+    }
+
+    /// Format `[Peer]` section for logs: same as [`fmt::Display`], but `PresharedKey` is
+    /// replaced with `[REDACTED]`.
+    pub fn display_redacted(&self) -> PeerDisplay<'_> {
+        const REDACTED: &FormatOptions = &FormatOptions::redacted();
+
+        self.display_with(REDACTED)
+    }
+
+    /// Format `[Peer]` section with custom [`FormatOptions`].
+    ///
+    /// Returned value implements [`fmt::Display`], so use it with `.to_string()`, `write!()`, etc.
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> PeerDisplay<'a> {
+        PeerDisplay {
+            peer: self,
+            options,
+        }
+    }
+}
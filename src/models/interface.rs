@@ -1,7 +1,7 @@
 use derive_builder::Builder;
 use either::Either;
 use ipnet::IpNet;
-use itertools::Itertools as _;
+use rand::CryptoRng;
 
 use std::fmt;
 use std::net::Ipv4Addr;
@@ -13,6 +13,8 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::prelude::*;
 
+use super::writer::write_interface;
+
 /// Controls the routing table to which routes are added.
 #[derive(PartialEq, Eq, Clone, Debug, Default)]
 pub enum Table {
@@ -233,6 +235,8 @@ impl Interface {
             allowed_ips: self.address.clone(),
             key: Either::Left(self.private_key.clone()),
             preshared_key: None,
+            #[cfg(feature = "rosenpass")]
+            external_preshared_key: false,
             persistent_keepalive: 0,
         }
     }
@@ -353,81 +357,35 @@ impl InterfaceBuilder {
     pub fn build(&self) -> Interface {
         self.fallible_build().unwrap_or_else(|_| unreachable!())
     }
-}
 
-impl fmt::Display for Interface {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "[Interface]")?;
-        if let Some(endpoint) = &self.endpoint {
-            writeln!(f, "# Name = {endpoint}")?;
-        }
-        writeln!(
-            f,
-            "Address = {}",
-            self.address
-                .iter()
-                .map(ToString::to_string)
-                .map(|addr| {
-                    if addr.ends_with("/32") {
-                        addr.trim_end_matches("/32").to_owned()
-                    } else if addr.ends_with("/128") {
-                        addr.trim_end_matches("/128").to_owned()
-                    } else {
-                        addr
-                    }
-                })
-                .join(",")
-        )?;
-        if let Some(listen_port) = self.listen_port {
-            writeln!(f, "ListenPort = {listen_port}")?;
-        }
-        writeln!(f, "PrivateKey = {}", self.private_key)?;
-        if !self.dns.is_empty() {
-            writeln!(f, "DNS = {}", self.dns.join(","))?;
-        }
-        if let Some(table) = &self.table {
-            writeln!(f, "Table = {table}")?;
-        }
-        if let Some(mtu) = &self.mtu {
-            writeln!(f, "MTU = {mtu}")?;
-        }
-
-        if !self.pre_up.is_empty() {
-            writeln!(f)?;
-            for snippet in &self.pre_up {
-                writeln!(f, "PreUp = {snippet}")?;
-            }
-        }
-        if !self.pre_down.is_empty() {
-            writeln!(f)?;
-            for snippet in &self.pre_down {
-                writeln!(f, "PreDown = {snippet}")?;
-            }
-        }
-        if !self.post_up.is_empty() {
-            writeln!(f)?;
-            for snippet in &self.post_up {
-                writeln!(f, "PostUp = {snippet}")?;
-            }
-        }
-        if !self.post_down.is_empty() {
-            writeln!(f)?;
-            for snippet in &self.post_down {
-                writeln!(f, "PostDown = {snippet}")?;
-            }
-        }
-
-        #[cfg(feature = "amneziawg")]
-        if let Some(amnezia_settings) = &self.amnezia_settings {
-            writeln!(f)?;
-            writeln!(f, "{amnezia_settings}")?;
+    /// Builds an `Interface`, generating default values (private key) using provided `rng`.
+    ///
+    /// Use seeded RNG (f.e. `ChaCha20Rng`) for reproducible configs in tests and demos.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let first = InterfaceBuilder::new().build_with_rng(&mut StdRng::seed_from_u64(42));
+    /// let second = InterfaceBuilder::new().build_with_rng(&mut StdRng::seed_from_u64(42));
+    ///
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn build_with_rng<R: CryptoRng + ?Sized>(&self, rng: &mut R) -> Interface {
+        if self.private_key.is_some() {
+            return self.build();
         }
 
-        for peer in &self.peers {
-            writeln!(f)?;
-            writeln!(f, "{peer}")?;
-        }
+        let mut builder = self.clone();
+        builder.private_key = Some(PrivateKey::random_with_rng(rng));
+        builder.build()
+    }
+}
 
-        fmt::Result::Ok(())
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_interface(f, self, &FormatOptions::new())
     }
 }
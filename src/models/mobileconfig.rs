@@ -0,0 +1,378 @@
+use plist::{Dictionary, Value};
+use rand::Rng;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// Apple platform, which WireGuard app profile is made for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum ApplePlatform {
+    /// iOS and iPadOS (`com.wireguard.ios`).
+    #[default]
+    Ios,
+
+    /// macOS (`com.wireguard.macos`).
+    MacOs,
+}
+
+impl ApplePlatform {
+    /// Bundle id of WireGuard app, used as `VPNSubType`.
+    fn bundle_id(self) -> &'static str {
+        match self {
+            ApplePlatform::Ios => "com.wireguard.ios",
+            ApplePlatform::MacOs => "com.wireguard.macos",
+        }
+    }
+}
+
+/// What to do, when [`OnDemandRule`] matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum OnDemandAction {
+    /// Connect tunnel.
+    Connect,
+
+    /// Disconnect tunnel and don't reconnect.
+    Disconnect,
+
+    /// Leave tunnel as is.
+    Ignore,
+}
+
+/// Network interface type, matched by [`OnDemandRule`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum NetworkInterfaceType {
+    /// Wi-Fi.
+    WiFi,
+
+    /// Cellular data.
+    Cellular,
+
+    /// Wired Ethernet (macOS only).
+    Ethernet,
+}
+
+/// On-demand rule of VPN payload. Rules are evaluated in order, first matching one is applied.
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// // connect everywhere, except home Wi-Fi
+/// let rules = [
+///     OnDemandRule::exclude_ssids(["Home"]),
+///     OnDemandRule::connect_on(NetworkInterfaceType::WiFi),
+///     OnDemandRule::connect_on(NetworkInterfaceType::Cellular),
+/// ];
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct OnDemandRule {
+    /// Action to apply (`Action`).
+    pub action: OnDemandAction,
+
+    /// Matched interface type (`InterfaceTypeMatch`). `None` matches every interface.
+    pub interface_type: Option<NetworkInterfaceType>,
+
+    /// Matched Wi-Fi SSIDs (`SSIDMatch`). Empty list matches every network.
+    pub ssids: Vec<String>,
+}
+
+impl OnDemandRule {
+    /// Create rule, which applies `action` on every network.
+    #[must_use]
+    pub fn new(action: OnDemandAction) -> Self {
+        Self {
+            action,
+            interface_type: None,
+            ssids: Vec::new(),
+        }
+    }
+
+    /// Create rule, which connects on `interface_type`.
+    #[must_use]
+    pub fn connect_on(interface_type: NetworkInterfaceType) -> Self {
+        Self {
+            interface_type: Some(interface_type),
+            ..Self::new(OnDemandAction::Connect)
+        }
+    }
+
+    /// Create rule, which disconnects on `interface_type`.
+    #[must_use]
+    pub fn disconnect_on(interface_type: NetworkInterfaceType) -> Self {
+        Self {
+            interface_type: Some(interface_type),
+            ..Self::new(OnDemandAction::Disconnect)
+        }
+    }
+
+    /// Create rule, which disconnects on Wi-Fi networks with `ssids` (f.e. trusted home network).
+    #[must_use]
+    pub fn exclude_ssids<S: Into<String>>(ssids: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            ssids: ssids.into_iter().map(Into::into).collect(),
+            ..Self::disconnect_on(NetworkInterfaceType::WiFi)
+        }
+    }
+
+    fn to_plist(&self) -> Value {
+        let mut rule = Dictionary::new();
+
+        let action = match self.action {
+            OnDemandAction::Connect => "Connect",
+            OnDemandAction::Disconnect => "Disconnect",
+            OnDemandAction::Ignore => "Ignore",
+        };
+        rule.insert("Action".to_string(), action.into());
+
+        if let Some(interface_type) = self.interface_type {
+            let interface_type = match interface_type {
+                NetworkInterfaceType::WiFi => "WiFi",
+                NetworkInterfaceType::Cellular => "Cellular",
+                NetworkInterfaceType::Ethernet => "Ethernet",
+            };
+            rule.insert("InterfaceTypeMatch".to_string(), interface_type.into());
+        }
+        if !self.ssids.is_empty() {
+            let ssids = self.ssids.iter().cloned().map(Value::from).collect();
+            rule.insert("SSIDMatch".to_string(), Value::Array(ssids));
+        }
+
+        rule.into()
+    }
+}
+
+/// Options for [`Interface::to_mobileconfig()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MobileConfigOptions {
+    /// Reverse-DNS identifier of profile.
+    identifier: String,
+
+    /// Name of profile and tunnel.
+    display_name: String,
+
+    /// Platform of WireGuard app.
+    platform: ApplePlatform,
+
+    /// On-demand rules.
+    on_demand_rules: Vec<OnDemandRule>,
+}
+
+impl MobileConfigOptions {
+    /// Create new [`MobileConfigOptions`] with profile's `identifier` (`PayloadIdentifier`, f.e.
+    /// `com.example.vpn`). Installing profile with same identifier replaces old one.
+    #[must_use]
+    pub fn new(identifier: impl Into<String>) -> Self {
+        Self {
+            identifier: identifier.into(),
+            display_name: "WireGuard".to_string(),
+            platform: ApplePlatform::default(),
+            on_demand_rules: Vec::new(),
+        }
+    }
+
+    /// Sets name of profile and tunnel, shown in Settings and WireGuard app. Defaults to
+    /// `WireGuard`.
+    #[must_use]
+    pub fn display_name(mut self, value: impl Into<String>) -> Self {
+        self.display_name = value.into();
+        self
+    }
+
+    /// Sets platform of WireGuard app.
+    #[must_use]
+    pub fn platform(mut self, value: ApplePlatform) -> Self {
+        self.platform = value;
+        self
+    }
+
+    /// Sets on-demand rules. If there are any, on-demand is enabled.
+    #[must_use]
+    pub fn on_demand_rules(mut self, value: impl IntoIterator<Item = OnDemandRule>) -> Self {
+        self.on_demand_rules = value.into_iter().collect();
+        self
+    }
+}
+
+impl Interface {
+    /// Wrap client config into Apple configuration profile (`.mobileconfig`) with WireGuard VPN
+    /// payload, which can be deployed by MDM.
+    ///
+    /// Config is embedded as wg-quick text (`WgQuickConfig`); first peer's endpoint is used as
+    /// `RemoteAddress`.
+    ///
+    /// Profile isn't signed. `PayloadUUID`s are random, so every call generates new ones (see
+    /// [`Interface::to_mobileconfig_with_rng()`]).
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidMobileConfig`] -- interface has no peer with endpoint, or has
+    ///   keys, which official WireGuard apps don't support: `Table`, `PreUp`, `PreDown`,
+    ///   `PostUp`, `PostDown` and AmneziaWG settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .endpoint("vpn.example.com")
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    /// let client = server.peers[0].to_interface(&server, ToInterfaceOptions::new())?;
+    ///
+    /// let options = MobileConfigOptions::new("com.example.vpn")
+    ///     .display_name("Example VPN")
+    ///     .on_demand_rules([
+    ///         OnDemandRule::exclude_ssids(["Office"]),
+    ///         OnDemandRule::new(OnDemandAction::Connect),
+    ///     ]);
+    /// let profile = client.to_mobileconfig(&options)?;
+    ///
+    /// assert!(profile.contains("<string>com.wireguard.ios</string>"));
+    /// assert!(profile.contains("<string>vpn.example.com:51820</string>"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_mobileconfig(&self, options: &MobileConfigOptions) -> WireguardResult<String> {
+        self.to_mobileconfig_with_rng(options, &mut rand::rng())
+    }
+
+    /// Same as [`Interface::to_mobileconfig()`], but generates `PayloadUUID`s with given `rng`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Interface::to_mobileconfig()`].
+    pub fn to_mobileconfig_with_rng<R: Rng + ?Sized>(
+        &self,
+        options: &MobileConfigOptions,
+        rng: &mut R,
+    ) -> WireguardResult<String> {
+        #[cfg(feature = "amneziawg")]
+        if self.amnezia_settings.is_some() {
+            return Err(WireguardError::InvalidMobileConfig(
+                "WireGuard apps don't support AmneziaWG settings".into(),
+            ));
+        }
+        let unsupported = [
+            ("Table", self.table.is_some()),
+            ("PreUp", !self.pre_up.is_empty()),
+            ("PreDown", !self.pre_down.is_empty()),
+            ("PostUp", !self.post_up.is_empty()),
+            ("PostDown", !self.post_down.is_empty()),
+        ]
+        .into_iter()
+        .filter_map(|(key, is_set)| is_set.then_some(key))
+        .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            return Err(WireguardError::InvalidMobileConfig(format!(
+                "WireGuard apps don't support {}",
+                unsupported.join(", ")
+            )));
+        }
+
+        let remote_address = self
+            .peers
+            .iter()
+            .find_map(|peer| peer.endpoint.as_deref())
+            .ok_or_else(|| {
+                WireguardError::InvalidMobileConfig("interface has no peer with endpoint".into())
+            })?;
+
+        let mut vpn = Dictionary::new();
+        vpn.insert("RemoteAddress".to_string(), remote_address.into());
+        vpn.insert("AuthenticationMethod".to_string(), "Password".into());
+        if !options.on_demand_rules.is_empty() {
+            let rules = options
+                .on_demand_rules
+                .iter()
+                .map(OnDemandRule::to_plist)
+                .collect();
+            vpn.insert("OnDemandEnabled".to_string(), 1.into());
+            vpn.insert("OnDemandRules".to_string(), Value::Array(rules));
+        }
+
+        let mut vendor_config = Dictionary::new();
+        vendor_config.insert("WgQuickConfig".to_string(), self.to_string().into());
+
+        let mut payload = new_payload(
+            "com.apple.vpn.managed",
+            &format!("{}.wireguard", options.identifier),
+            &options.display_name,
+            rng,
+        );
+        payload.insert(
+            "UserDefinedName".to_string(),
+            options.display_name.clone().into(),
+        );
+        payload.insert("VPNType".to_string(), "VPN".into());
+        payload.insert(
+            "VPNSubType".to_string(),
+            options.platform.bundle_id().into(),
+        );
+        payload.insert("VendorConfig".to_string(), vendor_config.into());
+        payload.insert("VPN".to_string(), vpn.into());
+
+        let mut profile = new_payload(
+            "Configuration",
+            &options.identifier,
+            &options.display_name,
+            rng,
+        );
+        profile.insert(
+            "PayloadContent".to_string(),
+            Value::Array(vec![payload.into()]),
+        );
+
+        let mut xml = Vec::new();
+        Value::from(profile)
+            .to_writer_xml(&mut xml)
+            .unwrap_or_else(|_| unreachable!());
+
+        Ok(String::from_utf8(xml).unwrap_or_else(|_| unreachable!()))
+    }
+}
+
+/// Create dictionary with common payload keys.
+fn new_payload<R: Rng + ?Sized>(
+    payload_type: &str,
+    identifier: &str,
+    display_name: &str,
+    rng: &mut R,
+) -> Dictionary {
+    let mut payload = Dictionary::new();
+    payload.insert("PayloadType".to_string(), payload_type.into());
+    payload.insert("PayloadVersion".to_string(), 1.into());
+    payload.insert("PayloadIdentifier".to_string(), identifier.into());
+    payload.insert("PayloadUUID".to_string(), uuid(rng).into());
+    payload.insert("PayloadDisplayName".to_string(), display_name.into());
+
+    payload
+}
+
+/// Generate random (version 4) UUID.
+fn uuid<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut bytes: [u8; 16] = rng.random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = format!("{:032X}", u128::from_be_bytes(bytes));
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
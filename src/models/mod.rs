@@ -1,5 +1,36 @@
+#[cfg(feature = "amneziavpn")]
+mod amneziavpn;
+#[cfg(feature = "clash")]
+mod clash;
+#[cfg(feature = "amneziawg")]
+mod conversion;
+mod format;
 mod interface;
+#[cfg(feature = "mobileconfig")]
+mod mobileconfig;
 mod peer;
+mod public;
+#[cfg(feature = "rosenpass")]
+mod rosenpass;
+mod rotation;
+#[cfg(feature = "sharelink")]
+mod share_link;
+#[cfg(feature = "singbox")]
+mod sing_box;
+mod writer;
 
+#[cfg(feature = "amneziawg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+pub use conversion::*;
+pub use format::*;
 pub use interface::*;
+#[cfg(feature = "mobileconfig")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mobileconfig")))]
+pub use mobileconfig::*;
 pub use peer::*;
+pub use public::*;
+#[cfg(feature = "rosenpass")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rosenpass")))]
+pub use rosenpass::*;
+pub use rotation::*;
+pub use writer::*;
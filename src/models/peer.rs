@@ -1,6 +1,7 @@
 use derive_builder::Builder;
 use either::Either;
 use ipnet::IpNet;
+use rand::CryptoRng;
 
 use std::fmt;
 use std::net::{IpAddr, Ipv6Addr};
@@ -12,6 +13,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
 
+use super::writer::write_peer_section;
+
 /// Options for [`Peer::to_interface()`].
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -91,6 +94,16 @@ pub struct Peer {
     /// Peer's preshared-key.
     #[builder(setter(strip_option), default)]
     pub preshared_key: Option<PresharedKey>,
+
+    /// Whether preshared key is managed externally (f.e. rotated by Rosenpass).
+    ///
+    /// If `true`, `PresharedKey = ...` is omitted in config, even if [`Peer::preshared_key`] is
+    /// set.
+    #[cfg(feature = "rosenpass")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rosenpass")))]
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub external_preshared_key: bool,
 }
 
 impl Peer {
@@ -148,9 +161,106 @@ impl PeerBuilder {
     pub fn build(&self) -> Peer {
         self.fallible_build().unwrap_or_else(|_| unreachable!())
     }
+
+    /// Builds a `Peer`, generating default values (private key) using provided `rng`.
+    ///
+    /// Use seeded RNG (f.e. `ChaCha20Rng`) for reproducible configs in tests and demos.
+    pub fn build_with_rng<R: CryptoRng + ?Sized>(&self, rng: &mut R) -> Peer {
+        if self.key.is_some() {
+            return self.build();
+        }
+
+        let mut builder = self.clone();
+        builder.key = Some(Either::Left(PrivateKey::random_with_rng(rng)));
+        builder.build()
+    }
 }
 
 impl Peer {
+    /// Get peer's public key (derived from private key, if peer has one).
+    #[must_use]
+    pub fn public_key(&self) -> PublicKey {
+        match &self.key {
+            Either::Left(private_key) => private_key.public_key(),
+            Either::Right(public_key) => public_key.clone(),
+        }
+    }
+
+    /// Get preshared key, which is written into exported configs: `None`, if it's managed
+    /// externally.
+    pub(crate) fn exported_preshared_key(&self) -> Option<&PresharedKey> {
+        #[cfg(feature = "rosenpass")]
+        if self.external_preshared_key {
+            return None;
+        }
+
+        self.preshared_key.as_ref()
+    }
+
+    /// Split [`Peer::endpoint`] into host and port. IPv6 hosts are returned without brackets.
+    ///
+    /// Returns `None`, if peer has no endpoint or endpoint has no port.
+    ///
+    /// ```rust
+    /// # use wireguard_conf::prelude::*;
+    /// let peer = PeerBuilder::new().endpoint("[2001:db8::1]:51820").build();
+    ///
+    /// assert_eq!(peer.split_endpoint(), Some(("2001:db8::1", 51820)));
+    /// ```
+    #[must_use]
+    pub fn split_endpoint(&self) -> Option<(&str, u16)> {
+        let (host, port) = self.endpoint.as_deref()?.rsplit_once(':')?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+
+        Some((host, port.parse().ok()?))
+    }
+
+    /// Join `host` and `port` into endpoint. IPv6 hosts are put into brackets.
+    #[cfg(any(
+        feature = "amneziavpn",
+        feature = "clash",
+        feature = "sharelink",
+        feature = "singbox"
+    ))]
+    pub(crate) fn join_endpoint(host: &str, port: u16) -> String {
+        if host.contains(':') {
+            format!("[{host}]:{port}")
+        } else {
+            format!("{host}:{port}")
+        }
+    }
+
+    /// Allowed IPs, routing all traffic: `0.0.0.0/0` and `::/0`.
+    #[cfg(any(
+        feature = "amneziavpn",
+        feature = "clash",
+        feature = "sharelink",
+        feature = "singbox"
+    ))]
+    pub(crate) fn default_allowed_ips() -> Vec<IpNet> {
+        vec![
+            IpNet::V4(ipnet::Ipv4Net::default()),
+            IpNet::V6(ipnet::Ipv6Net::default()),
+        ]
+    }
+
+    /// Parse network or plain IP (as `/32` or `/128` network).
+    #[cfg(any(
+        feature = "amneziavpn",
+        feature = "clash",
+        feature = "sharelink",
+        feature = "singbox"
+    ))]
+    pub(crate) fn parse_network(value: &str) -> Option<IpNet> {
+        value
+            .parse()
+            .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+            .ok()
+    }
+
     /// Generate [`Interface`] from client's [`Peer`] and server's [`Interface`].
     ///
     /// `options`
@@ -243,31 +353,6 @@ impl Peer {
 /// and then `.to_string()`
 impl fmt::Display for Peer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "[Peer]")?;
-        if let Some(endpoint) = self.endpoint.clone() {
-            writeln!(f, "Endpoint = {endpoint}")?;
-        }
-        writeln!(
-            f,
-            "AllowedIPs = {}",
-            self.allowed_ips
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<String>>()
-                .join(",")
-        )?;
-        writeln!(
-            f,
-            "PublicKey = {}",
-            self.key.clone().right_or_else(|key| PublicKey::from(&key))
-        )?;
-        if let Some(preshared_key) = &self.preshared_key {
-            writeln!(f, "PresharedKey = {preshared_key}")?;
-        }
-        if self.persistent_keepalive != 0 {
-            writeln!(f, "PersistentKeepalive = {}", self.persistent_keepalive)?;
-        }
-
-        Ok(())
+        write_peer_section(f, self, &FormatOptions::new())
     }
 }
@@ -0,0 +1,248 @@
+use either::Either;
+use ipnet::IpNet;
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::writer::{
+    write_interface_fields, write_peer_entry, write_peer_fields, InterfaceFields, PeerFields,
+};
+
+/// Options for [`Interface::to_public()`] and [`Peer::to_public()`].
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ToPublicOptions {
+    /// Option, for replacing preshared keys with their hashes, instead of omitting them.
+    hash_preshared_keys: bool,
+}
+
+impl ToPublicOptions {
+    /// Create new [`ToPublicOptions`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to replace preshared keys with their hashes (see [`PresharedKey::hash()`]).
+    ///
+    /// By default, preshared keys are omitted.
+    #[must_use]
+    pub fn hash_preshared_keys(mut self, value: bool) -> Self {
+        self.hash_preshared_keys = value;
+        self
+    }
+}
+
+/// Public-only view of [`Interface`], that is safe to publish (f.e. share with auditors or commit
+/// to git).
+///
+/// - Interface's [`PrivateKey`] is replaced with its [`PublicKey`].
+/// - Peers are converted to [`PublicPeer`]s.
+///
+/// Its [`fmt::Display`] output isn't a valid Wireguard config (it has `PublicKey` in
+/// `[Interface]` section), so it can't be accidentally used as one.
+///
+/// Use [`Interface::to_public()`] to create it.
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// # use wireguard_conf::as_ipnet;
+/// let interface = InterfaceBuilder::new()
+///     .address([as_ipnet!("10.0.0.1/24")])
+///     .peers([PeerBuilder::new()
+///         .allowed_ips([as_ipnet!("10.0.0.2/32")])
+///         .preshared_key(PresharedKey::random())
+///         .build()])
+///     .build();
+///
+/// let public = interface.to_public(ToPublicOptions::new().hash_preshared_keys(true));
+/// let output = public.to_string();
+///
+/// assert!(output.contains(&format!("PublicKey = {}", interface.private_key.public_key())));
+/// assert!(!output.contains(&interface.private_key.to_string()));
+/// assert!(output.contains("PresharedKeyHash = "));
+/// ```
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PublicInterface {
+    /// Interface's address.
+    pub address: Vec<IpNet>,
+
+    /// Port to listen for incoming VPN connections.
+    pub listen_port: Option<u16>,
+
+    /// Node's public key.
+    pub public_key: PublicKey,
+
+    /// The DNS servers to announce to VPN clients via DHCP.
+    pub dns: Vec<String>,
+
+    /// Endpoint.
+    pub endpoint: Option<String>,
+
+    /// Routing table to use for the WireGuard routes.
+    pub table: Option<Table>,
+
+    /// Maximum Transmission Unit.
+    pub mtu: Option<usize>,
+
+    /// AmneziaWG obfuscation values.
+    #[cfg(feature = "amneziawg")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+    pub amnezia_settings: Option<AmneziaSettings>,
+
+    /// Commands, that will be executed before the interface is brought up
+    pub pre_up: Vec<String>,
+
+    /// Commands, that will be executed before the interface is brought down
+    pub pre_down: Vec<String>,
+
+    /// Commands, that will be executed after the interface is brought up
+    pub post_up: Vec<String>,
+
+    /// Commands, that will be executed after the interface is brought down
+    pub post_down: Vec<String>,
+
+    /// Peers.
+    pub peers: Vec<PublicPeer>,
+}
+
+/// Public-only view of [`Peer`].
+///
+/// - Peer's key is always [`PublicKey`].
+/// - Preshared key is either omitted, or replaced with [`PresharedKeyHash`].
+///
+/// Use [`Peer::to_public()`] to create it.
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PublicPeer {
+    /// Peer's endpoint.
+    pub endpoint: Option<String>,
+
+    /// Peer's allowed IPs.
+    pub allowed_ips: Vec<IpNet>,
+
+    /// Peer's persistent keepalive.
+    pub persistent_keepalive: u16,
+
+    /// Peer's public key.
+    pub public_key: PublicKey,
+
+    /// Hash of peer's preshared key.
+    ///
+    /// Set only if [`ToPublicOptions::hash_preshared_keys()`] is enabled.
+    pub preshared_key_hash: Option<PresharedKeyHash>,
+}
+
+impl Interface {
+    /// Get public-only view of interface. See [`PublicInterface`].
+    pub fn to_public(&self, options: ToPublicOptions) -> PublicInterface {
+        PublicInterface {
+            address: self.address.clone(),
+            listen_port: self.listen_port,
+            public_key: self.private_key.public_key(),
+            dns: self.dns.clone(),
+            endpoint: self.endpoint.clone(),
+            table: self.table.clone(),
+            mtu: self.mtu,
+
+            #[cfg(feature = "amneziawg")]
+            amnezia_settings: self.amnezia_settings.clone(),
+
+            pre_up: self.pre_up.clone(),
+            pre_down: self.pre_down.clone(),
+            post_up: self.post_up.clone(),
+            post_down: self.post_down.clone(),
+
+            peers: self
+                .peers
+                .iter()
+                .map(|peer| peer.to_public(options))
+                .collect(),
+        }
+    }
+}
+
+impl Peer {
+    /// Get public-only view of peer. See [`PublicPeer`].
+    pub fn to_public(&self, options: ToPublicOptions) -> PublicPeer {
+        PublicPeer {
+            endpoint: self.endpoint.clone(),
+            allowed_ips: self.allowed_ips.clone(),
+            persistent_keepalive: self.persistent_keepalive,
+            public_key: self.public_key(),
+            preshared_key_hash: if options.hash_preshared_keys {
+                self.exported_preshared_key().map(PresharedKey::hash)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl PublicInterface {
+    fn fields(&self) -> InterfaceFields<'_> {
+        InterfaceFields {
+            address: &self.address,
+            listen_port: self.listen_port,
+            key: Either::Right(&self.public_key),
+            dns: &self.dns,
+            endpoint: self.endpoint.as_deref(),
+            table: self.table.as_ref(),
+            mtu: self.mtu,
+            #[cfg(feature = "amneziawg")]
+            amnezia_settings: self.amnezia_settings.as_ref(),
+            pre_up: &self.pre_up,
+            pre_down: &self.pre_down,
+            post_up: &self.post_up,
+            post_down: &self.post_down,
+        }
+    }
+}
+
+impl PublicPeer {
+    fn fields(&self) -> PeerFields<'_> {
+        PeerFields {
+            endpoint: self.endpoint.as_deref(),
+            allowed_ips: &self.allowed_ips,
+            key: Either::Right(&self.public_key),
+            preshared_key: self.preshared_key_hash.as_ref().map(Either::Right),
+            persistent_keepalive: self.persistent_keepalive,
+        }
+    }
+}
+
+/// Implements [`fmt::Display`] for exporting public view.
+///
+/// # Note
+///
+/// Output isn't valid Wireguard config.
+impl fmt::Display for PublicInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = FormatOptions::new()
+            .header_comment("Public view of Wireguard config. Private keys are removed.");
+
+        write_interface_fields(f, &self.fields(), &options)?;
+        for peer in &self.peers {
+            write_peer_entry(f, &peer.fields(), &options)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Implements [`fmt::Display`] for exporting public view of `[Peer]` section.
+impl fmt::Display for PublicPeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_peer_fields(f, &self.fields(), &FormatOptions::new())
+    }
+}
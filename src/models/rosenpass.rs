@@ -0,0 +1,449 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// Default Rosenpass port.
+const DEFAULT_PORT: u16 = 9999;
+
+/// Verbosity of Rosenpass daemon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum RosenpassVerbosity {
+    /// Log only errors.
+    #[default]
+    Quiet,
+
+    /// Log key exchanges.
+    Verbose,
+}
+
+impl fmt::Display for RosenpassVerbosity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Quiet => write!(f, "Quiet"),
+            Self::Verbose => write!(f, "Verbose"),
+        }
+    }
+}
+
+/// Options for [`Interface::to_rosenpass()`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RosenpassOptions {
+    key_dir: PathBuf,
+    device: String,
+    port: u16,
+    verbosity: RosenpassVerbosity,
+    peer_names: Vec<(PublicKey, String)>,
+}
+
+impl RosenpassOptions {
+    /// Create new [`RosenpassOptions`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets directory with Rosenpass keys (see [`RosenpassKeyFiles`]).
+    ///
+    /// Default: `/etc/rosenpass`
+    #[must_use]
+    pub fn key_dir(mut self, value: impl Into<PathBuf>) -> Self {
+        self.key_dir = value.into();
+        self
+    }
+
+    /// Sets name of WireGuard device, that Rosenpass sets preshared keys for.
+    ///
+    /// Default: `wg0`
+    #[must_use]
+    pub fn device(mut self, value: impl Into<String>) -> Self {
+        self.device = value.into();
+        self
+    }
+
+    /// Sets UDP port of Rosenpass. It's used for listening (if interface has
+    /// [`Interface::listen_port`]) and for peers' endpoints.
+    ///
+    /// Default: `9999`
+    #[must_use]
+    pub fn port(mut self, value: u16) -> Self {
+        self.port = value;
+        self
+    }
+
+    /// Sets verbosity of Rosenpass daemon.
+    ///
+    /// Default: [`RosenpassVerbosity::Quiet`]
+    #[must_use]
+    pub fn verbosity(mut self, value: RosenpassVerbosity) -> Self {
+        self.verbosity = value;
+        self
+    }
+
+    /// Sets name of peer with given public key. Name is used for locating peer's Rosenpass
+    /// public key (see [`RosenpassKeyFiles`]).
+    ///
+    /// Default: `peer-<first 8 hex digits of public key>`
+    #[must_use]
+    pub fn peer_name(mut self, public_key: PublicKey, name: impl Into<String>) -> Self {
+        self.peer_names
+            .retain(|(existing, _)| *existing != public_key);
+        self.peer_names.push((public_key, name.into()));
+        self
+    }
+
+    fn name_of(&self, public_key: &PublicKey) -> String {
+        self.peer_names
+            .iter()
+            .find(|(existing, _)| existing == public_key)
+            .map_or_else(
+                || format!("peer-{}", &public_key.to_hex()[..8]),
+                |(_, name)| name.clone(),
+            )
+    }
+}
+
+impl Default for RosenpassOptions {
+    fn default() -> Self {
+        Self {
+            key_dir: PathBuf::from("/etc/rosenpass"),
+            device: "wg0".to_string(),
+            port: DEFAULT_PORT,
+            verbosity: RosenpassVerbosity::default(),
+            peer_names: Vec::new(),
+        }
+    }
+}
+
+/// Layout of Rosenpass key files for one host, same as `rp genkey`/`rp pubkey` use.
+///
+/// ```text
+/// <key_dir>/<name>.rosenpass-secret/pqsk  -- Rosenpass secret key
+/// <key_dir>/<name>.rosenpass-secret/wgsk  -- WireGuard private key
+/// <key_dir>/<name>.rosenpass-public/pqpk  -- Rosenpass public key (to share with peers)
+/// <key_dir>/<name>.rosenpass-public/wgpk  -- WireGuard public key (to share with peers)
+/// ```
+///
+/// Rosenpass keys are Classic McEliece keys, so they have to be generated by Rosenpass itself
+/// (see [`RosenpassKeyFiles::gen_keys_command()`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RosenpassKeyFiles {
+    /// Directory with secret keys.
+    pub secret_dir: PathBuf,
+
+    /// Directory with public keys.
+    pub public_dir: PathBuf,
+}
+
+impl RosenpassKeyFiles {
+    /// Create layout for host `name` in `key_dir`.
+    #[must_use]
+    pub fn new(key_dir: impl AsRef<Path>, name: &str) -> Self {
+        let key_dir = key_dir.as_ref();
+
+        Self {
+            secret_dir: key_dir.join(format!("{name}.rosenpass-secret")),
+            public_dir: key_dir.join(format!("{name}.rosenpass-public")),
+        }
+    }
+
+    /// Path to Rosenpass secret key.
+    #[must_use]
+    pub fn secret_key(&self) -> PathBuf {
+        self.secret_dir.join("pqsk")
+    }
+
+    /// Path to Rosenpass public key.
+    #[must_use]
+    pub fn public_key(&self) -> PathBuf {
+        self.public_dir.join("pqpk")
+    }
+
+    /// Path to WireGuard private key.
+    #[must_use]
+    pub fn wireguard_private_key(&self) -> PathBuf {
+        self.secret_dir.join("wgsk")
+    }
+
+    /// Path to WireGuard public key.
+    #[must_use]
+    pub fn wireguard_public_key(&self) -> PathBuf {
+        self.public_dir.join("wgpk")
+    }
+
+    /// Command for generating Rosenpass keypair. Directories must exist
+    /// (see [`RosenpassKeyFiles::write_wireguard_keys()`]).
+    #[must_use]
+    pub fn gen_keys_command(&self) -> String {
+        format!(
+            "rosenpass gen-keys --secret-key {} --public-key {}",
+            shell_quote(&self.secret_key().to_string_lossy()),
+            shell_quote(&self.public_key().to_string_lossy()),
+        )
+    }
+
+    /// Create directories and write WireGuard keys (in `wg genkey` format).
+    ///
+    /// On Unix, secret directory gets `0700` permissions and private key gets `0600`.
+    ///
+    /// # Errors
+    ///
+    /// Errors of creating directories and writing files.
+    pub fn write_wireguard_keys(&self, private_key: &PrivateKey) -> io::Result<()> {
+        fs::create_dir_all(&self.secret_dir)?;
+        fs::create_dir_all(&self.public_dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&self.secret_dir, fs::Permissions::from_mode(0o700))?;
+        }
+
+        write_secret_file(&self.wireguard_private_key(), &private_key.expose_secret())?;
+        fs::write(
+            self.wireguard_public_key(),
+            format!("{}\n", private_key.public_key()),
+        )
+    }
+}
+
+fn write_secret_file(path: &Path, key: &str) -> io::Result<()> {
+    use io::Write as _;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // file may already exist with other permissions
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(key.as_bytes())?;
+    file.write_all(b"\n")
+}
+
+/// Rosenpass config (`rosenpass exchange-config <file>`).
+///
+/// Use [`Interface::to_rosenpass()`] to create it. Its [`fmt::Display`] exports TOML.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RosenpassConfig {
+    /// Path to own Rosenpass public key.
+    pub public_key: PathBuf,
+
+    /// Path to own Rosenpass secret key.
+    pub secret_key: PathBuf,
+
+    /// Addresses to listen on. Empty for hosts, that only initiate key exchanges (clients).
+    pub listen: Vec<String>,
+
+    /// Verbosity.
+    pub verbosity: RosenpassVerbosity,
+
+    /// Peers.
+    pub peers: Vec<RosenpassPeer>,
+}
+
+/// Peer of [`RosenpassConfig`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RosenpassPeer {
+    /// Path to peer's Rosenpass public key.
+    pub public_key: PathBuf,
+
+    /// Peer's Rosenpass endpoint.
+    pub endpoint: Option<String>,
+
+    /// WireGuard device, that gets preshared key.
+    pub device: String,
+
+    /// Peer's WireGuard public key.
+    pub peer: PublicKey,
+}
+
+/// Rosenpass setup for [`Interface`]. Created by [`Interface::to_rosenpass()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rosenpass {
+    /// Rosenpass config.
+    pub config: RosenpassConfig,
+
+    /// WireGuard config with preshared keys, marked as externally managed
+    /// (see [`Peer::external_preshared_key`]).
+    pub interface: Interface,
+
+    /// Own key files.
+    pub keys: RosenpassKeyFiles,
+}
+
+impl Interface {
+    /// Generate [Rosenpass](https://rosenpass.eu) setup for post-quantum preshared keys.
+    ///
+    /// `name` is this host's name, used for key files (see [`RosenpassKeyFiles`]). Every peer
+    /// gets Rosenpass peer, and its preshared key becomes externally managed: Rosenpass sets it
+    /// via `wg set`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// let client = PeerBuilder::new()
+    ///     .allowed_ips([as_ipnet!("10.0.0.2/32")])
+    ///     .build();
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .listen_port(51820)
+    ///     .peers([client.clone()])
+    ///     .build();
+    ///
+    /// let options = RosenpassOptions::new().peer_name(client.public_key(), "client");
+    /// let rosenpass = server.to_rosenpass("server", &options);
+    ///
+    /// assert!(rosenpass.interface.peers[0].external_preshared_key);
+    /// assert_eq!(
+    ///     rosenpass.config.to_string(),
+    ///     format!(
+    ///         "public_key = \"/etc/rosenpass/server.rosenpass-public/pqpk\"\n\
+    ///          secret_key = \"/etc/rosenpass/server.rosenpass-secret/pqsk\"\n\
+    ///          listen = [\"[::]:9999\"]\n\
+    ///          verbosity = \"Quiet\"\n\
+    ///          \n\
+    ///          [[peers]]\n\
+    ///          public_key = \"/etc/rosenpass/client.rosenpass-public/pqpk\"\n\
+    ///          device = \"wg0\"\n\
+    ///          peer = \"{}\"\n",
+    ///         client.public_key(),
+    ///     )
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_rosenpass(&self, name: &str, options: &RosenpassOptions) -> Rosenpass {
+        let keys = RosenpassKeyFiles::new(&options.key_dir, name);
+
+        let mut interface = self.clone();
+        for peer in &mut interface.peers {
+            peer.preshared_key = None;
+            peer.external_preshared_key = true;
+        }
+
+        let peers = self
+            .peers
+            .iter()
+            .map(|peer| {
+                let public_key = peer.public_key();
+                let peer_keys =
+                    RosenpassKeyFiles::new(&options.key_dir, &options.name_of(&public_key));
+
+                RosenpassPeer {
+                    public_key: peer_keys.public_key(),
+                    endpoint: peer
+                        .endpoint
+                        .as_deref()
+                        .map(|endpoint| rosenpass_endpoint(endpoint, options.port)),
+                    device: options.device.clone(),
+                    peer: public_key,
+                }
+            })
+            .collect();
+
+        let config = RosenpassConfig {
+            public_key: keys.public_key(),
+            secret_key: keys.secret_key(),
+            listen: if self.listen_port.is_some() {
+                vec![format!("[::]:{}", options.port)]
+            } else {
+                Vec::new()
+            },
+            verbosity: options.verbosity,
+            peers,
+        };
+
+        Rosenpass {
+            config,
+            interface,
+            keys,
+        }
+    }
+}
+
+/// Replace port of WireGuard endpoint (`host:port`) with Rosenpass port.
+fn rosenpass_endpoint(endpoint: &str, port: u16) -> String {
+    let host = match endpoint.rsplit_once(':') {
+        Some((host, wg_port)) if wg_port.parse::<u16>().is_ok() => host,
+        _ => endpoint,
+    };
+
+    format!("{host}:{port}")
+}
+
+/// Write TOML basic string.
+fn write_toml_string<W: fmt::Write + ?Sized>(f: &mut W, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04X}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Implements [`fmt::Display`] for exporting Rosenpass config (TOML).
+impl fmt::Display for RosenpassConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("public_key = ")?;
+        write_toml_string(f, &self.public_key.to_string_lossy())?;
+        f.write_str("\nsecret_key = ")?;
+        write_toml_string(f, &self.secret_key.to_string_lossy())?;
+        f.write_str("\nlisten = [")?;
+        for (i, listen) in self.listen.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write_toml_string(f, listen)?;
+        }
+        writeln!(f, "]")?;
+        writeln!(f, "verbosity = \"{}\"", self.verbosity)?;
+
+        for peer in &self.peers {
+            writeln!(f)?;
+            writeln!(f, "[[peers]]")?;
+            f.write_str("public_key = ")?;
+            write_toml_string(f, &peer.public_key.to_string_lossy())?;
+            writeln!(f)?;
+            if let Some(endpoint) = &peer.endpoint {
+                f.write_str("endpoint = ")?;
+                write_toml_string(f, endpoint)?;
+                writeln!(f)?;
+            }
+            f.write_str("device = ")?;
+            write_toml_string(f, &peer.device)?;
+            writeln!(f)?;
+            writeln!(f, "peer = \"{}\"", peer.peer)?;
+        }
+
+        Ok(())
+    }
+}
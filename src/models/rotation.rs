@@ -0,0 +1,300 @@
+use either::Either;
+use rand::CryptoRng;
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// Result of key rotation: old public key and new private key.
+///
+/// Created by [`Interface::rotate_key()`] and [`Peer::rotate_key()`].
+///
+/// Use [`KeyRotation::affected_configs()`] to update other configs, that reference old key, and
+/// [`KeyMetadata::record()`] to keep rotation timestamp.
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// # use wireguard_conf::as_ipnet;
+/// # fn main() -> WireguardResult<()> {
+/// let mut server = InterfaceBuilder::new()
+///     .address([as_ipnet!("10.0.0.1/24")])
+///     .endpoint("vpn.example.com")
+///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+///     .build();
+/// let clients = vec![server.peers[0].to_interface(&server, ToInterfaceOptions::new())?];
+///
+/// let mut metadata = KeyMetadata::new();
+///
+/// let rotation = server.rotate_key();
+/// metadata.record(&rotation);
+///
+/// // client's `[Peer]` section has old server's key, so it has to be updated
+/// let updates = rotation.affected_configs(&clients);
+/// assert_eq!(updates.len(), 1);
+/// assert_eq!(updates[0].index, 0);
+/// assert_eq!(updates[0].config.peers[0].public_key(), server.private_key.public_key());
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct KeyRotation {
+    /// Public key before rotation.
+    pub old_public_key: PublicKey,
+
+    /// New private key.
+    pub new_private_key: PrivateKey,
+
+    /// Time of rotation.
+    pub rotated_at: SystemTime,
+}
+
+/// Config, that has to be changed after [`KeyRotation`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConfigUpdate {
+    /// Index of config in slice, passed to [`KeyRotation::affected_configs()`].
+    pub index: usize,
+
+    /// Updated config.
+    pub config: Interface,
+}
+
+impl KeyRotation {
+    fn new(old_public_key: PublicKey, new_private_key: PrivateKey) -> Self {
+        Self {
+            old_public_key,
+            new_private_key,
+            rotated_at: SystemTime::now(),
+        }
+    }
+
+    /// Get new public key.
+    #[must_use]
+    pub fn new_public_key(&self) -> PublicKey {
+        self.new_private_key.public_key()
+    }
+
+    /// Check, whether config uses old key: either as its own private key, or as key of
+    /// one of its peers.
+    #[must_use]
+    pub fn is_affected(&self, config: &Interface) -> bool {
+        config.private_key.public_key() == self.old_public_key
+            || config
+                .peers
+                .iter()
+                .any(|peer| peer.public_key() == self.old_public_key)
+    }
+
+    /// Get updated config, if it uses old key. Otherwise, returns [`None`].
+    ///
+    /// - Interface's private key is replaced with new private key.
+    /// - Peers with old key get new key. Peers with private key get new private key, peers
+    ///   with public key get new public key.
+    #[must_use]
+    pub fn apply(&self, config: &Interface) -> Option<Interface> {
+        if !self.is_affected(config) {
+            return None;
+        }
+
+        let mut config = config.clone();
+        if config.private_key.public_key() == self.old_public_key {
+            config.private_key = self.new_private_key.clone();
+        }
+        for peer in &mut config.peers {
+            if peer.public_key() == self.old_public_key {
+                peer.key = match peer.key {
+                    Either::Left(_) => Either::Left(self.new_private_key.clone()),
+                    Either::Right(_) => Either::Right(self.new_public_key()),
+                };
+            }
+        }
+
+        Some(config)
+    }
+
+    /// Get every config, that uses old key, with updated version of it.
+    #[must_use]
+    pub fn affected_configs(&self, configs: &[Interface]) -> Vec<ConfigUpdate> {
+        configs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, config)| {
+                self.apply(config)
+                    .map(|config| ConfigUpdate { index, config })
+            })
+            .collect()
+    }
+}
+
+impl Interface {
+    /// Replace interface's private key with new random one.
+    ///
+    /// Interface is updated in place. Other configs, that have this interface as peer, have to
+    /// be updated too (see [`KeyRotation::affected_configs()`]).
+    pub fn rotate_key(&mut self) -> KeyRotation {
+        self.rotate_key_with_rng(&mut rand::rng())
+    }
+
+    /// Same as [`Interface::rotate_key()`], but uses provided `rng`.
+    pub fn rotate_key_with_rng<R: CryptoRng + ?Sized>(&mut self, rng: &mut R) -> KeyRotation {
+        let new_private_key = PrivateKey::random_with_rng(rng);
+        let old_private_key = std::mem::replace(&mut self.private_key, new_private_key.clone());
+
+        KeyRotation::new(old_private_key.public_key(), new_private_key)
+    }
+}
+
+impl Peer {
+    /// Replace peer's key with new random private key.
+    ///
+    /// Peer is updated in place (even if it had only public key before). Peer's own config
+    /// (f.e. created by [`Peer::to_interface()`]) has to be updated too
+    /// (see [`KeyRotation::affected_configs()`]).
+    pub fn rotate_key(&mut self) -> KeyRotation {
+        self.rotate_key_with_rng(&mut rand::rng())
+    }
+
+    /// Same as [`Peer::rotate_key()`], but uses provided `rng`.
+    pub fn rotate_key_with_rng<R: CryptoRng + ?Sized>(&mut self, rng: &mut R) -> KeyRotation {
+        let old_public_key = self.public_key();
+        let new_private_key = PrivateKey::random_with_rng(rng);
+        self.key = Either::Left(new_private_key.clone());
+
+        KeyRotation::new(old_public_key, new_private_key)
+    }
+}
+
+/// Record of key in [`KeyMetadata`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct KeyRecord {
+    /// Public key.
+    pub public_key: PublicKey,
+
+    /// Time, when key was created (or rotated in).
+    pub created_at: SystemTime,
+
+    /// Public key, that was replaced by this key.
+    pub previous_public_key: Option<PublicKey>,
+}
+
+/// Rotation timestamps of keys. Used for enforcing maximum key age.
+///
+/// Store it alongside configs (f.e. with `serde` feature) and check it periodically with
+/// [`KeyMetadata::expired()`].
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// use std::time::{Duration, SystemTime};
+///
+/// const MAX_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+///
+/// let mut interface = InterfaceBuilder::new().build();
+///
+/// let mut metadata = KeyMetadata::new();
+/// metadata.track(interface.private_key.public_key(), SystemTime::UNIX_EPOCH);
+///
+/// if metadata.needs_rotation(&interface.private_key.public_key(), MAX_AGE, SystemTime::now()) {
+///     metadata.record(&interface.rotate_key());
+/// }
+///
+/// assert!(metadata.expired(MAX_AGE, SystemTime::now()).is_empty());
+/// ```
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct KeyMetadata {
+    records: Vec<KeyRecord>,
+}
+
+impl KeyMetadata {
+    /// Create new empty [`KeyMetadata`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking existing key, created at `created_at`. Replaces previous record of the
+    /// key, if any.
+    pub fn track(&mut self, public_key: PublicKey, created_at: SystemTime) {
+        self.records
+            .retain(|record| record.public_key != public_key);
+        self.records.push(KeyRecord {
+            public_key,
+            created_at,
+            previous_public_key: None,
+        });
+    }
+
+    /// Record key rotation: old key is forgotten, new key is tracked since
+    /// [`KeyRotation::rotated_at`].
+    pub fn record(&mut self, rotation: &KeyRotation) {
+        let new_public_key = rotation.new_public_key();
+
+        self.records.retain(|record| {
+            record.public_key != rotation.old_public_key && record.public_key != new_public_key
+        });
+        self.records.push(KeyRecord {
+            public_key: new_public_key,
+            created_at: rotation.rotated_at,
+            previous_public_key: Some(rotation.old_public_key.clone()),
+        });
+    }
+
+    /// Get record of key.
+    #[must_use]
+    pub fn get(&self, public_key: &PublicKey) -> Option<&KeyRecord> {
+        self.records
+            .iter()
+            .find(|record| record.public_key == *public_key)
+    }
+
+    /// Get all records.
+    #[must_use]
+    pub fn records(&self) -> &[KeyRecord] {
+        &self.records
+    }
+
+    /// Get age of key at `now`.
+    ///
+    /// Returns [`None`], if key isn't tracked. Keys, created in the future, have zero age.
+    #[must_use]
+    pub fn age(&self, public_key: &PublicKey, now: SystemTime) -> Option<Duration> {
+        self.get(public_key)
+            .map(|record| now.duration_since(record.created_at).unwrap_or_default())
+    }
+
+    /// Check, whether key is older than `max_age` and has to be rotated.
+    ///
+    /// Untracked keys always need rotation, as their age is unknown.
+    #[must_use]
+    pub fn needs_rotation(
+        &self,
+        public_key: &PublicKey,
+        max_age: Duration,
+        now: SystemTime,
+    ) -> bool {
+        match self.age(public_key, now) {
+            Some(age) => age > max_age,
+            None => true,
+        }
+    }
+
+    /// Get records of keys, that are older than `max_age`.
+    #[must_use]
+    pub fn expired(&self, max_age: Duration, now: SystemTime) -> Vec<&KeyRecord> {
+        self.records
+            .iter()
+            .filter(|record| now.duration_since(record.created_at).unwrap_or_default() > max_age)
+            .collect()
+    }
+}
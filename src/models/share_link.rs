@@ -0,0 +1,238 @@
+use either::Either;
+use ipnet::IpNet;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::prelude::*;
+
+/// Scheme of share links.
+const SCHEME: &str = "wireguard://";
+
+/// Characters, which are encoded: everything except RFC 3986 unreserved ones.
+const ENCODED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+impl Interface {
+    /// Export client config as single-line `wireguard://` share link, supported by Hiddify,
+    /// v2rayN, NekoBox and others:
+    ///
+    /// ```text
+    /// wireguard://<private key>@<host>:<port>?publickey=...&address=...#<name>
+    /// ```
+    ///
+    /// Interface must have single peer with endpoint (f.e. created by
+    /// [`Peer::to_interface()`]). Keys and addresses are percent-encoded, IPv6 hosts are put into
+    /// brackets. `reserved` bytes are added as `reserved` parameter (Cloudflare WARP uses them for
+    /// client id).
+    ///
+    /// DNS, scripts and AmneziaWG settings aren't exported: links don't support them.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidShareLink`] -- interface doesn't have single peer with endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .endpoint("vpn.example.com")
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    /// let client = server.peers[0].to_interface(&server, ToInterfaceOptions::new())?;
+    ///
+    /// let link = client.to_share_link("My server", None)?;
+    /// assert!(link.starts_with("wireguard://"));
+    /// assert!(link.ends_with("#My%20server"));
+    ///
+    /// let imported = Interface::from_share_link(&link)?;
+    /// assert_eq!(imported.private_key, client.private_key);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_share_link(&self, name: &str, reserved: Option<[u8; 3]>) -> WireguardResult<String> {
+        let [peer] = self.peers.as_slice() else {
+            return Err(invalid("interface must have exactly one peer"));
+        };
+        let (host, port) = peer
+            .split_endpoint()
+            .ok_or_else(|| invalid("peer has no endpoint with port"))?;
+        let endpoint = Peer::join_endpoint(host, port);
+
+        let mut params = vec![format!(
+            "publickey={}",
+            encode(&peer.public_key().to_string())
+        )];
+        if let Some(preshared_key) = peer.exported_preshared_key() {
+            params.push(format!(
+                "presharedkey={}",
+                encode(&preshared_key.to_string())
+            ));
+        }
+        params.push(format!("address={}", encode_list(&self.address)));
+        params.push(format!("allowedips={}", encode_list(&peer.allowed_ips)));
+        if let Some(mtu) = self.mtu {
+            params.push(format!("mtu={mtu}"));
+        }
+        if peer.persistent_keepalive != 0 {
+            params.push(format!("keepalive={}", peer.persistent_keepalive));
+        }
+        if let Some([a, b, c]) = reserved {
+            params.push(format!("reserved={a},{b},{c}"));
+        }
+
+        Ok(format!(
+            "{SCHEME}{}@{endpoint}?{}#{}",
+            encode(&self.private_key.to_string()),
+            params.join("&"),
+            encode(name),
+        ))
+    }
+
+    /// Import client config from `wireguard://` share link.
+    ///
+    /// Addresses without prefix get `/32` or `/128`, missing `allowedips` means `0.0.0.0/0, ::/0`.
+    /// `reserved` bytes and name aren't part of WireGuard config, so they're dropped.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidShareLink`] -- link is malformed.
+    /// - [`WireguardError::InvalidPrivateKey`], [`WireguardError::InvalidPublicKey`],
+    ///   [`WireguardError::InvalidPresharedKey`] -- key is malformed.
+    pub fn from_share_link(link: &str) -> WireguardResult<Interface> {
+        let link = link
+            .trim()
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| invalid("missing `wireguard://` scheme"))?;
+        let link = link.split_once('#').map_or(link, |(link, _)| link);
+        let (authority, query) = link.split_once('?').unwrap_or((link, ""));
+        let authority = authority.trim_end_matches('/');
+
+        let (private_key, endpoint) = authority
+            .rsplit_once('@')
+            .ok_or_else(|| invalid("missing private key"))?;
+        let (host, port) = endpoint
+            .rsplit_once(':')
+            .ok_or_else(|| invalid("missing port"))?;
+        let port: u16 = port.parse().map_err(|_| invalid("malformed port"))?;
+
+        let mut params = Params::default();
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            params.set(key, decode(value)?)?;
+        }
+
+        let peer = Peer {
+            endpoint: Some(format!("{}:{port}", decode(host)?)),
+            allowed_ips: params.allowed_ips.unwrap_or_else(Peer::default_allowed_ips),
+            persistent_keepalive: params.keepalive,
+            key: Either::Right(PublicKey::try_from(
+                params
+                    .public_key
+                    .ok_or_else(|| invalid("missing `publickey`"))?
+                    .as_str(),
+            )?),
+            preshared_key: params
+                .preshared_key
+                .as_deref()
+                .map(PresharedKey::try_from)
+                .transpose()?,
+            #[cfg(feature = "rosenpass")]
+            external_preshared_key: false,
+        };
+
+        Ok(Interface {
+            address: params.address.ok_or_else(|| invalid("missing `address`"))?,
+            listen_port: None,
+            private_key: PrivateKey::try_from(decode(private_key)?.as_str())?,
+            dns: vec![],
+            endpoint: None,
+            table: None,
+            mtu: params.mtu,
+            #[cfg(feature = "amneziawg")]
+            amnezia_settings: None,
+            pre_up: vec![],
+            pre_down: vec![],
+            post_up: vec![],
+            post_down: vec![],
+            peers: vec![peer],
+        })
+    }
+}
+
+/// Query parameters of share link.
+#[derive(Default)]
+struct Params {
+    public_key: Option<String>,
+    preshared_key: Option<String>,
+    address: Option<Vec<IpNet>>,
+    allowed_ips: Option<Vec<IpNet>>,
+    mtu: Option<usize>,
+    keepalive: u16,
+}
+
+impl Params {
+    /// Set parameter. Unknown parameters are ignored, since clients add their own ones.
+    fn set(&mut self, key: &str, value: String) -> WireguardResult<()> {
+        let malformed = || invalid(&format!("malformed `{key}`"));
+
+        match key {
+            "publickey" => self.public_key = Some(value),
+            "presharedkey" => self.preshared_key = Some(value).filter(|key| !key.is_empty()),
+            "address" => self.address = Some(parse_list(&value).ok_or_else(malformed)?),
+            "allowedips" => self.allowed_ips = Some(parse_list(&value).ok_or_else(malformed)?),
+            "mtu" => self.mtu = Some(value.parse().map_err(|_| malformed())?),
+            "keepalive" => self.keepalive = value.parse().map_err(|_| malformed())?,
+            "reserved" => {
+                let bytes = value
+                    .split(',')
+                    .map(|byte| byte.trim().parse::<u8>().ok())
+                    .collect::<Option<Vec<_>>>();
+                if !matches!(bytes, Some(bytes) if bytes.len() == 3) {
+                    return Err(malformed());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(reason: &str) -> WireguardError {
+    WireguardError::InvalidShareLink(reason.to_string())
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, ENCODED).to_string()
+}
+
+/// Encode networks as comma-separated list.
+fn encode_list(networks: &[IpNet]) -> String {
+    networks
+        .iter()
+        .map(|network| encode(&network.to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn decode(value: &str) -> WireguardResult<String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(String::from)
+        .map_err(|_| invalid("malformed percent-encoding"))
+}
+
+/// Parse comma-separated networks or plain IPs.
+fn parse_list(value: &str) -> Option<Vec<IpNet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(Peer::parse_network)
+        .collect()
+}
@@ -0,0 +1,252 @@
+use either::Either;
+use ipnet::IpNet;
+use serde_json::{json, Value};
+
+use crate::prelude::*;
+
+impl Interface {
+    /// Export interface as [sing-box](https://sing-box.sagernet.org) WireGuard endpoint
+    /// (sing-box 1.11+) with `tag`.
+    ///
+    /// [`Interface::dns`] and scripts aren't exported, since sing-box configures them elsewhere.
+    /// Peers without endpoint are exported without `address` and `port`.
+    ///
+    /// If interface has [`Interface::amnezia_settings`], they are exported as `amnezia` object,
+    /// supported by sing-box forks with AmneziaWG (upstream sing-box rejects unknown fields).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// # fn main() -> WireguardResult<()> {
+    /// let server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .endpoint("vpn.example.com")
+    ///     .peers([PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build()])
+    ///     .build();
+    /// let client = server.peers[0].to_interface(&server, ToInterfaceOptions::new())?;
+    ///
+    /// let endpoint = client.to_sing_box("wg-out");
+    /// assert_eq!(endpoint["type"], "wireguard");
+    /// assert_eq!(endpoint["peers"][0]["address"], "vpn.example.com");
+    /// assert_eq!(endpoint["peers"][0]["port"], 51820);
+    ///
+    /// let imported = Interface::from_sing_box(&endpoint)?;
+    /// assert_eq!(imported.private_key, client.private_key);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn to_sing_box(&self, tag: &str) -> Value {
+        let mut endpoint = json!({
+            "type": "wireguard",
+            "tag": tag,
+            "address": addresses(&self.address),
+            "private_key": self.private_key.to_string(),
+            "peers": self.peers.iter().map(peer_to_sing_box).collect::<Vec<_>>(),
+        });
+        if let Some(mtu) = self.mtu {
+            endpoint["mtu"] = json!(mtu);
+        }
+        if let Some(listen_port) = self.listen_port {
+            endpoint["listen_port"] = json!(listen_port);
+        }
+
+        #[cfg(feature = "amneziawg")]
+        if let Some(settings) = &self.amnezia_settings {
+            endpoint["amnezia"] = amnezia_to_sing_box(settings);
+        }
+
+        endpoint
+    }
+
+    /// Import interface from [sing-box](https://sing-box.sagernet.org) WireGuard endpoint
+    /// (sing-box 1.11+) or legacy WireGuard outbound.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidSingBox`] -- object isn't WireGuard endpoint or outbound, or
+    ///   field is malformed.
+    /// - [`WireguardError::InvalidPrivateKey`], [`WireguardError::InvalidPublicKey`],
+    ///   [`WireguardError::InvalidPresharedKey`] -- key is malformed.
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- AmneziaWG settings are invalid.
+    pub fn from_sing_box(value: &Value) -> WireguardResult<Interface> {
+        if value["type"] != "wireguard" {
+            return Err(invalid("type isn't `wireguard`"));
+        }
+
+        // legacy outbound has `local_address` and single peer in top-level fields
+        let is_outbound = value.get("local_address").is_some();
+        let address = if is_outbound {
+            &value["local_address"]
+        } else {
+            &value["address"]
+        };
+
+        let peers = if is_outbound && value.get("peers").is_none() {
+            vec![Peer {
+                endpoint: endpoint(value, "server", "server_port")?,
+                allowed_ips: parse_ip_list(&value["allowed_ips"], "allowed_ips")?
+                    .unwrap_or_else(Peer::default_allowed_ips),
+                persistent_keepalive: 0,
+                key: Either::Right(PublicKey::try_from(string(value, "peer_public_key")?)?),
+                preshared_key: preshared_key(value)?,
+                #[cfg(feature = "rosenpass")]
+                external_preshared_key: false,
+            }]
+        } else {
+            value["peers"]
+                .as_array()
+                .ok_or_else(|| invalid("missing `peers`"))?
+                .iter()
+                .map(|peer| peer_from_sing_box(peer, is_outbound))
+                .collect::<WireguardResult<_>>()?
+        };
+
+        Ok(Interface {
+            address: parse_ip_list(address, "address")?
+                .ok_or_else(|| invalid("missing `address`"))?,
+            listen_port: number(value, "listen_port")?,
+            private_key: PrivateKey::try_from(string(value, "private_key")?)?,
+            dns: vec![],
+            endpoint: None,
+            table: None,
+            mtu: number(value, "mtu")?,
+            #[cfg(feature = "amneziawg")]
+            amnezia_settings: value
+                .get("amnezia")
+                .map(amnezia_from_sing_box)
+                .transpose()?,
+            pre_up: vec![],
+            pre_down: vec![],
+            post_up: vec![],
+            post_down: vec![],
+            peers,
+        })
+    }
+}
+
+fn peer_to_sing_box(peer: &Peer) -> Value {
+    let mut value = json!({
+        "public_key": peer.public_key().to_string(),
+        "allowed_ips": addresses(&peer.allowed_ips),
+    });
+    if let Some((host, port)) = peer.split_endpoint() {
+        value["address"] = json!(host);
+        value["port"] = json!(port);
+    }
+    if let Some(preshared_key) = peer.exported_preshared_key() {
+        value["pre_shared_key"] = json!(preshared_key.to_string());
+    }
+    if peer.persistent_keepalive != 0 {
+        value["persistent_keepalive_interval"] = json!(peer.persistent_keepalive);
+    }
+
+    value
+}
+
+fn peer_from_sing_box(value: &Value, is_outbound: bool) -> WireguardResult<Peer> {
+    let (host, port) = if is_outbound {
+        ("server", "server_port")
+    } else {
+        ("address", "port")
+    };
+
+    Ok(Peer {
+        endpoint: endpoint(value, host, port)?,
+        allowed_ips: parse_ip_list(&value["allowed_ips"], "allowed_ips")?
+            .unwrap_or_else(Peer::default_allowed_ips),
+        persistent_keepalive: number(value, "persistent_keepalive_interval")?.unwrap_or(0),
+        key: Either::Right(PublicKey::try_from(string(value, "public_key")?)?),
+        preshared_key: preshared_key(value)?,
+        #[cfg(feature = "rosenpass")]
+        external_preshared_key: false,
+    })
+}
+
+/// Render settings with lowercase keys, as sing-box forks expect. Numeric values are numbers,
+/// header ranges and signature packets are strings.
+#[cfg(feature = "amneziawg")]
+fn amnezia_to_sing_box(settings: &AmneziaSettings) -> Value {
+    settings
+        .keys()
+        .map(|(key, value)| {
+            let value = value.to_string();
+            let value = value
+                .parse::<u64>()
+                .map_or_else(|_| json!(value), |value| json!(value));
+
+            (key.to_lowercase(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[cfg(feature = "amneziawg")]
+fn amnezia_from_sing_box(value: &Value) -> WireguardResult<AmneziaSettings> {
+    AmneziaSettings::read_keys(|key| match &value[key.to_lowercase()] {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+fn invalid(reason: &str) -> WireguardError {
+    WireguardError::InvalidSingBox(reason.to_string())
+}
+
+fn addresses(networks: &[IpNet]) -> Vec<String> {
+    networks.iter().map(ToString::to_string).collect()
+}
+
+/// Parse list of networks. sing-box also accepts single string and plain IPs.
+fn parse_ip_list(value: &Value, key: &str) -> WireguardResult<Option<Vec<IpNet>>> {
+    let parse = |value: &Value| Peer::parse_network(value.as_str()?);
+    let malformed = || invalid(&format!("malformed `{key}`"));
+
+    match value {
+        Value::Null => Ok(None),
+        Value::Array(values) => values
+            .iter()
+            .map(parse)
+            .collect::<Option<_>>()
+            .map(Some)
+            .ok_or_else(malformed),
+        value => Ok(Some(vec![parse(value).ok_or_else(malformed)?])),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> WireguardResult<&'a str> {
+    value[key]
+        .as_str()
+        .ok_or_else(|| invalid(&format!("missing `{key}`")))
+}
+
+fn number<T: TryFrom<u64>>(value: &Value, key: &str) -> WireguardResult<Option<T>> {
+    match &value[key] {
+        Value::Null => Ok(None),
+        value => value
+            .as_u64()
+            .and_then(|value| T::try_from(value).ok())
+            .map(Some)
+            .ok_or_else(|| invalid(&format!("malformed `{key}`"))),
+    }
+}
+
+fn endpoint(value: &Value, host: &str, port: &str) -> WireguardResult<Option<String>> {
+    let Some(host) = value[host].as_str() else {
+        return Ok(None);
+    };
+    let port: u16 = number(value, port)?.ok_or_else(|| invalid(&format!("missing `{port}`")))?;
+
+    Ok(Some(Peer::join_endpoint(host, port)))
+}
+
+fn preshared_key(value: &Value) -> WireguardResult<Option<PresharedKey>> {
+    value["pre_shared_key"]
+        .as_str()
+        .filter(|key| !key.is_empty())
+        .map(PresharedKey::try_from)
+        .transpose()
+}
@@ -0,0 +1,586 @@
+use either::Either;
+use ipnet::IpNet;
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+
+use crate::prelude::*;
+use crate::utils::REDACTED;
+
+/// Streaming writer of Wireguard configs.
+///
+/// Unlike `.to_string()`, [`ConfigWriter`] writes sections straight into [`io::Write`] without
+/// building whole config in memory, and without any per-peer heap allocations. It's useful for
+/// writing multi-megabyte configs to files/sockets, or for rendering only some of the peers.
+///
+/// Output of [`ConfigWriter::interface()`] is byte-to-byte identical to [`Interface`]'s
+/// [`fmt::Display`].
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// # use wireguard_conf::as_ipnet;
+/// # fn main() -> std::io::Result<()> {
+/// let interface = InterfaceBuilder::new()
+///     .address([as_ipnet!("10.0.0.1/24")])
+///     .peers([
+///         PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.2/32")]).build(),
+///         PeerBuilder::new().allowed_ips([as_ipnet!("10.0.0.3/32")]).build(),
+///     ])
+///     .build();
+///
+/// // write full config:
+/// let mut writer = ConfigWriter::new(Vec::new());
+/// writer.interface(&interface)?;
+/// assert_eq!(writer.into_inner(), interface.to_string().into_bytes());
+///
+/// // write `[Interface]` section and only the last peer:
+/// let mut writer = ConfigWriter::new(Vec::new());
+/// writer
+///     .interface_section(&interface)?
+///     .peers(interface.peers.iter().skip(1))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ConfigWriter<W: io::Write> {
+    inner: W,
+    options: FormatOptions,
+    pending_newlines: usize,
+}
+
+impl<W: io::Write> ConfigWriter<W> {
+    /// Create new [`ConfigWriter`], that writes into `inner`.
+    ///
+    /// Writes aren't buffered, so wrap files and sockets into [`io::BufWriter`].
+    pub fn new(inner: W) -> Self {
+        Self::with_options(inner, FormatOptions::new())
+    }
+
+    /// Create new [`ConfigWriter`] with custom [`FormatOptions`].
+    ///
+    /// If [`FormatOptions::trailing_newline()`] is disabled, line breaks are held back until
+    /// something else is written, so output never ends with line break.
+    pub fn with_options(inner: W, options: FormatOptions) -> Self {
+        Self {
+            inner,
+            options,
+            pending_newlines: 0,
+        }
+    }
+
+    /// Write full config: `[Interface]` section and all of the peers.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if underlying writer has failed.
+    pub fn interface(&mut self, interface: &Interface) -> io::Result<&mut Self> {
+        self.render(|f, options| write_interface(f, interface, options))?;
+        Ok(self)
+    }
+
+    /// Write only `[Interface]` section (including scripts and AmneziaWG values), without peers.
+    ///
+    /// Use [`ConfigWriter::peer()`] or [`ConfigWriter::peers()`] to write peers afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if underlying writer has failed.
+    pub fn interface_section(&mut self, interface: &Interface) -> io::Result<&mut Self> {
+        self.render(|f, options| write_interface_section(f, interface, options))?;
+        Ok(self)
+    }
+
+    /// Write single `[Peer]` section, separated from previous section by blank line.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if underlying writer has failed.
+    pub fn peer(&mut self, peer: &Peer) -> io::Result<&mut Self> {
+        self.render(|f, options| write_peer_entry(f, &PeerFields::from(peer), options))?;
+        Ok(self)
+    }
+
+    /// Write `[Peer]` sections of all of the `peers`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if underlying writer has failed.
+    pub fn peers<'a, I>(&mut self, peers: I) -> io::Result<&mut Self>
+    where
+        I: IntoIterator<Item = &'a Peer>,
+    {
+        for peer in peers {
+            self.peer(peer)?;
+        }
+        Ok(self)
+    }
+
+    /// Flush underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if underlying writer has failed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Get reference to underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get mutable reference to underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwrap underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn render<F>(&mut self, render: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut dyn fmt::Write, &FormatOptions) -> fmt::Result,
+    {
+        let Self {
+            inner,
+            options,
+            pending_newlines,
+        } = self;
+
+        write_io(inner, |f| {
+            if options.trailing_newline {
+                render(f, options)
+            } else {
+                render(&mut NewlineTrimmer::new(f, pending_newlines), options)
+            }
+        })
+    }
+}
+
+impl Interface {
+    /// Write config into [`io::Write`].
+    ///
+    /// Same as `.to_string()`, but without building whole config in memory.
+    /// See [`ConfigWriter`] for writing only some of the peers.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if `writer` has failed.
+    pub fn write_to<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        ConfigWriter::new(writer).interface(self)?;
+        Ok(())
+    }
+}
+
+impl Peer {
+    /// Write `[Peer]` section into [`io::Write`].
+    ///
+    /// Same as `.to_string()`, but without intermediate allocations.
+    ///
+    /// # Errors
+    ///
+    /// Returns error, if `writer` has failed.
+    pub fn write_to<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        write_io(writer, |f| {
+            write_peer_section(f, self, &FormatOptions::new())
+        })
+    }
+}
+
+/// Adapter for writing [`fmt::Write`]-based renderers into [`io::Write`].
+struct IoAdapter<'a, W: io::Write + ?Sized> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write + ?Sized> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+fn write_io<W, F>(inner: &mut W, render: F) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    F: FnOnce(&mut IoAdapter<'_, W>) -> fmt::Result,
+{
+    let mut adapter = IoAdapter { inner, error: None };
+
+    render(&mut adapter).map_err(|_| {
+        adapter
+            .error
+            .take()
+            .unwrap_or_else(|| io::Error::other("formatter error"))
+    })
+}
+
+/// Adapter, that holds back trailing line breaks until something else is written.
+///
+/// Used for [`FormatOptions::trailing_newline()`].
+pub(crate) struct NewlineTrimmer<'a, W: fmt::Write + ?Sized> {
+    inner: &'a mut W,
+    pending_newlines: &'a mut usize,
+}
+
+impl<'a, W: fmt::Write + ?Sized> NewlineTrimmer<'a, W> {
+    pub(crate) fn new(inner: &'a mut W, pending_newlines: &'a mut usize) -> Self {
+        Self {
+            inner,
+            pending_newlines,
+        }
+    }
+}
+
+impl<W: fmt::Write + ?Sized> fmt::Write for NewlineTrimmer<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let trimmed = s.trim_end_matches('\n');
+
+        if !trimmed.is_empty() {
+            for _ in 0..*self.pending_newlines {
+                self.inner.write_char('\n')?;
+            }
+            *self.pending_newlines = 0;
+
+            self.inner.write_str(trimmed)?;
+        }
+        *self.pending_newlines += s.len() - trimmed.len();
+
+        Ok(())
+    }
+}
+
+/// Write items, separated by `separator`.
+pub(crate) fn write_joined<W, I, F>(
+    f: &mut W,
+    items: I,
+    separator: &str,
+    mut write_item: F,
+) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+    I: IntoIterator,
+    F: FnMut(&mut W, I::Item) -> fmt::Result,
+{
+    for (i, item) in items.into_iter().enumerate() {
+        if i != 0 {
+            f.write_str(separator)?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
+
+/// Write address. If `strip_host_prefix` is set, IPv4 `/32` and IPv6 `/128` networks are
+/// written as regular ips.
+pub(crate) fn write_address<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    address: &IpNet,
+    strip_host_prefix: bool,
+) -> fmt::Result {
+    if strip_host_prefix && address.prefix_len() == address.max_prefix_len() {
+        write!(f, "{}", address.addr())
+    } else {
+        write!(f, "{address}")
+    }
+}
+
+pub(crate) fn write_scripts<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    key: &str,
+    scripts: &[String],
+    options: &FormatOptions,
+) -> fmt::Result {
+    if !scripts.is_empty() {
+        if options.section_spacing {
+            writeln!(f)?;
+        }
+        for snippet in scripts {
+            writeln!(f, "{key}{}{snippet}", options.key_value_separator)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fields of `[Interface]` section, borrowed from [`Interface`] or [`PublicInterface`].
+pub(crate) struct InterfaceFields<'a> {
+    pub(crate) address: &'a [IpNet],
+    pub(crate) listen_port: Option<u16>,
+    /// Written as `PrivateKey` or `PublicKey`.
+    pub(crate) key: Either<&'a PrivateKey, &'a PublicKey>,
+    pub(crate) dns: &'a [String],
+    pub(crate) endpoint: Option<&'a str>,
+    pub(crate) table: Option<&'a Table>,
+    pub(crate) mtu: Option<usize>,
+    #[cfg(feature = "amneziawg")]
+    pub(crate) amnezia_settings: Option<&'a AmneziaSettings>,
+    pub(crate) pre_up: &'a [String],
+    pub(crate) pre_down: &'a [String],
+    pub(crate) post_up: &'a [String],
+    pub(crate) post_down: &'a [String],
+}
+
+/// Fields of `[Peer]` section, borrowed from [`Peer`] or [`PublicPeer`].
+pub(crate) struct PeerFields<'a> {
+    pub(crate) endpoint: Option<&'a str>,
+    pub(crate) allowed_ips: &'a [IpNet],
+    /// Written as `PublicKey`.
+    pub(crate) key: Either<&'a PrivateKey, &'a PublicKey>,
+    /// Written as `PresharedKey` or `PresharedKeyHash`.
+    pub(crate) preshared_key: Option<Either<&'a PresharedKey, &'a PresharedKeyHash>>,
+    pub(crate) persistent_keepalive: u16,
+}
+
+impl<'a> From<&'a Peer> for PeerFields<'a> {
+    fn from(peer: &'a Peer) -> Self {
+        Self {
+            endpoint: peer.endpoint.as_deref(),
+            allowed_ips: &peer.allowed_ips,
+            key: peer.key.as_ref(),
+            // externally managed keys are skipped
+            preshared_key: peer.exported_preshared_key().map(Either::Left),
+            persistent_keepalive: peer.persistent_keepalive,
+        }
+    }
+}
+
+/// Write full config: `[Interface]` section and all of the peers.
+pub(crate) fn write_interface<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    interface: &Interface,
+    options: &FormatOptions,
+) -> fmt::Result {
+    write_interface_section(f, interface, options)?;
+    for peer in &interface.peers {
+        write_peer_entry(f, &PeerFields::from(peer), options)?;
+    }
+
+    Ok(())
+}
+
+/// Write `[Interface]` section without peers.
+pub(crate) fn write_interface_section<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    interface: &Interface,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let key_commands = key_source_commands(interface, options);
+    let post_up = if key_commands.is_empty() {
+        Cow::Borrowed(interface.post_up.as_slice())
+    } else {
+        Cow::Owned([key_commands, interface.post_up.clone()].concat())
+    };
+
+    let fields = InterfaceFields {
+        address: &interface.address,
+        listen_port: interface.listen_port,
+        key: Either::Left(&interface.private_key),
+        dns: &interface.dns,
+        endpoint: interface.endpoint.as_deref(),
+        table: interface.table.as_ref(),
+        mtu: interface.mtu,
+        #[cfg(feature = "amneziawg")]
+        amnezia_settings: interface.amnezia_settings.as_ref(),
+        pre_up: &interface.pre_up,
+        pre_down: &interface.pre_down,
+        post_up: &post_up,
+        post_down: &interface.post_down,
+    };
+
+    write_interface_fields(f, &fields, options)
+}
+
+/// Write `[Interface]` section from its fields.
+pub(crate) fn write_interface_fields<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    fields: &InterfaceFields<'_>,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let sep = &*options.key_value_separator;
+
+    if let Some(header_comment) = &options.header_comment {
+        for line in header_comment.lines() {
+            writeln!(f, "# {line}")?;
+        }
+    }
+
+    writeln!(f, "[Interface]")?;
+    if options.name_comment {
+        if let Some(endpoint) = fields.endpoint {
+            writeln!(f, "# Name{sep}{endpoint}")?;
+        }
+    }
+    for key in options.interface_keys() {
+        match key {
+            InterfaceKey::Address => {
+                write!(f, "Address{sep}")?;
+                write_joined(f, fields.address, &options.list_separator, |f, ip| {
+                    write_address(f, ip, options.strip_host_prefix)
+                })?;
+                writeln!(f)?;
+            }
+            InterfaceKey::ListenPort => {
+                if let Some(listen_port) = fields.listen_port {
+                    writeln!(f, "ListenPort{sep}{listen_port}")?;
+                }
+            }
+            InterfaceKey::PrivateKey => match fields.key {
+                Either::Left(_) if options.private_key_source.is_some() => {
+                    // set by `PostUp`
+                }
+                Either::Left(_) if options.redact_secrets => {
+                    writeln!(f, "PrivateKey{sep}{REDACTED}")?;
+                }
+                Either::Left(private_key) => writeln!(f, "PrivateKey{sep}{private_key}")?,
+                Either::Right(public_key) => writeln!(f, "PublicKey{sep}{public_key}")?,
+            },
+            InterfaceKey::Dns => {
+                if !fields.dns.is_empty() {
+                    write!(f, "DNS{sep}")?;
+                    write_joined(f, fields.dns, &options.list_separator, |f, dns| {
+                        f.write_str(dns)
+                    })?;
+                    writeln!(f)?;
+                }
+            }
+            InterfaceKey::Table => {
+                if let Some(table) = fields.table {
+                    writeln!(f, "Table{sep}{table}")?;
+                }
+            }
+            InterfaceKey::Mtu => {
+                if let Some(mtu) = fields.mtu {
+                    writeln!(f, "MTU{sep}{mtu}")?;
+                }
+            }
+        }
+    }
+
+    write_scripts(f, "PreUp", fields.pre_up, options)?;
+    write_scripts(f, "PreDown", fields.pre_down, options)?;
+    write_scripts(f, "PostUp", fields.post_up, options)?;
+    write_scripts(f, "PostDown", fields.post_down, options)?;
+
+    #[cfg(feature = "amneziawg")]
+    if let Some(amnezia_settings) = fields.amnezia_settings {
+        if options.section_spacing {
+            writeln!(f)?;
+        }
+        amnezia_settings.write_keys(f, sep)?;
+        if options.section_spacing {
+            writeln!(f)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get `wg set` commands for keys, that are set indirectly via [`KeySource`]s.
+fn key_source_commands(interface: &Interface, options: &FormatOptions) -> Vec<String> {
+    let mut commands = Vec::new();
+
+    if let Some(source) = &options.private_key_source {
+        commands.push(format!("wg set %i private-key {}", source.shell_path()));
+    }
+    if !options.preshared_key_sources.is_empty() {
+        for peer in &interface.peers {
+            let public_key = peer.public_key();
+            if let Some(source) = options.preshared_key_source_of(&public_key) {
+                commands.push(format!(
+                    "wg set %i peer {public_key} preshared-key {}",
+                    source.shell_path()
+                ));
+            }
+        }
+    }
+
+    commands
+}
+
+/// Write `[Peer]` section, as it's placed in full config (surrounded by blank lines).
+pub(crate) fn write_peer_entry<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    fields: &PeerFields<'_>,
+    options: &FormatOptions,
+) -> fmt::Result {
+    if options.section_spacing {
+        writeln!(f)?;
+    }
+    write_peer_fields(f, fields, options)?;
+    if options.blank_line_after_peer {
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+/// Write `[Peer]` section.
+pub(crate) fn write_peer_section<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    peer: &Peer,
+    options: &FormatOptions,
+) -> fmt::Result {
+    write_peer_fields(f, &PeerFields::from(peer), options)
+}
+
+/// Write `[Peer]` section from its fields.
+pub(crate) fn write_peer_fields<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    fields: &PeerFields<'_>,
+    options: &FormatOptions,
+) -> fmt::Result {
+    let sep = &*options.key_value_separator;
+    let public_key = || match fields.key {
+        Either::Left(private_key) => private_key.public_key(),
+        Either::Right(public_key) => public_key.clone(),
+    };
+
+    writeln!(f, "[Peer]")?;
+    for key in options.peer_keys() {
+        match key {
+            PeerKey::Endpoint => {
+                if let Some(endpoint) = fields.endpoint {
+                    writeln!(f, "Endpoint{sep}{endpoint}")?;
+                }
+            }
+            PeerKey::AllowedIPs => {
+                write!(f, "AllowedIPs{sep}")?;
+                write_joined(f, fields.allowed_ips, &options.list_separator, |f, ip| {
+                    write!(f, "{ip}")
+                })?;
+                writeln!(f)?;
+            }
+            PeerKey::PublicKey => writeln!(f, "PublicKey{sep}{}", public_key())?,
+            PeerKey::PresharedKey => match fields.preshared_key {
+                Some(Either::Left(_))
+                    if !options.preshared_key_sources.is_empty()
+                        && options.preshared_key_source_of(&public_key()).is_some() =>
+                {
+                    // set by `PostUp` in `[Interface]` section
+                }
+                Some(Either::Left(_)) if options.redact_secrets => {
+                    writeln!(f, "PresharedKey{sep}{REDACTED}")?;
+                }
+                Some(Either::Left(preshared_key)) => {
+                    writeln!(f, "PresharedKey{sep}{preshared_key}")?;
+                }
+                Some(Either::Right(hash)) => writeln!(f, "PresharedKeyHash{sep}{hash}")?,
+                None => {}
+            },
+            PeerKey::PersistentKeepalive => {
+                if fields.persistent_keepalive != 0 {
+                    writeln!(f, "PersistentKeepalive{sep}{}", fields.persistent_keepalive)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 //! Re-exports of common structs.
 
 pub use crate::models::*;
+pub use crate::protocol::*;
 pub use crate::utils::*;
@@ -0,0 +1,496 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use super::handshake::{initiator_public_key, INITIATION_MAC1_OFFSET, RESPONSE_MAC1_OFFSET};
+use super::pcap::{read_datagrams, Datagram};
+use crate::prelude::*;
+
+/// Kind of captured packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PacketKind {
+    /// Message, encoded with interface's [`PacketCodec`].
+    Message(MessageType),
+
+    /// Vanilla WireGuard message, while interface uses AmneziaWG.
+    VanillaMessage(MessageType),
+
+    /// AmneziaWG junk packet (unrecognised packet with size in `Jmin..=Jmax`).
+    Junk,
+
+    /// Unrecognised packet.
+    Unknown,
+}
+
+/// Direction of captured packet relative to analyzed interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by interface.
+    Outgoing,
+
+    /// Received by interface.
+    Incoming,
+}
+
+/// Classified packet from capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Capture time (since Unix epoch).
+    pub timestamp: Duration,
+
+    /// Source address.
+    pub source: SocketAddr,
+
+    /// Destination address.
+    pub destination: SocketAddr,
+
+    /// Length of UDP payload.
+    pub len: usize,
+
+    /// Kind of packet.
+    pub kind: PacketKind,
+
+    /// Direction of packet, if known.
+    pub direction: Option<Direction>,
+
+    /// Peer, that sent or receives the packet, if known.
+    pub peer: Option<PublicKey>,
+}
+
+/// Handshake statistics of one peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerHandshakes {
+    /// Peer's public key.
+    pub public_key: PublicKey,
+
+    /// Handshake initiations, sent to peer.
+    pub initiations_sent: usize,
+
+    /// Handshake initiations, received from peer.
+    pub initiations_received: usize,
+
+    /// Handshake responses, sent to peer.
+    pub responses_sent: usize,
+
+    /// Handshake responses, received from peer.
+    pub responses_received: usize,
+
+    /// Handshakes, where response matches initiation.
+    pub completed: usize,
+
+    /// Time of last completed handshake.
+    pub last_handshake: Option<Duration>,
+
+    /// Peer's addresses, seen in handshakes.
+    pub endpoints: Vec<SocketAddr>,
+}
+
+impl PeerHandshakes {
+    fn new(public_key: PublicKey) -> Self {
+        Self {
+            public_key,
+            initiations_sent: 0,
+            initiations_received: 0,
+            responses_sent: 0,
+            responses_received: 0,
+            completed: 0,
+            last_handshake: None,
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Get count of handshake attempts (initiations in both directions).
+    #[must_use]
+    pub fn attempts(&self) -> usize {
+        self.initiations_sent + self.initiations_received
+    }
+}
+
+/// Sign, that obfuscation parameters of both sides don't match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObfuscationIssue {
+    /// Vanilla WireGuard messages were seen, but interface uses AmneziaWG.
+    VanillaMessages {
+        /// Count of messages.
+        count: usize,
+    },
+
+    /// Packets weren't recognised. Other side may use different `S1`, `S2` or `H1..H4` values
+    /// (or AmneziaWG, if interface doesn't).
+    UnknownPackets {
+        /// Count of packets.
+        count: usize,
+    },
+
+    /// Count of junk packets before handshake initiation isn't `Jc`.
+    JunkCount {
+        /// Interface's `Jc`.
+        expected: usize,
+
+        /// Observed count.
+        observed: usize,
+    },
+}
+
+/// Result of capture analysis. Created by [`Interface::analyze_capture()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureReport {
+    /// Classified packets of interface's UDP flows.
+    pub packets: Vec<CapturedPacket>,
+
+    /// Handshake statistics of interface's peers (in same order as [`Interface::peers`]).
+    pub peers: Vec<PeerHandshakes>,
+
+    /// Handshake messages, that can't be matched to any peer.
+    pub unmatched_handshakes: usize,
+
+    /// Obfuscation issues. Empty, if parameters look consistent.
+    pub issues: Vec<ObfuscationIssue>,
+}
+
+impl CaptureReport {
+    /// Get handshake statistics of peer with `public_key`.
+    #[must_use]
+    pub fn peer(&self, public_key: &PublicKey) -> Option<&PeerHandshakes> {
+        self.peers
+            .iter()
+            .find(|peer| &peer.public_key == public_key)
+    }
+
+    /// Check, if obfuscation parameters look consistent.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Count packets of `kind`.
+    #[must_use]
+    pub fn count(&self, kind: PacketKind) -> usize {
+        self.packets
+            .iter()
+            .filter(|packet| packet.kind == kind)
+            .count()
+    }
+}
+
+/// Get port of `host:port` endpoint.
+fn endpoint_port(endpoint: &str) -> Option<u16> {
+    endpoint.rsplit_once(':')?.1.parse().ok()
+}
+
+/// Receiver of handshake message, whose public key `mac1` is keyed by.
+enum Mac1Owner {
+    Interface,
+    Peer(usize),
+}
+
+struct Analyzer<'a> {
+    interface: &'a Interface,
+    public_key: PublicKey,
+    peer_keys: Vec<PublicKey>,
+    codec: PacketCodec,
+    vanilla: PacketCodec,
+    /// `(Jc, Jmin..=Jmax)`
+    junk: Option<(usize, std::ops::RangeInclusive<usize>)>,
+
+    report: CaptureReport,
+    /// Session index → peer index.
+    indices: HashMap<u32, usize>,
+    /// Sender indices of outgoing/incoming initiations, that wait for response.
+    pending_outgoing: HashMap<u32, usize>,
+    pending_incoming: HashMap<u32, usize>,
+    /// Consecutive junk packets per flow.
+    junk_counts: HashMap<(SocketAddr, SocketAddr), usize>,
+    junk_observed: Vec<usize>,
+}
+
+impl<'a> Analyzer<'a> {
+    fn new(interface: &'a Interface) -> WireguardResult<Self> {
+        #[cfg(feature = "amneziawg")]
+        let junk = interface
+            .amnezia_settings
+            .as_ref()
+            .map(|settings| (settings.jc, settings.jmin..=settings.jmax));
+        #[cfg(not(feature = "amneziawg"))]
+        let junk = None;
+
+        Ok(Self {
+            interface,
+            public_key: interface.private_key.public_key(),
+            peer_keys: interface.peers.iter().map(Peer::public_key).collect(),
+            codec: interface.packet_codec()?,
+            vanilla: PacketCodec::default(),
+            junk,
+
+            report: CaptureReport {
+                packets: Vec::new(),
+                peers: interface
+                    .peers
+                    .iter()
+                    .map(|peer| PeerHandshakes::new(peer.public_key()))
+                    .collect(),
+                unmatched_handshakes: 0,
+                issues: Vec::new(),
+            },
+            indices: HashMap::new(),
+            pending_outgoing: HashMap::new(),
+            pending_incoming: HashMap::new(),
+            junk_counts: HashMap::new(),
+            junk_observed: Vec::new(),
+        })
+    }
+
+    fn classify(&self, payload: &[u8]) -> PacketKind {
+        if let Some(message_type) = self.codec.classify(payload) {
+            return PacketKind::Message(message_type);
+        }
+        let Some((_, junk_range)) = &self.junk else {
+            return PacketKind::Unknown;
+        };
+
+        if let Some(message_type) = self.vanilla.classify(payload) {
+            PacketKind::VanillaMessage(message_type)
+        } else if junk_range.contains(&payload.len()) {
+            PacketKind::Junk
+        } else {
+            PacketKind::Unknown
+        }
+    }
+
+    /// Find key, that `mac1` of message (without junk prefix) is keyed by.
+    fn mac1_owner(&self, message: &[u8], mac1_offset: usize) -> Option<Mac1Owner> {
+        let mac1 = &message[mac1_offset..mac1_offset + 16];
+        let data = &message[..mac1_offset];
+
+        if compute_mac1(&self.public_key, data) == mac1 {
+            return Some(Mac1Owner::Interface);
+        }
+        self.peer_keys
+            .iter()
+            .position(|key| compute_mac1(key, data) == mac1)
+            .map(Mac1Owner::Peer)
+    }
+
+    fn add(&mut self, datagram: &Datagram<'_>) {
+        let kind = self.classify(datagram.payload);
+        let flow = (datagram.source, datagram.destination);
+
+        let (direction, peer) = match kind {
+            PacketKind::Message(message_type) | PacketKind::VanillaMessage(message_type) => {
+                let codec = if matches!(kind, PacketKind::Message(_)) {
+                    &self.codec
+                } else {
+                    &self.vanilla
+                };
+                let message = &datagram.payload[codec.padding(message_type)..];
+                match codec.decode(datagram.payload) {
+                    Ok(packet) => self.add_message(datagram, message, &packet),
+                    Err(_) => (None, None),
+                }
+            }
+            PacketKind::Junk | PacketKind::Unknown => (None, None),
+        };
+
+        if kind == PacketKind::Junk {
+            *self.junk_counts.entry(flow).or_default() += 1;
+        } else if let Some(count) = self.junk_counts.remove(&flow) {
+            if kind == PacketKind::Message(MessageType::HandshakeInitiation) {
+                self.junk_observed.push(count);
+            }
+        } else if kind == PacketKind::Message(MessageType::HandshakeInitiation) {
+            self.junk_observed.push(0);
+        }
+
+        self.report.packets.push(CapturedPacket {
+            timestamp: datagram.timestamp,
+            source: datagram.source,
+            destination: datagram.destination,
+            len: datagram.payload.len(),
+            kind,
+            direction,
+            peer: peer.map(|index| self.peer_keys[index].clone()),
+        });
+    }
+
+    /// Update handshake statistics. Returns direction and peer index of message.
+    fn add_message(
+        &mut self,
+        datagram: &Datagram<'_>,
+        message: &[u8],
+        packet: &Packet<'_>,
+    ) -> (Option<Direction>, Option<usize>) {
+        match packet {
+            Packet::HandshakeInitiation(initiation) => {
+                match self.mac1_owner(message, INITIATION_MAC1_OFFSET) {
+                    Some(Mac1Owner::Peer(peer)) => {
+                        self.report.peers[peer].initiations_sent += 1;
+                        self.add_endpoint(peer, datagram.destination);
+                        self.pending_outgoing.insert(initiation.sender, peer);
+                        self.indices.insert(initiation.sender, peer);
+                        (Some(Direction::Outgoing), Some(peer))
+                    }
+                    Some(Mac1Owner::Interface) => {
+                        let peer = message
+                            .get(..HANDSHAKE_INITIATION_LEN)
+                            .and_then(|message| message.try_into().ok())
+                            .and_then(|message| {
+                                initiator_public_key(&self.interface.private_key, message)
+                            })
+                            .and_then(|key| self.peer_keys.iter().position(|peer| peer == &key));
+
+                        if let Some(peer) = peer {
+                            self.report.peers[peer].initiations_received += 1;
+                            self.add_endpoint(peer, datagram.source);
+                            self.pending_incoming.insert(initiation.sender, peer);
+                            self.indices.insert(initiation.sender, peer);
+                        } else {
+                            self.report.unmatched_handshakes += 1;
+                        }
+                        (Some(Direction::Incoming), peer)
+                    }
+                    None => {
+                        self.report.unmatched_handshakes += 1;
+                        (None, None)
+                    }
+                }
+            }
+            Packet::HandshakeResponse(response) => {
+                match self.mac1_owner(message, RESPONSE_MAC1_OFFSET) {
+                    Some(Mac1Owner::Peer(peer)) => {
+                        self.report.peers[peer].responses_sent += 1;
+                        self.add_endpoint(peer, datagram.destination);
+                        if self.pending_incoming.remove(&response.receiver) == Some(peer) {
+                            self.complete(peer, datagram.timestamp);
+                        }
+                        self.indices.insert(response.sender, peer);
+                        (Some(Direction::Outgoing), Some(peer))
+                    }
+                    Some(Mac1Owner::Interface) => {
+                        let peer = self.pending_outgoing.remove(&response.receiver);
+                        if let Some(peer) = peer {
+                            self.report.peers[peer].responses_received += 1;
+                            self.add_endpoint(peer, datagram.source);
+                            self.complete(peer, datagram.timestamp);
+                            self.indices.insert(response.sender, peer);
+                        } else {
+                            self.report.unmatched_handshakes += 1;
+                        }
+                        (Some(Direction::Incoming), peer)
+                    }
+                    None => {
+                        self.report.unmatched_handshakes += 1;
+                        (None, None)
+                    }
+                }
+            }
+            Packet::CookieReply(_) | Packet::TransportData(_) => (
+                None,
+                packet
+                    .receiver()
+                    .and_then(|receiver| self.indices.get(&receiver).copied()),
+            ),
+        }
+    }
+
+    fn add_endpoint(&mut self, peer: usize, endpoint: SocketAddr) {
+        let endpoints = &mut self.report.peers[peer].endpoints;
+        if !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+
+    fn complete(&mut self, peer: usize, timestamp: Duration) {
+        let peer = &mut self.report.peers[peer];
+        peer.completed += 1;
+        peer.last_handshake = Some(timestamp);
+    }
+
+    fn finish(mut self) -> CaptureReport {
+        let vanilla = self
+            .report
+            .packets
+            .iter()
+            .filter(|packet| matches!(packet.kind, PacketKind::VanillaMessage(_)))
+            .count();
+        if vanilla > 0 {
+            self.report
+                .issues
+                .push(ObfuscationIssue::VanillaMessages { count: vanilla });
+        }
+
+        let unknown = self.report.count(PacketKind::Unknown);
+        if unknown > 0 {
+            self.report
+                .issues
+                .push(ObfuscationIssue::UnknownPackets { count: unknown });
+        }
+
+        if let Some((jc, _)) = self.junk {
+            let mut seen = HashSet::new();
+            for observed in self.junk_observed {
+                if observed != jc && seen.insert(observed) {
+                    self.report.issues.push(ObfuscationIssue::JunkCount {
+                        expected: jc,
+                        observed,
+                    });
+                }
+            }
+        }
+
+        self.report
+    }
+}
+
+impl Interface {
+    /// Analyze pcap or pcapng capture of interface's traffic.
+    ///
+    /// Only UDP flows on [`Interface::listen_port`] and peers' endpoint ports are analyzed (or
+    /// all UDP datagrams, if there are no ports). Packets are classified with
+    /// [`Interface::packet_codec()`]. Handshake messages are matched to peers by `mac1`;
+    /// received initiations are decrypted with interface's private key.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidCapture`] -- capture is malformed.
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- `H1..H4` don't fit into `u32`.
+    pub fn analyze_capture(&self, capture: &[u8]) -> WireguardResult<CaptureReport> {
+        let ports: HashSet<u16> = self
+            .listen_port
+            .into_iter()
+            .chain(
+                self.peers
+                    .iter()
+                    .filter_map(|peer| peer.endpoint.as_deref().and_then(endpoint_port)),
+            )
+            .collect();
+
+        let mut analyzer = Analyzer::new(self)?;
+        for datagram in read_datagrams(capture)? {
+            if ports.is_empty()
+                || ports.contains(&datagram.source.port())
+                || ports.contains(&datagram.destination.port())
+            {
+                analyzer.add(&datagram);
+            }
+        }
+
+        Ok(analyzer.finish())
+    }
+
+    /// Same as [`Interface::analyze_capture()`], but reads capture from file.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidCapture`] -- capture can't be read or is malformed.
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- `H1..H4` don't fit into `u32`.
+    pub fn analyze_capture_file<P: AsRef<Path>>(&self, path: P) -> WireguardResult<CaptureReport> {
+        let path = path.as_ref();
+        let capture = fs::read(path).map_err(|err| {
+            WireguardError::InvalidCapture(format!("can't read `{}`: {err}", path.display()))
+        })?;
+
+        self.analyze_capture(&capture)
+    }
+}
@@ -0,0 +1,599 @@
+//! Offline simulation of WireGuard handshake (`Noise_IKpsk2_25519_ChaChaPoly_BLAKE2s`).
+//!
+//! See [WireGuard whitepaper](https://www.wireguard.com/papers/wireguard.pdf), section 5.4.
+
+use blake2::digest::consts::U16;
+use blake2::digest::{Digest, KeyInit, Mac};
+use blake2::{Blake2s256, Blake2sMac};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hmac::SimpleHmac;
+use rand::CryptoRng;
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::prelude::*;
+use crate::utils::REDACTED;
+
+const CONSTRUCTION: &[u8] = b"Noise_IKpsk2_25519_ChaChaPoly_BLAKE2s";
+const IDENTIFIER: &[u8] = b"WireGuard v1 zx2c4 Jason@zx2c4.com";
+const LABEL_MAC1: &[u8] = b"mac1----";
+
+/// TAI64 label of Unix epoch (`2^62 + 10` leap seconds).
+const TAI64_EPOCH: u64 = 0x4000_0000_0000_000a;
+
+/// Offset of `mac1` in handshake initiation.
+pub(crate) const INITIATION_MAC1_OFFSET: usize = 116;
+
+/// Offset of `mac1` in handshake response.
+pub(crate) const RESPONSE_MAC1_OFFSET: usize = 60;
+
+type Key = Zeroizing<[u8; 32]>;
+
+fn hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Blake2s256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> Key {
+    let mut mac = <SimpleHmac<Blake2s256> as KeyInit>::new_from_slice(key)
+        .unwrap_or_else(|_| unreachable!("HMAC accepts keys of any length"));
+    for part in parts {
+        mac.update(part);
+    }
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+/// HKDF with HMAC-BLAKE2s, returning `N` (1..=3) keys.
+fn kdf<const N: usize>(chaining_key: &[u8; 32], input: &[u8]) -> [Key; N] {
+    let secret = hmac(chaining_key, &[input]);
+
+    let mut previous: Key = Zeroizing::new([0; 32]);
+    core::array::from_fn(|i| {
+        #[allow(clippy::cast_possible_truncation)]
+        let counter = [i as u8 + 1];
+        let output = if i == 0 {
+            hmac(secret.as_ref(), &[&counter])
+        } else {
+            hmac(secret.as_ref(), &[previous.as_ref(), &counter])
+        };
+        previous.clone_from(&output);
+        output
+    })
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+    ChaCha20Poly1305::new(key.into())
+        .encrypt(
+            &[0; 12].into(),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .unwrap_or_else(|_| unreachable!("encryption of short messages can't fail"))
+}
+
+fn open(key: &[u8; 32], ciphertext: &[u8], aad: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(
+            &[0; 12].into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+        .map(Zeroizing::new)
+}
+
+/// Get key for `mac1` of messages, sent to peer with `public_key`.
+#[must_use]
+pub fn mac1_key(public_key: &PublicKey) -> [u8; 32] {
+    hash(&[LABEL_MAC1, public_key.as_bytes()])
+}
+
+/// Compute `mac1` of message (without `mac1` and `mac2` fields), sent to peer with
+/// `public_key`.
+#[must_use]
+pub fn compute_mac1(public_key: &PublicKey, message: &[u8]) -> [u8; 16] {
+    let mut mac = <Blake2sMac<U16> as KeyInit>::new_from_slice(&mac1_key(public_key))
+        .unwrap_or_else(|_| unreachable!("32 bytes is valid BLAKE2s key length"));
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// Check `mac1` of handshake message (initiation, or response), sent to peer with `public_key`.
+///
+/// Returns `false` for messages of other types and lengths.
+#[must_use]
+pub fn verify_mac1(public_key: &PublicKey, packet: &[u8]) -> bool {
+    let offset = match (packet.first(), packet.len()) {
+        (Some(1), HANDSHAKE_INITIATION_LEN) => INITIATION_MAC1_OFFSET,
+        (Some(2), HANDSHAKE_RESPONSE_LEN) => RESPONSE_MAC1_OFFSET,
+        _ => return false,
+    };
+
+    compute_mac1(public_key, &packet[..offset]) == packet[offset..offset + 16]
+}
+
+fn tai64n(time: SystemTime) -> [u8; 12] {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    let mut timestamp = [0; 12];
+    timestamp[..8].copy_from_slice(&(TAI64_EPOCH + since_epoch.as_secs()).to_be_bytes());
+    timestamp[8..].copy_from_slice(&since_epoch.subsec_nanos().to_be_bytes());
+    timestamp
+}
+
+/// Encrypted field of handshake message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeField {
+    /// Initiator's static public key in handshake initiation.
+    Static,
+
+    /// Timestamp in handshake initiation.
+    Timestamp,
+
+    /// Empty payload of handshake response, which authenticates preshared key.
+    Empty,
+}
+
+impl fmt::Display for HandshakeField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Static => "static",
+            Self::Timestamp => "timestamp",
+            Self::Empty => "empty",
+        })
+    }
+}
+
+/// Reason, why handshake between two configs can't complete.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum HandshakeMismatch {
+    /// Client doesn't have peer for the server.
+    #[error("client has no peer for server ({server_public_key})")]
+    NoServerPeer {
+        /// Server's public key.
+        server_public_key: PublicKey,
+    },
+
+    /// Client's peer has wrong server's public key. Server drops handshake initiation, as its
+    /// `mac1` is invalid.
+    #[error(
+        "client's peer has public key {configured}, but server's public key is {actual} \
+         (server drops handshake initiation with invalid mac1)"
+    )]
+    ServerPublicKeyMismatch {
+        /// Public key in client's peer.
+        configured: PublicKey,
+
+        /// Actual server's public key.
+        actual: PublicKey,
+    },
+
+    /// Server doesn't have peer with client's public key.
+    #[error("server has no peer with client's public key {client_public_key}")]
+    UnknownClient {
+        /// Client's public key.
+        client_public_key: PublicKey,
+    },
+
+    /// Preshared keys of client and server are different (or set only on one side). Client
+    /// can't decrypt handshake response.
+    #[error(
+        "preshared keys of client and server don't match (client can't decrypt handshake response)"
+    )]
+    PresharedKeyMismatch {
+        /// Hash of client's preshared key.
+        client: Option<PresharedKeyHash>,
+
+        /// Hash of server's preshared key.
+        server: Option<PresharedKeyHash>,
+    },
+
+    /// Public key is low-order point, so Diffie-Hellman result is zero and WireGuard rejects it.
+    #[error("public key {public_key} is invalid (low-order point)")]
+    InvalidPublicKey {
+        /// Invalid public key.
+        public_key: PublicKey,
+    },
+
+    /// Field of handshake message can't be decrypted. Shouldn't happen with keys, that passed
+    /// previous checks.
+    #[error("can't decrypt `{field}` field of handshake message")]
+    DecryptionFailed {
+        /// Field, which can't be decrypted.
+        field: HandshakeField,
+    },
+}
+
+/// Transport data keys of one side of the tunnel.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct TransportKeys {
+    sending: [u8; 32],
+    receiving: [u8; 32],
+}
+
+impl TransportKeys {
+    /// Key for encrypting outgoing packets.
+    #[must_use]
+    pub fn sending(&self) -> &[u8; 32] {
+        &self.sending
+    }
+
+    /// Key for decrypting incoming packets.
+    #[must_use]
+    pub fn receiving(&self) -> &[u8; 32] {
+        &self.receiving
+    }
+}
+
+/// Redacted, doesn't expose the keys.
+impl fmt::Debug for TransportKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportKeys")
+            .field("sending", &format_args!("{REDACTED}"))
+            .field("receiving", &format_args!("{REDACTED}"))
+            .finish()
+    }
+}
+
+/// Result of successful handshake simulation. Created by [`Interface::simulate_handshake()`].
+#[derive(Clone, Debug)]
+pub struct HandshakeTranscript {
+    /// Handshake initiation message, sent by client.
+    pub initiation: [u8; HANDSHAKE_INITIATION_LEN],
+
+    /// Handshake response message, sent by server.
+    pub response: [u8; HANDSHAKE_RESPONSE_LEN],
+
+    /// Client's transport keys.
+    pub client_keys: TransportKeys,
+
+    /// Server's transport keys.
+    pub server_keys: TransportKeys,
+}
+
+impl HandshakeTranscript {
+    /// Check, that client's sending key is server's receiving key, and vice versa.
+    #[must_use]
+    pub fn keys_match(&self) -> bool {
+        self.client_keys.sending == self.server_keys.receiving
+            && self.client_keys.receiving == self.server_keys.sending
+    }
+}
+
+/// Diffie-Hellman, that rejects low-order public keys.
+fn dh(private_key: &PrivateKey, public_key: &PublicKey) -> Result<Key, HandshakeMismatch> {
+    let shared = private_key.diffie_hellman(public_key);
+    if *shared == [0; 32] {
+        return Err(HandshakeMismatch::InvalidPublicKey {
+            public_key: public_key.clone(),
+        });
+    }
+    Ok(shared)
+}
+
+/// Noise handshake state: chaining key and hash.
+struct State {
+    chaining_key: Key,
+    hash: [u8; 32],
+}
+
+impl State {
+    fn new(responder_public_key: &PublicKey) -> Self {
+        let chaining_key = hash(&[CONSTRUCTION]);
+        let hash = hash(&[
+            &hash(&[&chaining_key, IDENTIFIER]),
+            responder_public_key.as_bytes(),
+        ]);
+
+        Self {
+            chaining_key: Zeroizing::new(chaining_key),
+            hash,
+        }
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        self.hash = hash(&[&self.hash, data]);
+    }
+
+    fn mix_key(&mut self, input: &[u8]) {
+        let [chaining_key] = kdf(&self.chaining_key, input);
+        self.chaining_key = chaining_key;
+    }
+
+    fn mix_key_and_get(&mut self, input: &[u8]) -> Key {
+        let [chaining_key, key] = kdf(&self.chaining_key, input);
+        self.chaining_key = chaining_key;
+        key
+    }
+
+    fn mix_psk(&mut self, psk: Option<&PresharedKey>) -> Key {
+        let psk = psk.map_or([0; 32], |psk| *psk.as_bytes());
+        let [chaining_key, tau, key] = kdf(&self.chaining_key, &psk);
+        self.chaining_key = chaining_key;
+        self.mix_hash(tau.as_ref());
+        key
+    }
+
+    fn transport_keys(&self, initiator: bool) -> TransportKeys {
+        let [first, second] = kdf(&self.chaining_key, &[]);
+        if initiator {
+            TransportKeys {
+                sending: *first,
+                receiving: *second,
+            }
+        } else {
+            TransportKeys {
+                sending: *second,
+                receiving: *first,
+            }
+        }
+    }
+}
+
+impl Interface {
+    /// Simulate WireGuard handshake between this interface (client, initiator) and `server`
+    /// (responder), without sending any packets.
+    ///
+    /// Client's peer for the server is the one with server's public key. If there is no such
+    /// peer and client has exactly one peer, it's used (and mismatch of public keys is
+    /// reported).
+    ///
+    /// # Errors
+    ///
+    /// [`HandshakeMismatch`] -- reason, why handshake can't complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// # use wireguard_conf::as_ipnet;
+    /// let mut server = InterfaceBuilder::new()
+    ///     .address([as_ipnet!("10.0.0.1/24")])
+    ///     .peers([PeerBuilder::new()
+    ///         .allowed_ips([as_ipnet!("10.0.0.2/32")])
+    ///         .preshared_key(PresharedKey::random())
+    ///         .build()])
+    ///     .build();
+    /// let mut client = server.peers[0]
+    ///     .to_interface(&server, ToInterfaceOptions::new())
+    ///     .unwrap();
+    ///
+    /// // `to_interface()` doesn't copy preshared key
+    /// assert!(matches!(
+    ///     client.simulate_handshake(&server),
+    ///     Err(HandshakeMismatch::PresharedKeyMismatch { .. })
+    /// ));
+    ///
+    /// client.peers[0].preshared_key = server.peers[0].preshared_key.clone();
+    /// assert!(client.simulate_handshake(&server).unwrap().keys_match());
+    /// ```
+    pub fn simulate_handshake(
+        &self,
+        server: &Interface,
+    ) -> Result<HandshakeTranscript, HandshakeMismatch> {
+        self.simulate_handshake_with_rng(server, &mut rand::rng())
+    }
+
+    /// Same as [`Interface::simulate_handshake()`], but uses provided `rng` for ephemeral keys
+    /// and indices.
+    ///
+    /// # Errors
+    ///
+    /// [`HandshakeMismatch`] -- reason, why handshake can't complete.
+    pub fn simulate_handshake_with_rng<R: CryptoRng + ?Sized>(
+        &self,
+        server: &Interface,
+        rng: &mut R,
+    ) -> Result<HandshakeTranscript, HandshakeMismatch> {
+        let server_public_key = server.private_key.public_key();
+        let server_peer = self
+            .peers
+            .iter()
+            .find(|peer| peer.public_key() == server_public_key)
+            .or(match self.peers.as_slice() {
+                [peer] => Some(peer),
+                _ => None,
+            })
+            .ok_or_else(|| HandshakeMismatch::NoServerPeer {
+                server_public_key: server_public_key.clone(),
+            })?;
+
+        // client: create initiation
+        let (initiation, mut client_state, client_ephemeral) =
+            create_initiation(&self.private_key, &server_peer.public_key(), rng)?;
+
+        // server: consume initiation
+        if !verify_mac1(&server_public_key, &initiation) {
+            return Err(HandshakeMismatch::ServerPublicKeyMismatch {
+                configured: server_peer.public_key(),
+                actual: server_public_key,
+            });
+        }
+        let (mut server_state, client_public_key, client_ephemeral_public) =
+            consume_initiation(&server.private_key, &initiation)?;
+        let client_peer = server
+            .peers
+            .iter()
+            .find(|peer| peer.public_key() == client_public_key)
+            .ok_or(HandshakeMismatch::UnknownClient { client_public_key })?;
+
+        // server: create response
+        let response = create_response(
+            &mut server_state,
+            &initiation,
+            &client_peer.public_key(),
+            &client_ephemeral_public,
+            client_peer.preshared_key.as_ref(),
+            rng,
+        )?;
+
+        // client: consume response
+        consume_response(
+            &mut client_state,
+            &self.private_key,
+            &client_ephemeral,
+            &response,
+            server_peer.preshared_key.as_ref(),
+        )
+        .map_err(|err| match err {
+            HandshakeMismatch::DecryptionFailed {
+                field: HandshakeField::Empty,
+            } => HandshakeMismatch::PresharedKeyMismatch {
+                client: server_peer.preshared_key.as_ref().map(PresharedKey::hash),
+                server: client_peer.preshared_key.as_ref().map(PresharedKey::hash),
+            },
+            err => err,
+        })?;
+
+        Ok(HandshakeTranscript {
+            initiation,
+            response,
+            client_keys: client_state.transport_keys(true),
+            server_keys: server_state.transport_keys(false),
+        })
+    }
+}
+
+type Initiation = ([u8; HANDSHAKE_INITIATION_LEN], State, PrivateKey);
+
+fn create_initiation<R: CryptoRng + ?Sized>(
+    private_key: &PrivateKey,
+    server_public_key: &PublicKey,
+    rng: &mut R,
+) -> Result<Initiation, HandshakeMismatch> {
+    let mut message = [0u8; HANDSHAKE_INITIATION_LEN];
+    message[0] = 1;
+    message[4..8].copy_from_slice(&rng.next_u32().to_le_bytes());
+
+    let mut state = State::new(server_public_key);
+
+    let ephemeral = PrivateKey::random_with_rng(rng);
+    let ephemeral_public = ephemeral.public_key();
+    state.mix_key(ephemeral_public.as_bytes());
+    state.mix_hash(ephemeral_public.as_bytes());
+    message[8..40].copy_from_slice(ephemeral_public.as_bytes());
+
+    let key = state.mix_key_and_get(dh(&ephemeral, server_public_key)?.as_ref());
+    let encrypted_static = seal(&key, private_key.public_key().as_bytes(), &state.hash);
+    state.mix_hash(&encrypted_static);
+    message[40..88].copy_from_slice(&encrypted_static);
+
+    let key = state.mix_key_and_get(dh(private_key, server_public_key)?.as_ref());
+    let encrypted_timestamp = seal(&key, &tai64n(SystemTime::now()), &state.hash);
+    state.mix_hash(&encrypted_timestamp);
+    message[88..116].copy_from_slice(&encrypted_timestamp);
+
+    let mac1 = compute_mac1(server_public_key, &message[..INITIATION_MAC1_OFFSET]);
+    message[INITIATION_MAC1_OFFSET..INITIATION_MAC1_OFFSET + 16].copy_from_slice(&mac1);
+
+    Ok((message, state, ephemeral))
+}
+
+fn consume_initiation(
+    private_key: &PrivateKey,
+    message: &[u8; HANDSHAKE_INITIATION_LEN],
+) -> Result<(State, PublicKey, PublicKey), HandshakeMismatch> {
+    let mut state = State::new(&private_key.public_key());
+
+    let ephemeral_public =
+        PublicKey::from(<[u8; 32]>::try_from(&message[8..40]).unwrap_or_default());
+    state.mix_key(ephemeral_public.as_bytes());
+    state.mix_hash(ephemeral_public.as_bytes());
+
+    let key = state.mix_key_and_get(dh(private_key, &ephemeral_public)?.as_ref());
+    let client_public_key = open(&key, &message[40..88], &state.hash)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .map(PublicKey::from)
+        .ok_or(HandshakeMismatch::DecryptionFailed {
+            field: HandshakeField::Static,
+        })?;
+    state.mix_hash(&message[40..88]);
+
+    let key = state.mix_key_and_get(dh(private_key, &client_public_key)?.as_ref());
+    open(&key, &message[88..116], &state.hash).ok_or(HandshakeMismatch::DecryptionFailed {
+        field: HandshakeField::Timestamp,
+    })?;
+    state.mix_hash(&message[88..116]);
+
+    Ok((state, client_public_key, ephemeral_public))
+}
+
+fn create_response<R: CryptoRng + ?Sized>(
+    state: &mut State,
+    initiation: &[u8; HANDSHAKE_INITIATION_LEN],
+    client_public_key: &PublicKey,
+    client_ephemeral_public: &PublicKey,
+    preshared_key: Option<&PresharedKey>,
+    rng: &mut R,
+) -> Result<[u8; HANDSHAKE_RESPONSE_LEN], HandshakeMismatch> {
+    let mut message = [0u8; HANDSHAKE_RESPONSE_LEN];
+    message[0] = 2;
+    message[4..8].copy_from_slice(&rng.next_u32().to_le_bytes());
+    message[8..12].copy_from_slice(&initiation[4..8]);
+
+    let ephemeral = PrivateKey::random_with_rng(rng);
+    let ephemeral_public = ephemeral.public_key();
+    state.mix_key(ephemeral_public.as_bytes());
+    state.mix_hash(ephemeral_public.as_bytes());
+    message[12..44].copy_from_slice(ephemeral_public.as_bytes());
+
+    state.mix_key(dh(&ephemeral, client_ephemeral_public)?.as_ref());
+    state.mix_key(dh(&ephemeral, client_public_key)?.as_ref());
+
+    let key = state.mix_psk(preshared_key);
+    let encrypted_empty = seal(&key, &[], &state.hash);
+    state.mix_hash(&encrypted_empty);
+    message[44..60].copy_from_slice(&encrypted_empty);
+
+    let mac1 = compute_mac1(client_public_key, &message[..RESPONSE_MAC1_OFFSET]);
+    message[RESPONSE_MAC1_OFFSET..RESPONSE_MAC1_OFFSET + 16].copy_from_slice(&mac1);
+
+    Ok(message)
+}
+
+fn consume_response(
+    state: &mut State,
+    private_key: &PrivateKey,
+    ephemeral: &PrivateKey,
+    message: &[u8; HANDSHAKE_RESPONSE_LEN],
+    preshared_key: Option<&PresharedKey>,
+) -> Result<(), HandshakeMismatch> {
+    let ephemeral_public =
+        PublicKey::from(<[u8; 32]>::try_from(&message[12..44]).unwrap_or_default());
+    state.mix_key(ephemeral_public.as_bytes());
+    state.mix_hash(ephemeral_public.as_bytes());
+
+    state.mix_key(dh(ephemeral, &ephemeral_public)?.as_ref());
+    state.mix_key(dh(private_key, &ephemeral_public)?.as_ref());
+
+    let key = state.mix_psk(preshared_key);
+    open(&key, &message[44..60], &state.hash).ok_or(HandshakeMismatch::DecryptionFailed {
+        field: HandshakeField::Empty,
+    })?;
+    state.mix_hash(&message[44..60]);
+
+    Ok(())
+}
+
+/// Decrypt initiator's static public key from handshake initiation, received by interface with
+/// `private_key`. Message type field isn't authenticated, so it may be AmneziaWG's one.
+#[cfg(feature = "pcap")]
+pub(crate) fn initiator_public_key(
+    private_key: &PrivateKey,
+    message: &[u8; HANDSHAKE_INITIATION_LEN],
+) -> Option<PublicKey> {
+    consume_initiation(private_key, message)
+        .ok()
+        .map(|(_, public_key, _)| public_key)
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "pcap")]
+mod analyzer;
+#[cfg(feature = "handshake")]
+mod handshake;
+mod packet;
+#[cfg(feature = "pcap")]
+mod pcap;
+
+#[cfg(feature = "pcap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pcap")))]
+pub use analyzer::*;
+
+#[cfg(feature = "handshake")]
+#[cfg_attr(docsrs, doc(cfg(feature = "handshake")))]
+pub use handshake::*;
+
+pub use packet::*;
@@ -0,0 +1,422 @@
+//! Encoding and decoding of WireGuard (and AmneziaWG) messages.
+//!
+//! See [WireGuard whitepaper](https://www.wireguard.com/papers/wireguard.pdf), section 5.4.
+
+use rand::Rng;
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::prelude::*;
+
+/// Length of handshake initiation message.
+pub const HANDSHAKE_INITIATION_LEN: usize = 148;
+
+/// Length of handshake response message.
+pub const HANDSHAKE_RESPONSE_LEN: usize = 92;
+
+/// Length of cookie reply message.
+pub const COOKIE_REPLY_LEN: usize = 64;
+
+/// Minimal length of transport data message (keepalive: header and empty encrypted packet).
+pub const TRANSPORT_DATA_MIN_LEN: usize = 32;
+
+/// WireGuard message type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageType {
+    /// Handshake initiation, sent by initiator.
+    HandshakeInitiation,
+
+    /// Handshake response, sent by responder.
+    HandshakeResponse,
+
+    /// Cookie reply, sent by responder under load.
+    CookieReply,
+
+    /// Transport data (encrypted IP packet or keepalive).
+    TransportData,
+}
+
+impl MessageType {
+    /// All message types in order of their vanilla WireGuard values.
+    pub const ALL: [MessageType; 4] = [
+        MessageType::HandshakeInitiation,
+        MessageType::HandshakeResponse,
+        MessageType::CookieReply,
+        MessageType::TransportData,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            MessageType::HandshakeInitiation => 0,
+            MessageType::HandshakeResponse => 1,
+            MessageType::CookieReply => 2,
+            MessageType::TransportData => 3,
+        }
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MessageType::HandshakeInitiation => "handshake initiation",
+            MessageType::HandshakeResponse => "handshake response",
+            MessageType::CookieReply => "cookie reply",
+            MessageType::TransportData => "transport data",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Handshake initiation message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeInitiation {
+    /// Initiator's session index.
+    pub sender: u32,
+
+    /// Initiator's ephemeral public key.
+    pub ephemeral: PublicKey,
+
+    /// Encrypted initiator's static public key.
+    pub encrypted_static: [u8; 48],
+
+    /// Encrypted TAI64N timestamp.
+    pub encrypted_timestamp: [u8; 28],
+
+    /// MAC, keyed by responder's public key.
+    pub mac1: [u8; 16],
+
+    /// MAC, keyed by cookie (zeros, if there is no cookie).
+    pub mac2: [u8; 16],
+}
+
+/// Handshake response message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeResponse {
+    /// Responder's session index.
+    pub sender: u32,
+
+    /// Initiator's session index.
+    pub receiver: u32,
+
+    /// Responder's ephemeral public key.
+    pub ephemeral: PublicKey,
+
+    /// Encrypted empty payload (authentication tag only).
+    pub encrypted_nothing: [u8; 16],
+
+    /// MAC, keyed by initiator's public key.
+    pub mac1: [u8; 16],
+
+    /// MAC, keyed by cookie (zeros, if there is no cookie).
+    pub mac2: [u8; 16],
+}
+
+/// Cookie reply message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CookieReply {
+    /// Session index of handshake message sender.
+    pub receiver: u32,
+
+    /// XChaCha20Poly1305 nonce.
+    pub nonce: [u8; 24],
+
+    /// Encrypted cookie.
+    pub encrypted_cookie: [u8; 32],
+}
+
+/// Transport data message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransportData<'a> {
+    /// Receiver's session index.
+    pub receiver: u32,
+
+    /// Nonce counter.
+    pub counter: u64,
+
+    /// Encrypted IP packet (empty for keepalive) with authentication tag.
+    pub encrypted_packet: &'a [u8],
+}
+
+impl TransportData<'_> {
+    /// Check, if message is keepalive (encrypted packet is empty).
+    #[must_use]
+    pub fn is_keepalive(&self) -> bool {
+        self.encrypted_packet.len() == 16
+    }
+}
+
+/// Decoded WireGuard message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Packet<'a> {
+    /// Handshake initiation.
+    HandshakeInitiation(HandshakeInitiation),
+
+    /// Handshake response.
+    HandshakeResponse(HandshakeResponse),
+
+    /// Cookie reply.
+    CookieReply(CookieReply),
+
+    /// Transport data.
+    TransportData(TransportData<'a>),
+}
+
+impl Packet<'_> {
+    /// Get message type.
+    #[must_use]
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            Packet::HandshakeInitiation(_) => MessageType::HandshakeInitiation,
+            Packet::HandshakeResponse(_) => MessageType::HandshakeResponse,
+            Packet::CookieReply(_) => MessageType::CookieReply,
+            Packet::TransportData(_) => MessageType::TransportData,
+        }
+    }
+
+    /// Get receiver's session index (`None` for handshake initiation).
+    #[must_use]
+    pub fn receiver(&self) -> Option<u32> {
+        match self {
+            Packet::HandshakeInitiation(_) => None,
+            Packet::HandshakeResponse(message) => Some(message.receiver),
+            Packet::CookieReply(message) => Some(message.receiver),
+            Packet::TransportData(message) => Some(message.receiver),
+        }
+    }
+}
+
+/// Encoder/decoder of WireGuard messages.
+///
+/// Vanilla WireGuard ([`PacketCodec::default()`]) uses message types `1..=4` and no padding.
+/// AmneziaWG replaces message types with `H1..H4` values (or ranges since AmneziaWG 2.0), and
+/// prepends `S1`/`S2` random bytes to handshake initiation/response (and `S3`/`S4` to cookie
+/// reply/transport data since AmneziaWG 2.0).
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// let codec = PacketCodec::default();
+///
+/// let mut keepalive = [0u8; 32];
+/// keepalive[0] = 4;
+///
+/// assert_eq!(codec.classify(&keepalive), Some(MessageType::TransportData));
+/// assert!(matches!(
+///     codec.decode(&keepalive),
+///     Ok(Packet::TransportData(data)) if data.is_keepalive()
+/// ));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PacketCodec {
+    message_types: [RangeInclusive<u32>; 4],
+    paddings: [usize; 4],
+}
+
+impl Default for PacketCodec {
+    fn default() -> Self {
+        Self {
+            message_types: [1..=1, 2..=2, 3..=3, 4..=4],
+            paddings: [0; 4],
+        }
+    }
+}
+
+impl PacketCodec {
+    /// Create codec for AmneziaWG settings.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- settings are invalid (see
+    ///   [`AmneziaSettings::validate()`]), or `H1..H4` don't fit into `u32`.
+    #[cfg(feature = "amneziawg")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+    pub fn amneziawg(settings: &AmneziaSettings) -> WireguardResult<Self> {
+        settings.validate()?;
+
+        let header = |(name, header): (&str, MagicHeader)| {
+            let value = |value: usize| {
+                u32::try_from(value)
+                    .map_err(|_| WireguardError::InvalidAmneziaSetting(name.to_string()))
+            };
+
+            Ok(value(header.start)?..=value(header.end)?)
+        };
+        let [h1, h2, h3, h4] = settings.headers();
+
+        Ok(Self {
+            message_types: [header(h1)?, header(h2)?, header(h3)?, header(h4)?],
+            paddings: [settings.s1, settings.s2, settings.s3, settings.s4],
+        })
+    }
+
+    /// Get range of message type field values for `message_type`.
+    #[must_use]
+    pub fn message_type_range(&self, message_type: MessageType) -> RangeInclusive<u32> {
+        self.message_types[message_type.index()].clone()
+    }
+
+    /// Get length of junk prefix for `message_type`.
+    #[must_use]
+    pub fn padding(&self, message_type: MessageType) -> usize {
+        self.paddings[message_type.index()]
+    }
+
+    /// Classify packet (UDP payload) by its length and message type field.
+    ///
+    /// Returns `None` if packet isn't WireGuard message for this codec.
+    #[must_use]
+    pub fn classify(&self, packet: &[u8]) -> Option<MessageType> {
+        let has_type = |message_type: MessageType| {
+            let offset = self.padding(message_type);
+            packet
+                .get(offset..offset + 4)
+                .map(|field| u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+                .is_some_and(|value| self.message_types[message_type.index()].contains(&value))
+        };
+        let is_len = |message_type: MessageType, len: usize| {
+            packet.len() == self.padding(message_type) + len && has_type(message_type)
+        };
+
+        if is_len(MessageType::HandshakeInitiation, HANDSHAKE_INITIATION_LEN) {
+            Some(MessageType::HandshakeInitiation)
+        } else if is_len(MessageType::HandshakeResponse, HANDSHAKE_RESPONSE_LEN) {
+            Some(MessageType::HandshakeResponse)
+        } else if is_len(MessageType::CookieReply, COOKIE_REPLY_LEN) {
+            Some(MessageType::CookieReply)
+        } else if packet.len() >= self.padding(MessageType::TransportData) + TRANSPORT_DATA_MIN_LEN
+            && has_type(MessageType::TransportData)
+        {
+            Some(MessageType::TransportData)
+        } else {
+            None
+        }
+    }
+
+    /// Decode packet (UDP payload). Junk prefix is skipped.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidPacket`] -- packet isn't WireGuard message for this codec.
+    pub fn decode<'a>(&self, packet: &'a [u8]) -> WireguardResult<Packet<'a>> {
+        let message_type = self.classify(packet).ok_or_else(|| {
+            WireguardError::InvalidPacket(format!(
+                "unknown message ({} bytes, type field {})",
+                packet.len(),
+                packet
+                    .get(..4)
+                    .map_or("missing".to_string(), |field| format!("{field:02x?}"))
+            ))
+        })?;
+
+        let message = &packet[self.padding(message_type)..];
+        let packet = match message_type {
+            MessageType::HandshakeInitiation => Packet::HandshakeInitiation(HandshakeInitiation {
+                sender: read_u32(message, 4),
+                ephemeral: PublicKey::from(read_array(message, 8)),
+                encrypted_static: read_array(message, 40),
+                encrypted_timestamp: read_array(message, 88),
+                mac1: read_array(message, 116),
+                mac2: read_array(message, 132),
+            }),
+            MessageType::HandshakeResponse => Packet::HandshakeResponse(HandshakeResponse {
+                sender: read_u32(message, 4),
+                receiver: read_u32(message, 8),
+                ephemeral: PublicKey::from(read_array(message, 12)),
+                encrypted_nothing: read_array(message, 44),
+                mac1: read_array(message, 60),
+                mac2: read_array(message, 76),
+            }),
+            MessageType::CookieReply => Packet::CookieReply(CookieReply {
+                receiver: read_u32(message, 4),
+                nonce: read_array(message, 8),
+                encrypted_cookie: read_array(message, 32),
+            }),
+            MessageType::TransportData => Packet::TransportData(TransportData {
+                receiver: read_u32(message, 4),
+                counter: u64::from_le_bytes(read_array(message, 8)),
+                encrypted_packet: &message[16..],
+            }),
+        };
+
+        Ok(packet)
+    }
+
+    /// Encode message. Junk prefix is filled with random bytes, message type field is random value
+    /// from range.
+    #[must_use]
+    pub fn encode(&self, packet: &Packet<'_>) -> Vec<u8> {
+        self.encode_with_rng(packet, &mut rand::rng())
+    }
+
+    /// Same as [`PacketCodec::encode()`], but uses provided `rng` for junk prefix and message type.
+    pub fn encode_with_rng<R: Rng + ?Sized>(&self, packet: &Packet<'_>, rng: &mut R) -> Vec<u8> {
+        let message_type = packet.message_type();
+
+        let mut buf = vec![0u8; self.padding(message_type)];
+        rng.fill_bytes(&mut buf);
+        let value = rng.random_range(self.message_type_range(message_type));
+        buf.extend_from_slice(&value.to_le_bytes());
+
+        match packet {
+            Packet::HandshakeInitiation(message) => {
+                buf.extend_from_slice(&message.sender.to_le_bytes());
+                buf.extend_from_slice(message.ephemeral.as_bytes());
+                buf.extend_from_slice(&message.encrypted_static);
+                buf.extend_from_slice(&message.encrypted_timestamp);
+                buf.extend_from_slice(&message.mac1);
+                buf.extend_from_slice(&message.mac2);
+            }
+            Packet::HandshakeResponse(message) => {
+                buf.extend_from_slice(&message.sender.to_le_bytes());
+                buf.extend_from_slice(&message.receiver.to_le_bytes());
+                buf.extend_from_slice(message.ephemeral.as_bytes());
+                buf.extend_from_slice(&message.encrypted_nothing);
+                buf.extend_from_slice(&message.mac1);
+                buf.extend_from_slice(&message.mac2);
+            }
+            Packet::CookieReply(message) => {
+                buf.extend_from_slice(&message.receiver.to_le_bytes());
+                buf.extend_from_slice(&message.nonce);
+                buf.extend_from_slice(&message.encrypted_cookie);
+            }
+            Packet::TransportData(message) => {
+                buf.extend_from_slice(&message.receiver.to_le_bytes());
+                buf.extend_from_slice(&message.counter.to_le_bytes());
+                buf.extend_from_slice(message.encrypted_packet);
+            }
+        }
+
+        buf
+    }
+}
+
+/// Read `N` bytes at `offset`. Caller must check length.
+fn read_array<const N: usize>(message: &[u8], offset: usize) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(&message[offset..offset + N]);
+    array
+}
+
+fn read_u32(message: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(read_array(message, offset))
+}
+
+impl Interface {
+    /// Get [`PacketCodec`] for decoding this interface's traffic: AmneziaWG codec, if
+    /// [`Interface::amnezia_settings`] are set, vanilla WireGuard otherwise.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::InvalidAmneziaSetting`] -- AmneziaWG settings are invalid.
+    pub fn packet_codec(&self) -> WireguardResult<PacketCodec> {
+        #[cfg(feature = "amneziawg")]
+        if let Some(settings) = &self.amnezia_settings {
+            return PacketCodec::amneziawg(settings);
+        }
+
+        Ok(PacketCodec::default())
+    }
+}
@@ -0,0 +1,335 @@
+//! Minimal pcap/pcapng reader, that extracts UDP datagrams.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use crate::prelude::*;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+const IP_PROTOCOL_UDP: u8 = 17;
+
+/// UDP datagram from capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Datagram<'a> {
+    /// Time since Unix epoch.
+    pub(crate) timestamp: Duration,
+    pub(crate) source: SocketAddr,
+    pub(crate) destination: SocketAddr,
+    pub(crate) payload: &'a [u8],
+}
+
+fn invalid(reason: &str) -> WireguardError {
+    WireguardError::InvalidCapture(reason.to_string())
+}
+
+/// Byte reader with runtime endianness.
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: usize, len: usize) -> WireguardResult<&'a [u8]> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or_else(|| invalid("unexpected end of file"))
+    }
+
+    fn u16(&self, offset: usize) -> WireguardResult<u16> {
+        let bytes = self.bytes(offset, 2)?.try_into().unwrap_or_default();
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> WireguardResult<u32> {
+        let bytes = self.bytes(offset, 4)?.try_into().unwrap_or_default();
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn len(&self, offset: usize) -> WireguardResult<usize> {
+        usize::try_from(self.u32(offset)?).map_err(|_| invalid("length is too big"))
+    }
+}
+
+/// Read UDP datagrams from pcap or pcapng file. Non-UDP and fragmented packets are skipped.
+///
+/// # Errors
+///
+/// - [`WireguardError::InvalidCapture`] -- file is malformed or has unsupported link type.
+pub(crate) fn read_datagrams(capture: &[u8]) -> WireguardResult<Vec<Datagram<'_>>> {
+    let magic = capture
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+        .ok_or_else(|| invalid("file is too short"))?;
+
+    if magic == PCAPNG_SECTION_HEADER {
+        read_pcapng(capture)
+    } else {
+        read_pcap(capture)
+    }
+}
+
+fn read_pcap(capture: &[u8]) -> WireguardResult<Vec<Datagram<'_>>> {
+    let mut reader = Reader {
+        data: capture,
+        big_endian: false,
+    };
+    let nanos = match reader.u32(0)? {
+        PCAP_MAGIC_MICROS => false,
+        PCAP_MAGIC_NANOS => true,
+        magic if magic.swap_bytes() == PCAP_MAGIC_MICROS => {
+            reader.big_endian = true;
+            false
+        }
+        magic if magic.swap_bytes() == PCAP_MAGIC_NANOS => {
+            reader.big_endian = true;
+            true
+        }
+        _ => return Err(invalid("unknown file format (expected pcap or pcapng)")),
+    };
+    let link_type = reader.u32(20)? & 0x0fff_ffff;
+
+    let mut datagrams = Vec::new();
+    let mut offset = 24;
+    while offset < capture.len() {
+        let seconds = reader.u32(offset)?;
+        let fraction = reader.u32(offset + 4)?;
+        let captured_len = reader.len(offset + 8)?;
+        let frame = reader.bytes(offset + 16, captured_len)?;
+        offset += 16 + captured_len;
+
+        let timestamp = Duration::from_secs(seconds.into())
+            + if nanos {
+                Duration::from_nanos(fraction.into())
+            } else {
+                Duration::from_micros(fraction.into())
+            };
+        if let Some(datagram) = parse_frame(link_type, frame, timestamp)? {
+            datagrams.push(datagram);
+        }
+    }
+
+    Ok(datagrams)
+}
+
+fn read_pcapng(capture: &[u8]) -> WireguardResult<Vec<Datagram<'_>>> {
+    let mut reader = Reader {
+        data: capture,
+        big_endian: false,
+    };
+
+    // (link type, units per second)
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+    let mut datagrams = Vec::new();
+    let mut offset = 0;
+    while offset < capture.len() {
+        if reader.u32(offset)? == PCAPNG_SECTION_HEADER {
+            let byte_order = reader.bytes(offset + 8, 4)?;
+            reader.big_endian = match u32::from_le_bytes(byte_order.try_into().unwrap_or_default())
+            {
+                PCAPNG_BYTE_ORDER_MAGIC => false,
+                magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+                _ => return Err(invalid("invalid pcapng byte-order magic")),
+            };
+            interfaces.clear();
+        }
+
+        let block_type = reader.u32(offset)?;
+        let block_len = reader.len(offset + 4)?;
+        if block_len < 12 || block_len % 4 != 0 {
+            return Err(invalid("invalid pcapng block length"));
+        }
+        let block = Reader {
+            data: reader.bytes(offset, block_len)?,
+            big_endian: reader.big_endian,
+        };
+        offset += block_len;
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = u32::from(block.u16(8)?);
+                let resolution = interface_resolution(&block, block_len)?;
+                interfaces.push((link_type, resolution));
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let interface_id = block.len(8)?;
+                let &(link_type, resolution) = interfaces
+                    .get(interface_id)
+                    .ok_or_else(|| invalid("packet refers to unknown interface"))?;
+                let units = (u64::from(block.u32(12)?) << 32) | u64::from(block.u32(16)?);
+                let captured_len = block.len(20)?;
+                let frame = block.bytes(28, captured_len)?;
+
+                let nanos = u128::from(units % resolution) * 1_000_000_000 / u128::from(resolution);
+                let timestamp = Duration::from_secs(units / resolution)
+                    + Duration::from_nanos(u64::try_from(nanos).unwrap_or_default());
+                if let Some(datagram) = parse_frame(link_type, frame, timestamp)? {
+                    datagrams.push(datagram);
+                }
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                if block_len < 16 {
+                    return Err(invalid("invalid pcapng block length"));
+                }
+                let &(link_type, _) = interfaces
+                    .first()
+                    .ok_or_else(|| invalid("packet refers to unknown interface"))?;
+                let original_len = block.len(8)?;
+                let frame = block.bytes(12, original_len.min(block_len - 16))?;
+
+                if let Some(datagram) = parse_frame(link_type, frame, Duration::ZERO)? {
+                    datagrams.push(datagram);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(datagrams)
+}
+
+/// Get timestamp resolution (units per second) from `if_tsresol` option.
+fn interface_resolution(block: &Reader<'_>, block_len: usize) -> WireguardResult<u64> {
+    let mut offset = 16;
+    while offset + 4 <= block_len - 4 {
+        let code = block.u16(offset)?;
+        let len = usize::from(block.u16(offset + 2)?);
+        if code == 0 {
+            break;
+        }
+        if code == PCAPNG_OPTION_TSRESOL && len == 1 {
+            let value = block.bytes(offset + 4, 1)?[0];
+            let exponent = u32::from(value & 0x7f);
+            let base: u64 = if value & 0x80 == 0 { 10 } else { 2 };
+            return base
+                .checked_pow(exponent)
+                .filter(|resolution| *resolution > 0)
+                .ok_or_else(|| invalid("unsupported timestamp resolution"));
+        }
+        offset += 4 + len.next_multiple_of(4);
+    }
+
+    Ok(1_000_000)
+}
+
+fn parse_frame(
+    link_type: u32,
+    frame: &[u8],
+    timestamp: Duration,
+) -> WireguardResult<Option<Datagram<'_>>> {
+    let packet = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = read_be_u16(frame, offset);
+            while matches!(ethertype, Some(ETHERTYPE_VLAN | ETHERTYPE_QINQ)) {
+                offset += 4;
+                ethertype = read_be_u16(frame, offset);
+            }
+            if !matches!(ethertype, Some(ETHERTYPE_IPV4 | ETHERTYPE_IPV6)) {
+                return Ok(None);
+            }
+            frame.get(offset + 2..)
+        }
+        LINKTYPE_NULL | LINKTYPE_LOOP => frame.get(4..),
+        LINKTYPE_LINUX_SLL => frame.get(16..),
+        LINKTYPE_LINUX_SLL2 => frame.get(20..),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(frame),
+        _ => {
+            return Err(WireguardError::InvalidCapture(format!(
+                "unsupported link type {link_type}"
+            )))
+        }
+    };
+
+    Ok(packet.and_then(|packet| parse_ip(packet, timestamp)))
+}
+
+fn read_be_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn parse_ip(packet: &[u8], timestamp: Duration) -> Option<Datagram<'_>> {
+    let version = packet.first()? >> 4;
+    let (source, destination, udp) = match version {
+        4 => {
+            let header_len = usize::from(packet[0] & 0x0f) * 4;
+            let total_len = usize::from(read_be_u16(packet, 2)?);
+            let fragment = read_be_u16(packet, 6)?;
+            // more fragments flag, or non-zero offset
+            if packet.get(9)? != &IP_PROTOCOL_UDP || fragment & 0x3fff != 0 {
+                return None;
+            }
+
+            let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::V4(Ipv4Addr::from(source)),
+                IpAddr::V4(Ipv4Addr::from(destination)),
+                packet.get(header_len..total_len.min(packet.len()))?,
+            )
+        }
+        6 => {
+            // extension headers aren't supported
+            if packet.get(6)? != &IP_PROTOCOL_UDP {
+                return None;
+            }
+            let payload_len = usize::from(read_be_u16(packet, 4)?);
+
+            let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                packet.get(40..(40 + payload_len).min(packet.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    if udp.len() < 8 {
+        return None;
+    }
+    let source_port = read_be_u16(udp, 0)?;
+    let destination_port = read_be_u16(udp, 2)?;
+    let udp_len = usize::from(read_be_u16(udp, 4)?);
+
+    Some(Datagram {
+        timestamp,
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        payload: udp.get(8..udp_len.min(udp.len()))?,
+    })
+}
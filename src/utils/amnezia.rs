@@ -1,18 +1,126 @@
 use rand::prelude::*;
-use std::{collections::HashSet, fmt};
+use std::{fmt, ops::RangeInclusive, str::FromStr};
 
 #[cfg(feature = "serde")]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 use serde::{Deserialize, Serialize};
 
-use super::{WireguardError, WireguardResult};
+use super::{AmneziaSettingsBuilder, SignaturePacket, WireguardError, WireguardResult};
 
-macro_rules! assert_return {
-    ($test:expr, $err:expr) => {
-        if !($test) {
-            return Err($err);
+/// AmneziaWG protocol version. Controls which keys are rendered and allowed by
+/// [`AmneziaSettings::validate()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum AmneziaVersion {
+    /// AmneziaWG 1.0: `Jc`, `Jmin`, `Jmax`, `S1`, `S2` and single-value `H1..H4`.
+    V1,
+
+    /// AmneziaWG 1.5: adds signature packets `I1..I5`, `J1..J3` and `Itime`.
+    #[default]
+    V1_5,
+
+    /// AmneziaWG 2.0: adds `S3`/`S4` padding and `H1..H4` ranges.
+    V2,
+}
+
+/// AmneziaWG magic header (`H1..H4`): single value, or range of values (AmneziaWG 2.0), f.e.
+/// `100000-200000`.
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// let header: MagicHeader = "100000-200000".parse().unwrap();
+///
+/// assert_eq!(header, MagicHeader::from(100_000..=200_000));
+/// assert!(header.contains(150_000));
+/// assert_eq!(MagicHeader::from(42).to_string(), "42");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MagicHeader {
+    /// First value (inclusive).
+    pub start: usize,
+
+    /// Last value (inclusive).
+    pub end: usize,
+}
+
+impl MagicHeader {
+    /// Check, if header is range of several values.
+    #[must_use]
+    pub fn is_range(&self) -> bool {
+        self.start != self.end
+    }
+
+    /// Check, if `value` is in header's range.
+    #[must_use]
+    pub fn contains(&self, value: usize) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    /// Check, if ranges of two headers have common values.
+    #[must_use]
+    pub fn overlaps(&self, other: &MagicHeader) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Get header's range.
+    #[must_use]
+    pub fn range(&self) -> RangeInclusive<usize> {
+        self.start..=self.end
+    }
+}
+
+impl From<usize> for MagicHeader {
+    fn from(value: usize) -> Self {
+        Self {
+            start: value,
+            end: value,
         }
-    };
+    }
+}
+
+impl From<RangeInclusive<usize>> for MagicHeader {
+    fn from(range: RangeInclusive<usize>) -> Self {
+        Self {
+            start: *range.start(),
+            end: *range.end(),
+        }
+    }
+}
+
+impl fmt::Display for MagicHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_range() {
+            write!(f, "{}-{}", self.start, self.end)
+        } else {
+            write!(f, "{}", self.start)
+        }
+    }
+}
+
+/// Parses `value` or `start-end`.
+impl FromStr for MagicHeader {
+    type Err = WireguardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || WireguardError::InvalidAmneziaSetting(format!("magic header `{s}`"));
+        let parse = |value: &str| value.trim().parse::<usize>().map_err(|_| invalid());
+
+        let header = match s.split_once('-') {
+            Some((start, end)) => Self {
+                start: parse(start)?,
+                end: parse(end)?,
+            },
+            None => Self::from(parse(s)?),
+        };
+        if header.start > header.end {
+            return Err(invalid());
+        }
+
+        Ok(header)
+    }
 }
 
 /// AmneziaWG obfuscation values.
@@ -22,6 +130,10 @@ macro_rules! assert_return {
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct AmneziaSettings {
+    /// protocol version; controls which keys are rendered
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: AmneziaVersion,
+
     /// 1 ≤ Jc ≤ 128; recommended range is from 3 to 10 inclusive
     pub jc: usize,
 
@@ -33,20 +145,60 @@ pub struct AmneziaSettings {
     pub s1: usize,
     /// S2 < 1280; recommended range is from 15 to 150 inclusive
     pub s2: usize,
+    /// S3 < 1280; padding of cookie reply messages (AmneziaWG 2.0)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub s3: usize,
+    /// S4 < 1280; padding of transport data messages (AmneziaWG 2.0)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub s4: usize,
+
+    /// must not overlap with each other; ranges are allowed since AmneziaWG 2.0; recommended
+    /// range is from 5 to 2147483647 inclusive
+    pub h1: MagicHeader,
+    /// must not overlap with each other; ranges are allowed since AmneziaWG 2.0; recommended
+    /// range is from 5 to 2147483647 inclusive
+    pub h2: MagicHeader,
+    /// must not overlap with each other; ranges are allowed since AmneziaWG 2.0; recommended
+    /// range is from 5 to 2147483647 inclusive
+    pub h3: MagicHeader,
+    /// must not overlap with each other; ranges are allowed since AmneziaWG 2.0; recommended
+    /// range is from 5 to 2147483647 inclusive
+    pub h4: MagicHeader,
 
-    /// must be unique among each other; recommended range is from 5 to 2147483647 inclusive
-    pub h1: usize,
-    /// must be unique among each other; recommended range is from 5 to 2147483647 inclusive
-    pub h2: usize,
-    /// must be unique among each other; recommended range is from 5 to 2147483647 inclusive
-    pub h3: usize,
-    /// must be unique among each other; recommended range is from 5 to 2147483647 inclusive
-    pub h4: usize,
+    /// first signature packet, sent before handshake initiation (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i1: Option<SignaturePacket>,
+    /// second signature packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i2: Option<SignaturePacket>,
+    /// third signature packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i3: Option<SignaturePacket>,
+    /// fourth signature packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i4: Option<SignaturePacket>,
+    /// fifth signature packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i5: Option<SignaturePacket>,
+
+    /// first controlled junk packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub j1: Option<SignaturePacket>,
+    /// second controlled junk packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub j2: Option<SignaturePacket>,
+    /// third controlled junk packet (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub j3: Option<SignaturePacket>,
+    /// interval of sending signature packets in seconds (AmneziaWG 1.5)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub itime: Option<u64>,
 }
 
 /// Methods
 impl AmneziaSettings {
-    /// Generate [`AmneziaSettings`] with randomized values, based of recommended ranges or values.
+    /// Generate [`AmneziaSettings`] with randomized values, based of recommended ranges or values
+    /// ([`AmneziaPreset::Balanced`](super::AmneziaPreset::Balanced)).
     ///
     /// # Examples
     ///
@@ -61,38 +213,42 @@ impl AmneziaSettings {
     ///    .build();
     /// ```
     pub fn random() -> Self {
-        let mut rng = rand::rng();
-
-        let jc = rng.random_range(4..=12);
-        let jmin = 8;
-        let jmax = 80;
-        let s1 = rng.random_range(15..=150);
-        let s2 = {
-            let mut value = s1 + 56;
-
-            while s1 + 56 == value {
-                value = rng.random_range(1..=150);
-            }
-
-            value
-        };
+        Self::random_with_rng(&mut rand::rng())
+    }
 
-        let h1 = rng.random_range(10..=2_147_483_640);
-        let h2 = rng.random_range(10..=2_147_483_640);
-        let h3 = rng.random_range(10..=2_147_483_640);
-        let h4 = rng.random_range(10..=2_147_483_640);
+    /// Generate [`AmneziaSettings`] with randomized values using provided `rng`.
+    ///
+    /// Same as [`AmneziaSettings::random()`], but reproducible with seeded RNG.
+    ///
+    /// Unlike keys, obfuscation values aren't secret (they're written into every client config),
+    /// so any [`Rng`] is accepted, not only [`rand::CryptoRng`].
+    // balanced preset ranges always contain valid values
+    #[allow(clippy::missing_panics_doc)]
+    pub fn random_with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        AmneziaSettingsBuilder::new()
+            .build_with_rng(rng)
+            .expect("balanced preset is always valid")
+    }
 
-        Self {
-            jc,
-            jmin,
-            jmax,
-            s1,
-            s2,
-            h1,
-            h2,
-            h3,
-            h4,
-        }
+    /// Create new [`AmneziaSettingsBuilder`]. Alias for `AmneziaSettingsBuilder::new()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wireguard_conf::prelude::*;
+    ///
+    /// let settings = AmneziaSettings::builder()
+    ///     .preset(AmneziaPreset::Aggressive)
+    ///     .version(AmneziaVersion::V2)
+    ///     .header_width(1000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(settings.h1.is_range());
+    /// ```
+    #[must_use]
+    pub fn builder() -> AmneziaSettingsBuilder {
+        AmneziaSettingsBuilder::new()
     }
 
     /// Validates [`AmneziaSettings`].
@@ -102,40 +258,146 @@ impl AmneziaSettings {
     /// If [`AmneziaSettings`] is invalid, it will throw [`WireguardError::InvalidAmneziaSetting`]
     /// with setting name
     pub fn validate(&self) -> WireguardResult<()> {
-        assert_return!(
-            1 <= self.jc && self.jc <= 128,
-            WireguardError::InvalidAmneziaSetting("Jc".to_string())
-        );
-
-        assert_return!(
-            self.jmin < self.jmax,
-            WireguardError::InvalidAmneziaSetting("Jmin".to_string())
-        );
-        assert_return!(
-            self.jmax <= 1280,
-            WireguardError::InvalidAmneziaSetting("Jmax".to_string())
-        );
-        assert_return!(
-            self.s1 < 1280 && self.s1 + 56 != self.s2,
-            WireguardError::InvalidAmneziaSetting("S1".to_string())
-        );
-        assert_return!(
-            self.s2 < 1280,
-            WireguardError::InvalidAmneziaSetting("S2".to_string())
-        );
-
-        let are_h_values_unique = {
-            let set = HashSet::from([self.h1, self.h2, self.h3, self.h4]);
-
-            set.len() == 4
-        };
-        assert_return!(
-            are_h_values_unique,
-            WireguardError::InvalidAmneziaSetting("H1/H2/H3/H4".to_string())
-        );
+        match self.diagnose(None).errors().next() {
+            Some(error) => Err(WireguardError::InvalidAmneziaSetting(error.setting.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Get magic headers (`H1..H4`) with their names.
+    #[must_use]
+    pub fn headers(&self) -> [(&'static str, MagicHeader); 4] {
+        [
+            ("H1", self.h1),
+            ("H2", self.h2),
+            ("H3", self.h3),
+            ("H4", self.h4),
+        ]
+    }
+
+    /// Get set signature packets (`I1..I5`, `J1..J3`) with their names.
+    pub fn signature_packets(&self) -> impl Iterator<Item = (&'static str, &SignaturePacket)> {
+        [
+            ("I1", &self.i1),
+            ("I2", &self.i2),
+            ("I3", &self.i3),
+            ("I4", &self.i4),
+            ("I5", &self.i5),
+            ("J1", &self.j1),
+            ("J2", &self.j2),
+            ("J3", &self.j3),
+        ]
+        .into_iter()
+        .filter_map(|(name, packet)| Some((name, packet.as_ref()?)))
+    }
+}
+
+impl AmneziaSettings {
+    /// Get `(key, value)` pairs, supported by [`AmneziaSettings::version`], in config order.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = (&'static str, &dyn fmt::Display)> {
+        let is_v1_5 = self.version >= AmneziaVersion::V1_5;
+        let is_v2 = self.version >= AmneziaVersion::V2;
+
+        let junk: [(&'static str, &dyn fmt::Display); 5] = [
+            ("Jc", &self.jc),
+            ("Jmin", &self.jmin),
+            ("Jmax", &self.jmax),
+            ("S1", &self.s1),
+            ("S2", &self.s2),
+        ];
+        let paddings: [(&'static str, &dyn fmt::Display); 2] = [("S3", &self.s3), ("S4", &self.s4)];
+        let headers: [(&'static str, &dyn fmt::Display); 4] = [
+            ("H1", &self.h1),
+            ("H2", &self.h2),
+            ("H3", &self.h3),
+            ("H4", &self.h4),
+        ];
+        let packets = self
+            .signature_packets()
+            .filter(move |_| is_v1_5)
+            .map(|(name, packet)| (name, packet as &dyn fmt::Display));
+        let itime = self
+            .itime
+            .as_ref()
+            .filter(|_| is_v1_5)
+            .map(|itime| ("Itime", itime as &dyn fmt::Display));
+
+        junk.into_iter()
+            .chain(paddings.into_iter().filter(move |_| is_v2))
+            .chain(headers)
+            .chain(packets)
+            .chain(itime)
+    }
+
+    /// Write `Key<separator>value` lines (see [`AmneziaSettings::keys`]).
+    pub(crate) fn write_keys<W: fmt::Write + ?Sized>(&self, f: &mut W, sep: &str) -> fmt::Result {
+        for (key, value) in self.keys() {
+            writeln!(f, "{key}{sep}{value}")?;
+        }
 
         Ok(())
     }
+
+    /// Read settings from `Key -> value` lookup (keys are same, as [`AmneziaSettings::keys`]
+    /// yields) and validate them.
+    ///
+    /// Missing `Jc..S4` are `0`, empty signature packets are ignored. Version is
+    /// [`AmneziaVersion::V2`], if `S3`/`S4` are set or some header is range.
+    #[cfg(any(feature = "amneziavpn", feature = "singbox", feature = "clash"))]
+    pub(crate) fn read_keys(value: impl Fn(&str) -> Option<String>) -> WireguardResult<Self> {
+        let invalid = |key: &str| WireguardError::InvalidAmneziaSetting(key.to_string());
+        let number = |key: &str| -> WireguardResult<usize> {
+            value(key).map_or(Ok(0), |value| value.parse().map_err(|_| invalid(key)))
+        };
+        let header = |key: &str| -> WireguardResult<MagicHeader> {
+            value(key).ok_or_else(|| invalid(key))?.parse()
+        };
+        let packet = |key: &str| -> WireguardResult<Option<SignaturePacket>> {
+            value(key)
+                .filter(|packet| !packet.is_empty())
+                .map(|packet| packet.parse().map_err(|_| invalid(key)))
+                .transpose()
+        };
+
+        let headers = [header("H1")?, header("H2")?, header("H3")?, header("H4")?];
+        let is_v2 = value("S3").is_some()
+            || value("S4").is_some()
+            || headers.iter().any(MagicHeader::is_range);
+        let [h1, h2, h3, h4] = headers;
+
+        let settings = AmneziaSettings {
+            version: if is_v2 {
+                AmneziaVersion::V2
+            } else {
+                AmneziaVersion::default()
+            },
+            jc: number("Jc")?,
+            jmin: number("Jmin")?,
+            jmax: number("Jmax")?,
+            s1: number("S1")?,
+            s2: number("S2")?,
+            s3: number("S3")?,
+            s4: number("S4")?,
+            h1,
+            h2,
+            h3,
+            h4,
+            i1: packet("I1")?,
+            i2: packet("I2")?,
+            i3: packet("I3")?,
+            i4: packet("I4")?,
+            i5: packet("I5")?,
+            j1: packet("J1")?,
+            j2: packet("J2")?,
+            j3: packet("J3")?,
+            itime: value("Itime")
+                .map(|itime| itime.parse().map_err(|_| invalid("Itime")))
+                .transpose()?,
+        };
+        settings.validate()?;
+
+        Ok(settings)
+    }
 }
 
 /// Implements [`fmt::Display`] for exporting AmneziaWG values.
@@ -145,16 +407,6 @@ impl AmneziaSettings {
 /// It exports only [`Jc = ..., Jmin = ..., etc`]. To export full interface, use `Interface.to_string()`.
 impl fmt::Display for AmneziaSettings {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "Jc = {}", self.jc)?;
-        writeln!(f, "Jmin = {}", self.jmin)?;
-        writeln!(f, "Jmax = {}", self.jmax)?;
-        writeln!(f, "S1 = {}", self.s1)?;
-        writeln!(f, "S2 = {}", self.s2)?;
-        writeln!(f, "H1 = {}", self.h1)?;
-        writeln!(f, "H2 = {}", self.h2)?;
-        writeln!(f, "H3 = {}", self.h3)?;
-        writeln!(f, "H4 = {}", self.h4)?;
-
-        Ok(())
+        self.write_keys(f, " = ")
     }
 }
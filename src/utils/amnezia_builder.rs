@@ -0,0 +1,326 @@
+use rand::prelude::*;
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use super::{AmneziaSettings, AmneziaVersion, MagicHeader, WireguardError, WireguardResult};
+
+/// Named set of ranges for [`AmneziaSettingsBuilder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum AmneziaPreset {
+    /// Few small junk packets and little padding. Suitable for mobile data, where every byte
+    /// counts.
+    Light,
+
+    /// Values within recommended ranges: `Jc` 4-10, `S1`/`S2` 15-150, fixed `Jmin`/`Jmax` of
+    /// 8/80. Used by [`AmneziaSettings::random()`].
+    #[default]
+    Balanced,
+
+    /// Many large junk packets and heavy padding. Suitable for restrictive networks with DPI.
+    Aggressive,
+}
+
+/// Builder of randomized [`AmneziaSettings`].
+///
+/// Every value is picked from a range, configured by [`AmneziaPreset`] or by setters. Ranges are
+/// clamped to values, allowed by [`AmneziaSettings::validate()`], so built settings are always
+/// valid.
+///
+/// `S3`/`S4` and header ranges are generated only for [`AmneziaVersion::V2`]; older versions get
+/// `0` and single-value headers.
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// let settings = AmneziaSettingsBuilder::new()
+///     .preset(AmneziaPreset::Light)
+///     .jc(3..=3)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(settings.jc, 3);
+/// assert!(settings.validate().is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AmneziaSettingsBuilder {
+    version: AmneziaVersion,
+    jc: RangeInclusive<usize>,
+    jmin: RangeInclusive<usize>,
+    jmax: RangeInclusive<usize>,
+    s1: RangeInclusive<usize>,
+    s2: RangeInclusive<usize>,
+    s3: RangeInclusive<usize>,
+    s4: RangeInclusive<usize>,
+    headers: RangeInclusive<usize>,
+    header_width: usize,
+}
+
+impl Default for AmneziaSettingsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<AmneziaPreset> for AmneziaSettingsBuilder {
+    fn from(preset: AmneziaPreset) -> Self {
+        let (jc, jmin, jmax, s1, s2, s3, s4) = match preset {
+            AmneziaPreset::Light => (3..=4, 10..=20, 30..=50, 15..=32, 15..=32, 0..=16, 0..=4),
+            AmneziaPreset::Balanced => (4..=10, 8..=8, 80..=80, 15..=150, 15..=150, 1..=64, 1..=16),
+            AmneziaPreset::Aggressive => (
+                8..=10,
+                64..=128,
+                512..=1024,
+                64..=150,
+                64..=150,
+                32..=128,
+                16..=32,
+            ),
+        };
+
+        Self {
+            version: AmneziaVersion::default(),
+            jc,
+            jmin,
+            jmax,
+            s1,
+            s2,
+            s3,
+            s4,
+            headers: 10..=2_147_483_640,
+            header_width: 1,
+        }
+    }
+}
+
+impl AmneziaSettingsBuilder {
+    /// Create new [`AmneziaSettingsBuilder`] with [`AmneziaPreset::Balanced`] ranges.
+    #[must_use]
+    pub fn new() -> Self {
+        AmneziaPreset::default().into()
+    }
+
+    /// Replace `Jc`, `Jmin`, `Jmax` and `S1..S4` ranges with `preset` ones.
+    #[must_use]
+    pub fn preset(self, preset: AmneziaPreset) -> Self {
+        Self {
+            version: self.version,
+            headers: self.headers,
+            header_width: self.header_width,
+            ..preset.into()
+        }
+    }
+
+    /// Sets protocol version of generated settings.
+    #[must_use]
+    pub fn version(mut self, value: AmneziaVersion) -> Self {
+        self.version = value;
+        self
+    }
+
+    /// Sets range of junk packets count (`Jc`).
+    #[must_use]
+    pub fn jc(mut self, value: RangeInclusive<usize>) -> Self {
+        self.jc = value;
+        self
+    }
+
+    /// Sets range of minimal junk packet size (`Jmin`).
+    #[must_use]
+    pub fn jmin(mut self, value: RangeInclusive<usize>) -> Self {
+        self.jmin = value;
+        self
+    }
+
+    /// Sets range of maximal junk packet size (`Jmax`).
+    #[must_use]
+    pub fn jmax(mut self, value: RangeInclusive<usize>) -> Self {
+        self.jmax = value;
+        self
+    }
+
+    /// Sets range of handshake initiation padding (`S1`).
+    #[must_use]
+    pub fn s1(mut self, value: RangeInclusive<usize>) -> Self {
+        self.s1 = value;
+        self
+    }
+
+    /// Sets range of handshake response padding (`S2`).
+    #[must_use]
+    pub fn s2(mut self, value: RangeInclusive<usize>) -> Self {
+        self.s2 = value;
+        self
+    }
+
+    /// Sets range of cookie reply padding (`S3`). Used only for [`AmneziaVersion::V2`].
+    #[must_use]
+    pub fn s3(mut self, value: RangeInclusive<usize>) -> Self {
+        self.s3 = value;
+        self
+    }
+
+    /// Sets range of transport data padding (`S4`). Used only for [`AmneziaVersion::V2`].
+    #[must_use]
+    pub fn s4(mut self, value: RangeInclusive<usize>) -> Self {
+        self.s4 = value;
+        self
+    }
+
+    /// Sets range, in which magic headers (`H1..H4`) are placed.
+    #[must_use]
+    pub fn headers(mut self, value: RangeInclusive<usize>) -> Self {
+        self.headers = value;
+        self
+    }
+
+    /// Sets number of values in each magic header. `0` is treated as `1`.
+    ///
+    /// Used only for [`AmneziaVersion::V2`], older versions support only single values.
+    #[must_use]
+    pub fn header_width(mut self, value: usize) -> Self {
+        self.header_width = value.max(1);
+        self
+    }
+
+    /// Build [`AmneziaSettings`].
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidAmneziaSetting`] with setting name -- range doesn't contain any
+    /// valid value.
+    pub fn build(&self) -> WireguardResult<AmneziaSettings> {
+        self.build_with_rng(&mut rand::rng())
+    }
+
+    /// Same as [`AmneziaSettingsBuilder::build()`], but uses provided `rng`.
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidAmneziaSetting`] with setting name -- range doesn't contain any
+    /// valid value.
+    pub fn build_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> WireguardResult<AmneziaSettings> {
+        let is_v2 = self.version >= AmneziaVersion::V2;
+
+        let jc = pick(rng, "Jc", &self.jc, 1..=128, &[])?;
+
+        // jmax first: it must leave room for jmin below it
+        let jmax_start = (*self.jmax.start()).max(self.jmin.start().saturating_add(1));
+        let jmax = pick(rng, "Jmax", &(jmax_start..=*self.jmax.end()), 0..=1280, &[])?;
+        let jmin = pick(rng, "Jmin", &self.jmin, 0..=jmax - 1, &[])?;
+
+        let s1 = pick(rng, "S1", &self.s1, 0..=1279, &[])?;
+        let s2 = pick(rng, "S2", &self.s2, 0..=1279, &[s1 + 56])?;
+        let (s3, s4) = if is_v2 {
+            (
+                pick(rng, "S3", &self.s3, 0..=1279, &[s1 + 84, s2 + 28])?,
+                pick(rng, "S4", &self.s4, 0..=1279, &[])?,
+            )
+        } else {
+            (0, 0)
+        };
+
+        let width = if is_v2 { self.header_width } else { 1 };
+        let [h1, h2, h3, h4] = self.pick_headers(rng, width)?;
+
+        Ok(AmneziaSettings {
+            version: self.version,
+            jc,
+            jmin,
+            jmax,
+            s1,
+            s2,
+            s3,
+            s4,
+            h1,
+            h2,
+            h3,
+            h4,
+            i1: None,
+            i2: None,
+            i3: None,
+            i4: None,
+            i5: None,
+            j1: None,
+            j2: None,
+            j3: None,
+            itime: None,
+        })
+    }
+
+    /// Split headers range into 4 slots and place one header into each, in random order.
+    fn pick_headers<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        width: usize,
+    ) -> WireguardResult<[MagicHeader; 4]> {
+        let start = *self.headers.start() as u64;
+        let end = (*self.headers.end() as u64).min(u64::from(u32::MAX));
+        let width = width as u64;
+
+        let slot = if start <= end {
+            (end - start + 1) / 4
+        } else {
+            0
+        };
+        if slot < width {
+            return Err(WireguardError::InvalidAmneziaSetting(
+                "H1/H2/H3/H4".to_string(),
+            ));
+        }
+
+        let mut headers = [0, 1, 2, 3].map(|i| {
+            let header_start = start + i * slot + rng.random_range(0..=slot - width);
+
+            // values fit into `u32`, checked above
+            #[allow(clippy::cast_possible_truncation)]
+            MagicHeader::from(header_start as usize..=(header_start + width - 1) as usize)
+        });
+        headers.shuffle(rng);
+
+        Ok(headers)
+    }
+}
+
+/// Pick random value from intersection of `range` and `allowed`, except `excluded` values.
+fn pick<R: Rng + ?Sized>(
+    rng: &mut R,
+    name: &str,
+    range: &RangeInclusive<usize>,
+    allowed: RangeInclusive<usize>,
+    excluded: &[usize],
+) -> WireguardResult<usize> {
+    let start = (*range.start()).max(*allowed.start());
+    let end = (*range.end()).min(*allowed.end());
+
+    let mut excluded: Vec<usize> = excluded
+        .iter()
+        .copied()
+        .filter(|value| (start..=end).contains(value))
+        .collect();
+    excluded.sort_unstable();
+    excluded.dedup();
+
+    let count = if start <= end {
+        end - start + 1 - excluded.len()
+    } else {
+        0
+    };
+    if count == 0 {
+        return Err(WireguardError::InvalidAmneziaSetting(name.to_string()));
+    }
+
+    let mut value = start + rng.random_range(0..count);
+    for skipped in excluded {
+        if skipped <= value {
+            value += 1;
+        }
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,372 @@
+use std::{fmt, ops::RangeInclusive};
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// MTU, used by [`AmneziaSettings::diagnose()`], when it isn't set.
+pub const DEFAULT_MTU: usize = 1420;
+
+/// Bytes between interface MTU and largest UDP payload, which fits into link MTU.
+///
+/// Link MTU is assumed to be `MTU + 80` (WireGuard's own assumption), and IPv6 + UDP headers take
+/// 48 bytes of it.
+const UDP_PAYLOAD_OVERHEAD: usize = 80 - 48;
+
+/// Recommended range of `Jc`.
+const RECOMMENDED_JC: RangeInclusive<usize> = 3..=10;
+
+/// Recommended range of `S1` and `S2`.
+const RECOMMENDED_PADDING: RangeInclusive<usize> = 15..=150;
+
+/// Recommended range of `H1..H4`.
+const RECOMMENDED_HEADER: RangeInclusive<usize> = 5..=2_147_483_647;
+
+/// Severity of [`AmneziaDiagnostic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Severity {
+    /// Value isn't recommended, but is accepted by AmneziaWG.
+    Warning,
+
+    /// Value violates protocol; [`AmneziaSettings::validate()`] fails.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Single finding of [`AmneziaSettings::diagnose()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct AmneziaDiagnostic {
+    /// Severity.
+    pub severity: Severity,
+
+    /// Name of offending setting, f.e. `Jc` or `H1/H2/H3/H4`.
+    pub setting: String,
+
+    /// Offending value.
+    pub value: String,
+
+    /// Allowed or recommended value, or violated rule, f.e. `allowed 1-128` or
+    /// `recommended 3-10`.
+    pub expected: String,
+}
+
+impl AmneziaDiagnostic {
+    fn new(
+        severity: Severity,
+        setting: &str,
+        value: impl fmt::Display,
+        expected: impl fmt::Display,
+    ) -> Self {
+        Self {
+            severity,
+            setting: setting.to_string(),
+            value: value.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+/// Formats as `error: Jc = 200 (allowed 1-128)`.
+impl fmt::Display for AmneziaDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} = {} ({})",
+            self.severity, self.setting, self.value, self.expected
+        )
+    }
+}
+
+/// Result of [`AmneziaSettings::diagnose()`].
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// let mut settings = AmneziaSettings::random();
+/// settings.jc = 20;
+/// settings.s2 = settings.s1 + 56;
+///
+/// let report = settings.diagnose(None);
+///
+/// assert!(!report.is_valid());
+/// assert_eq!(report.errors().next().unwrap().setting, "S1");
+/// assert_eq!(report.warnings().next().unwrap().to_string(), "warning: Jc = 20 (recommended 3-10)");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct AmneziaReport {
+    /// Errors and warnings, in order of settings.
+    pub diagnostics: Vec<AmneziaDiagnostic>,
+}
+
+impl AmneziaReport {
+    /// Iterate over errors.
+    pub fn errors(&self) -> impl Iterator<Item = &AmneziaDiagnostic> {
+        self.of(Severity::Error)
+    }
+
+    /// Iterate over warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &AmneziaDiagnostic> {
+        self.of(Severity::Warning)
+    }
+
+    /// Check, if there are no errors (settings pass [`AmneziaSettings::validate()`]).
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Check, if there are neither errors nor warnings.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    fn of(&self, severity: Severity) -> impl Iterator<Item = &AmneziaDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.severity == severity)
+    }
+}
+
+/// One diagnostic per line.
+impl fmt::Display for AmneziaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AmneziaSettings {
+    /// Check every setting: protocol violations are reported as errors, values outside of
+    /// recommended ranges (and packets, which don't fit into `mtu`) -- as warnings.
+    ///
+    /// `mtu` is interface MTU. If it's `None`, [`DEFAULT_MTU`] is used. Junk, signature and
+    /// padded handshake packets must fit into link MTU (`mtu + 80`, as WireGuard assumes);
+    /// padded transport packets (`S4`) aren't checked, since their size depends on link MTU only.
+    #[must_use]
+    pub fn diagnose(&self, mtu: Option<usize>) -> AmneziaReport {
+        let mut diagnoser = Diagnoser {
+            settings: self,
+            mtu: mtu.unwrap_or(DEFAULT_MTU),
+            diagnostics: Vec::new(),
+        };
+
+        diagnoser.junk();
+        diagnoser.padding();
+        diagnoser.headers();
+        diagnoser.signature_packets();
+
+        AmneziaReport {
+            diagnostics: diagnoser.diagnostics,
+        }
+    }
+}
+
+impl Interface {
+    /// Diagnose [`Interface::amnezia_settings`] with interface's MTU.
+    ///
+    /// Returns `None`, if interface doesn't use AmneziaWG.
+    #[must_use]
+    pub fn amnezia_diagnostics(&self) -> Option<AmneziaReport> {
+        Some(self.amnezia_settings.as_ref()?.diagnose(self.mtu))
+    }
+}
+
+struct Diagnoser<'a> {
+    settings: &'a AmneziaSettings,
+    mtu: usize,
+    diagnostics: Vec<AmneziaDiagnostic>,
+}
+
+impl Diagnoser<'_> {
+    fn error(&mut self, setting: &str, value: impl fmt::Display, expected: impl fmt::Display) {
+        self.diagnostics.push(AmneziaDiagnostic::new(
+            Severity::Error,
+            setting,
+            value,
+            expected,
+        ));
+    }
+
+    fn warning(&mut self, setting: &str, value: impl fmt::Display, expected: impl fmt::Display) {
+        self.diagnostics.push(AmneziaDiagnostic::new(
+            Severity::Warning,
+            setting,
+            value,
+            expected,
+        ));
+    }
+
+    fn recommend(&mut self, setting: &str, value: usize, range: &RangeInclusive<usize>) {
+        if !range.contains(&value) {
+            self.warning(setting, value, recommended(range));
+        }
+    }
+
+    /// Warn about packet of `size` bytes, which doesn't fit into link MTU.
+    fn fits_mtu(&mut self, setting: &str, size: usize) {
+        let budget = self.mtu.saturating_add(UDP_PAYLOAD_OVERHEAD);
+
+        if size > budget {
+            self.warning(
+                setting,
+                format!("{size} bytes packet"),
+                format!("at most {budget} bytes for MTU {}", self.mtu),
+            );
+        }
+    }
+
+    /// `Jc`, `Jmin`, `Jmax`.
+    fn junk(&mut self) {
+        let settings = self.settings;
+
+        if (1..=128).contains(&settings.jc) {
+            self.recommend("Jc", settings.jc, &RECOMMENDED_JC);
+        } else {
+            self.error("Jc", settings.jc, "allowed 1-128");
+        }
+
+        if settings.jmin >= settings.jmax {
+            self.error(
+                "Jmin",
+                settings.jmin,
+                format!("allowed less than Jmax ({})", settings.jmax),
+            );
+        }
+        if settings.jmax > 1280 {
+            self.error("Jmax", settings.jmax, "allowed at most 1280");
+        }
+        self.fits_mtu("Jmax", settings.jmax);
+    }
+
+    /// `S1..S4`.
+    ///
+    /// Sizes of padded packets are checked only for paddings, which passed range check, so huge
+    /// values don't overflow.
+    fn padding(&mut self) {
+        let settings = self.settings;
+        let is_v2 = settings.version >= AmneziaVersion::V2;
+
+        if settings.s1 >= 1280 {
+            self.error("S1", settings.s1, "allowed less than 1280");
+        } else {
+            if settings.s1 + 56 == settings.s2 {
+                self.error(
+                    "S1",
+                    settings.s1,
+                    format!("S1 + 56 must differ from S2 ({})", settings.s2),
+                );
+            } else {
+                self.recommend("S1", settings.s1, &RECOMMENDED_PADDING);
+            }
+            self.fits_mtu("S1", settings.s1 + HANDSHAKE_INITIATION_LEN);
+        }
+
+        if settings.s2 >= 1280 {
+            self.error("S2", settings.s2, "allowed less than 1280");
+        } else {
+            self.recommend("S2", settings.s2, &RECOMMENDED_PADDING);
+            self.fits_mtu("S2", settings.s2 + HANDSHAKE_RESPONSE_LEN);
+        }
+
+        if settings.s3 != 0 && !is_v2 {
+            self.error("S3", settings.s3, "requires AmneziaWG 2.0");
+        } else if settings.s3 >= 1280 {
+            self.error("S3", settings.s3, "allowed less than 1280");
+        } else {
+            let cookie_reply = settings.s3 + COOKIE_REPLY_LEN;
+            if cookie_reply == settings.s1.saturating_add(HANDSHAKE_INITIATION_LEN)
+                || cookie_reply == settings.s2.saturating_add(HANDSHAKE_RESPONSE_LEN)
+            {
+                self.error(
+                    "S3",
+                    settings.s3,
+                    "padded cookie reply must differ in size from padded handshake messages",
+                );
+            }
+            self.fits_mtu("S3", cookie_reply);
+        }
+
+        if settings.s4 != 0 && !is_v2 {
+            self.error("S4", settings.s4, "requires AmneziaWG 2.0");
+        } else if settings.s4 >= 1280 {
+            self.error("S4", settings.s4, "allowed less than 1280");
+        }
+    }
+
+    /// `H1..H4`.
+    fn headers(&mut self) {
+        let is_v2 = self.settings.version >= AmneziaVersion::V2;
+        let headers = self.settings.headers();
+
+        for (i, (name, header)) in headers.iter().enumerate() {
+            if header.start > header.end {
+                self.error(
+                    name,
+                    format!("{}-{}", header.start, header.end),
+                    "range start must not exceed its end",
+                );
+            } else if u32::try_from(header.end).is_err() {
+                self.error(name, header, "allowed at most 4294967295");
+            } else if header.is_range() && !is_v2 {
+                self.error(name, header, "ranges require AmneziaWG 2.0");
+            } else if !RECOMMENDED_HEADER.contains(&header.start)
+                || !RECOMMENDED_HEADER.contains(&header.end)
+            {
+                self.warning(name, header, recommended(&RECOMMENDED_HEADER));
+            }
+
+            for (other_name, other) in &headers[i + 1..] {
+                if header.overlaps(other) {
+                    self.error(
+                        "H1/H2/H3/H4",
+                        format!("{name} = {header}, {other_name} = {other}"),
+                        "headers must not overlap",
+                    );
+                }
+            }
+        }
+    }
+
+    /// `I1..I5`, `J1..J3`, `Itime`.
+    fn signature_packets(&mut self) {
+        let settings = self.settings;
+        let is_v1_5 = settings.version >= AmneziaVersion::V1_5;
+
+        for (name, packet) in settings.signature_packets() {
+            if !is_v1_5 {
+                self.error(name, packet, "requires AmneziaWG 1.5");
+            } else if let Err(err) = packet.validate() {
+                self.error(name, packet, err);
+            } else {
+                self.fits_mtu(name, packet.len());
+            }
+        }
+
+        if let Some(itime) = settings.itime.filter(|_| !is_v1_5) {
+            self.error("Itime", itime, "requires AmneziaWG 1.5");
+        }
+    }
+}
+
+fn recommended(range: &RangeInclusive<usize>) -> String {
+    format!("recommended {}-{}", range.start(), range.end())
+}
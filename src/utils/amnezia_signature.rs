@@ -0,0 +1,354 @@
+use rand::prelude::*;
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{WireguardError, WireguardResult};
+
+/// Maximum size of generated signature packet (maximum UDP payload).
+pub const MAX_SIGNATURE_PACKET_LEN: usize = 65_507;
+
+const RANDOM_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const RANDOM_DIGITS: &[u8] = b"0123456789";
+
+/// Tag of AmneziaWG signature packet (`I1..I5`, `J1..J3`).
+///
+/// - [Documentation](https://docs.amnezia.org/documentation/amnezia-wg/)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignatureTag {
+    /// `<b 0x...>` -- static bytes.
+    Bytes(Vec<u8>),
+
+    /// `<r N>` -- `N` random bytes.
+    Random(usize),
+
+    /// `<rc N>` -- `N` random ASCII letters.
+    RandomChars(usize),
+
+    /// `<rd N>` -- `N` random ASCII digits.
+    RandomDigits(usize),
+
+    /// `<t>` -- Unix timestamp (4 bytes, big-endian).
+    Timestamp,
+
+    /// `<c>` -- packet counter (4 bytes, big-endian).
+    Counter,
+}
+
+impl SignatureTag {
+    /// Get size of generated tag in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            SignatureTag::Bytes(bytes) => bytes.len(),
+            SignatureTag::Random(len)
+            | SignatureTag::RandomChars(len)
+            | SignatureTag::RandomDigits(len) => *len,
+            SignatureTag::Timestamp | SignatureTag::Counter => 4,
+        }
+    }
+
+    /// Check, if tag generates no bytes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for SignatureTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureTag::Bytes(bytes) => {
+                f.write_str("<b 0x")?;
+                for byte in bytes {
+                    write!(f, "{byte:02x}")?;
+                }
+                f.write_str(">")
+            }
+            SignatureTag::Random(len) => write!(f, "<r {len}>"),
+            SignatureTag::RandomChars(len) => write!(f, "<rc {len}>"),
+            SignatureTag::RandomDigits(len) => write!(f, "<rd {len}>"),
+            SignatureTag::Timestamp => f.write_str("<t>"),
+            SignatureTag::Counter => f.write_str("<c>"),
+        }
+    }
+}
+
+/// AmneziaWG signature packet (`I1..I5`, `J1..J3`): sequence of [`SignatureTag`]s, f.e.
+/// `<b 0xc0ff><r 16><t>`.
+///
+/// Use [`str::parse()`] for parsing and [`fmt::Display`] for exporting.
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// let packet: SignaturePacket = "<b 0x1234> <r 8><c>".parse().unwrap();
+///
+/// assert_eq!(
+///     packet.tags,
+///     [
+///         SignatureTag::Bytes(vec![0x12, 0x34]),
+///         SignatureTag::Random(8),
+///         SignatureTag::Counter,
+///     ]
+/// );
+/// assert_eq!(packet.len(), 14);
+/// assert_eq!(packet.to_string(), "<b 0x1234><r 8><c>");
+/// ```
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignaturePacket {
+    /// Tags of packet.
+    pub tags: Vec<SignatureTag>,
+}
+
+impl SignaturePacket {
+    /// Create [`SignaturePacket`] from tags.
+    pub fn new<I: IntoIterator<Item = SignatureTag>>(tags: I) -> Self {
+        Self {
+            tags: tags.into_iter().collect(),
+        }
+    }
+
+    /// Get size of generated packet in bytes. Saturates at [`usize::MAX`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tags
+            .iter()
+            .map(SignatureTag::len)
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Check, if packet generates no bytes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Validates [`SignaturePacket`].
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidSignaturePacket`] -- packet has empty tag, or its size is bigger
+    /// than [`MAX_SIGNATURE_PACKET_LEN`].
+    pub fn validate(&self) -> WireguardResult<()> {
+        if let Some(tag) = self.tags.iter().find(|tag| tag.is_empty()) {
+            return Err(WireguardError::InvalidSignaturePacket(format!(
+                "tag `{tag}` is empty"
+            )));
+        }
+
+        let len = self.len();
+        if len == 0 {
+            return Err(WireguardError::InvalidSignaturePacket(
+                "packet is empty".to_string(),
+            ));
+        }
+        if len > MAX_SIGNATURE_PACKET_LEN {
+            return Err(WireguardError::InvalidSignaturePacket(format!(
+                "packet is too big ({len} > {MAX_SIGNATURE_PACKET_LEN} bytes)"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Generate packet bytes. `counter` is used for `<c>` tags.
+    #[must_use]
+    pub fn generate(&self, counter: u32) -> Vec<u8> {
+        self.generate_with_rng(&mut rand::rng(), counter)
+    }
+
+    /// Same as [`SignaturePacket::generate()`], but uses provided `rng`.
+    pub fn generate_with_rng<R: Rng + ?Sized>(&self, rng: &mut R, counter: u32) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.len());
+
+        for tag in &self.tags {
+            match tag {
+                SignatureTag::Bytes(bytes) => buf.extend_from_slice(bytes),
+                SignatureTag::Random(len) => {
+                    let start = buf.len();
+                    buf.resize(start + len, 0);
+                    rng.fill_bytes(&mut buf[start..]);
+                }
+                SignatureTag::RandomChars(len) => {
+                    buf.extend(
+                        (0..*len).map(|_| RANDOM_CHARS[rng.random_range(..RANDOM_CHARS.len())]),
+                    );
+                }
+                SignatureTag::RandomDigits(len) => {
+                    buf.extend(
+                        (0..*len).map(|_| RANDOM_DIGITS[rng.random_range(..RANDOM_DIGITS.len())]),
+                    );
+                }
+                SignatureTag::Timestamp => {
+                    let seconds = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+
+                    #[allow(clippy::cast_possible_truncation)]
+                    buf.extend_from_slice(&(seconds as u32).to_be_bytes());
+                }
+                SignatureTag::Counter => buf.extend_from_slice(&counter.to_be_bytes()),
+            }
+        }
+
+        buf
+    }
+}
+
+/// Templates
+impl SignaturePacket {
+    /// QUIC v1 Initial packet (1200 bytes): long header with random connection ID, followed by
+    /// random "encrypted" payload.
+    pub fn quic_initial() -> Self {
+        Self::new([
+            // long header, Initial, 4-byte packet number; version 1; DCID length
+            SignatureTag::Bytes(vec![0xc3, 0x00, 0x00, 0x00, 0x01, 0x08]),
+            SignatureTag::Random(8),
+            // empty SCID and token; length = 1182 (2-byte varint)
+            SignatureTag::Bytes(vec![0x00, 0x00, 0x44, 0x9e]),
+            SignatureTag::Random(1182),
+        ])
+    }
+
+    /// DNS query for `A` record of `domain`, with random transaction ID.
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidSignaturePacket`] -- `domain` isn't valid domain name.
+    pub fn dns_query(domain: &str) -> WireguardResult<Self> {
+        let domain = domain.strip_suffix('.').unwrap_or(domain);
+        if domain.is_empty() || domain.len() > 253 {
+            return Err(WireguardError::InvalidSignaturePacket(format!(
+                "invalid domain `{domain}`"
+            )));
+        }
+
+        // flags (recursion desired), 1 question
+        let mut bytes = vec![0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        for label in domain.split('.') {
+            let len = u8::try_from(label.len())
+                .ok()
+                .filter(|len| (1..=63).contains(len))
+                .ok_or_else(|| {
+                    WireguardError::InvalidSignaturePacket(format!("invalid domain `{domain}`"))
+                })?;
+
+            bytes.push(len);
+            bytes.extend_from_slice(label.as_bytes());
+        }
+        // root label, type A, class IN
+        bytes.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x01]);
+
+        Ok(Self::new([
+            SignatureTag::Random(2),
+            SignatureTag::Bytes(bytes),
+        ]))
+    }
+
+    /// SIP `OPTIONS` request to `host`, with random branch, tag and call ID.
+    pub fn sip_options(host: &str) -> Self {
+        let text = |text: String| SignatureTag::Bytes(text.into_bytes());
+
+        Self::new([
+            text(format!(
+                "OPTIONS sip:{host} SIP/2.0\r\nVia: SIP/2.0/UDP {host};branch=z9hG4bK"
+            )),
+            SignatureTag::RandomChars(10),
+            text(format!(
+                "\r\nMax-Forwards: 70\r\nTo: <sip:{host}>\r\nFrom: <sip:{host}>;tag="
+            )),
+            SignatureTag::RandomChars(8),
+            text("\r\nCall-ID: ".to_string()),
+            SignatureTag::RandomChars(16),
+            text("\r\nCSeq: 1 OPTIONS\r\nContent-Length: 0\r\n\r\n".to_string()),
+        ])
+    }
+}
+
+impl fmt::Display for SignaturePacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for tag in &self.tags {
+            write!(f, "{tag}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_tag(tag: &str) -> WireguardResult<SignatureTag> {
+    let invalid = |reason: &str| {
+        WireguardError::InvalidSignaturePacket(format!("invalid tag `<{tag}>`: {reason}"))
+    };
+    let len = |arg: Option<&str>| {
+        arg.ok_or_else(|| invalid("expected length"))?
+            .parse::<usize>()
+            .map_err(|_| invalid("invalid length"))
+    };
+
+    let mut parts = tag.split_whitespace();
+    let name = parts.next().ok_or_else(|| invalid("empty tag"))?;
+    let arg = parts.next();
+    if parts.next().is_some() {
+        return Err(invalid("too many arguments"));
+    }
+
+    let tag = match name {
+        "b" => {
+            let hex = arg
+                .and_then(|arg| arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")))
+                .ok_or_else(|| invalid("expected 0x-prefixed hex"))?;
+            if hex.is_empty() || hex.len() % 2 != 0 || !hex.is_ascii() {
+                return Err(invalid("expected even count of hex digits"));
+            }
+
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("invalid hex digit"))?;
+            SignatureTag::Bytes(bytes)
+        }
+        "r" => SignatureTag::Random(len(arg)?),
+        "rc" => SignatureTag::RandomChars(len(arg)?),
+        "rd" => SignatureTag::RandomDigits(len(arg)?),
+        "t" | "c" if arg.is_some() => return Err(invalid("unexpected argument")),
+        "t" => SignatureTag::Timestamp,
+        "c" => SignatureTag::Counter,
+        _ => return Err(invalid("unknown tag")),
+    };
+
+    Ok(tag)
+}
+
+/// Parses and validates [`SignaturePacket`].
+impl FromStr for SignaturePacket {
+    type Err = WireguardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tags = Vec::new();
+
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            let (tag, tail) = rest
+                .strip_prefix('<')
+                .and_then(|rest| rest.split_once('>'))
+                .ok_or_else(|| {
+                    WireguardError::InvalidSignaturePacket(format!("expected `<tag>` at `{rest}`"))
+                })?;
+
+            tags.push(parse_tag(tag)?);
+            rest = tail.trim_start();
+        }
+
+        let packet = Self { tags };
+        packet.validate()?;
+
+        Ok(packet)
+    }
+}
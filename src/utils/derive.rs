@@ -0,0 +1,130 @@
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::{PresharedKey, PrivateKey};
+
+/// Salt for [`PrivateKey::derive()`].
+const PRIVATE_KEY_SALT: &[u8] = b"wireguard-conf v1 private key";
+/// Salt for [`PresharedKey::derive()`].
+const PRESHARED_KEY_SALT: &[u8] = b"wireguard-conf v1 preshared key";
+
+/// Append length-prefixed (`u32`, big-endian) label to `info`.
+fn push_label(info: &mut Vec<u8>, label: &[u8]) {
+    let len = u32::try_from(label.len()).expect("label is too long");
+
+    info.extend_from_slice(&len.to_be_bytes());
+    info.extend_from_slice(label);
+}
+
+fn hkdf_sha256(salt: &[u8], master: &[u8], info: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut okm = Zeroizing::new([0u8; 32]);
+
+    Hkdf::<Sha256>::new(Some(salt), master)
+        .expand(info, okm.as_mut())
+        .unwrap_or_else(|_| unreachable!());
+
+    okm
+}
+
+impl PrivateKey {
+    /// Derive [`PrivateKey`] from master secret and node's label (f.e. hostname).
+    ///
+    /// Same `master` and `label` always give the same key, so whole network can be reconstructed
+    /// from single master secret. `master` should be at least 32 random bytes, and must be kept
+    /// secret.
+    ///
+    /// # Algorithm
+    ///
+    /// This algorithm is stable and won't change between versions:
+    ///
+    /// ```text
+    /// info = u32_be(len(label)) || label
+    /// okm  = HKDF-SHA256(salt = "wireguard-conf v1 private key", ikm = master, info, L = 32)
+    /// key  = clamp(okm)
+    /// ```
+    ///
+    /// where `clamp` is the X25519 clamping (same as in `wg genkey`).
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `label` is longer than [`u32::MAX`] bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// let master = [7u8; 32];
+    ///
+    /// let server_key = PrivateKey::derive(&master, "server");
+    /// let client_key = PrivateKey::derive(&master, "client-1");
+    ///
+    /// assert_eq!(server_key, PrivateKey::derive(&master, "server"));
+    /// assert_ne!(server_key, client_key);
+    /// ```
+    #[must_use]
+    pub fn derive(master: impl AsRef<[u8]>, label: impl AsRef<[u8]>) -> Self {
+        let mut info = Vec::new();
+        push_label(&mut info, label.as_ref());
+
+        let mut okm = hkdf_sha256(PRIVATE_KEY_SALT, master.as_ref(), &info);
+        okm[0] &= 0b1111_1000;
+        okm[31] &= 0b0111_1111;
+        okm[31] |= 0b0100_0000;
+
+        Self::from(*okm)
+    }
+}
+
+impl PresharedKey {
+    /// Derive [`PresharedKey`] for pair of peers from master secret and peers' labels.
+    ///
+    /// Derivation is order-independent: `derive(master, a, b) == derive(master, b, a)`, so both
+    /// sides of tunnel get the same key.
+    ///
+    /// # Algorithm
+    ///
+    /// This algorithm is stable and won't change between versions:
+    ///
+    /// ```text
+    /// (a, b) = sorted(peer_a, peer_b)      // bytewise lexicographic order
+    /// info   = u32_be(len(a)) || a || u32_be(len(b)) || b
+    /// key    = HKDF-SHA256(salt = "wireguard-conf v1 preshared key", ikm = master, info, L = 32)
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, if any of labels is longer than [`u32::MAX`] bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// let master = [7u8; 32];
+    ///
+    /// assert_eq!(
+    ///     PresharedKey::derive(&master, "server", "client-1"),
+    ///     PresharedKey::derive(&master, "client-1", "server"),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn derive(
+        master: impl AsRef<[u8]>,
+        peer_a: impl AsRef<[u8]>,
+        peer_b: impl AsRef<[u8]>,
+    ) -> Self {
+        let (a, b) = if peer_a.as_ref() <= peer_b.as_ref() {
+            (peer_a.as_ref(), peer_b.as_ref())
+        } else {
+            (peer_b.as_ref(), peer_a.as_ref())
+        };
+
+        let mut info = Vec::new();
+        push_label(&mut info, a);
+        push_label(&mut info, b);
+
+        let okm = hkdf_sha256(PRESHARED_KEY_SALT, master.as_ref(), &info);
+
+        Self::from(*okm)
+    }
+}
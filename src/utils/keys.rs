@@ -1,22 +1,66 @@
 use core::fmt;
 
 use base64::prelude::*;
-use rand::RngCore;
+use rand::CryptoRng;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use x25519_dalek::{PublicKey as XPublicKey, StaticSecret};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use crate::WireguardError;
 
+/// Length of base64-encoded 32-byte key.
+const KEY_BASE64_LEN: usize = 44;
+
+/// Placeholder, that is written instead of secrets.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Encode key as base64 into stack buffer, and pass it to `f`. Buffer is zeroized afterwards.
+pub(crate) fn with_base64<R>(bytes: &[u8; 32], f: impl FnOnce(&str) -> R) -> R {
+    let mut buf = Zeroizing::new([0u8; KEY_BASE64_LEN]);
+    let len = BASE64_STANDARD
+        .encode_slice(bytes, buf.as_mut())
+        .unwrap_or_else(|_| unreachable!());
+
+    f(core::str::from_utf8(&buf[..len]).unwrap_or_else(|_| unreachable!()))
+}
+
+/// Decode base64 key without leaving copies of it in memory.
+fn decode_base64(value: &str) -> Option<[u8; 32]> {
+    // one extra byte, because `decode_slice` may require more space, than actually decoded.
+    let mut buf = Zeroizing::new([0u8; 33]);
+    let len = BASE64_STANDARD.decode_slice(value, buf.as_mut()).ok()?;
+
+    if len != 32 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&buf[..32]);
+    Some(bytes)
+}
+
+fn write_base64(f: &mut fmt::Formatter<'_>, bytes: &[u8; 32]) -> fmt::Result {
+    with_base64(bytes, |encoded| f.write_str(encoded))
+}
+
 /// Private key
 ///
 /// Wrapper around [`x25519_dalek::StaticSecret`] with some traits.
 ///
+/// Public key is derived once, when private key is created, and cached alongside the secret.
+/// This makes [`PrivateKey::public_key`] and [`PublicKey::from(&PrivateKey)`](PublicKey::from)
+/// cheap, so rendering configs with many private-key-holding peers doesn't redo X25519 scalar
+/// multiplication for every peer.
+///
 /// # Implements
 ///
 /// - Implements [`Zeroize`] and [`ZeroizeOnDrop`] for clearing secrets from memory.
 /// - Implements [`TryFrom<&str>`] or [`TryFrom<String>`] for importing key from Base64 format.
 /// - Implements [`fmt::Display`] for exporting key in Wireguard's format.
-/// - Implements [`fmt::Debug`].
+/// - Implements [`fmt::Debug`], which doesn't expose the key. Use [`PrivateKey::expose_secret`]
+///   to get the key explicitly.
+/// - Implements constant-time [`PartialEq`].
 ///
 /// # Examples
 ///
@@ -37,13 +81,40 @@ use crate::WireguardError;
 /// # }
 /// ```
 #[derive(Clone, Zeroize, ZeroizeOnDrop)]
-pub struct PrivateKey(StaticSecret);
+pub struct PrivateKey {
+    secret: StaticSecret,
+    public: XPublicKey,
+}
 
 impl PrivateKey {
     /// Generate new a random [`PrivateKey`]
     #[must_use]
     pub fn random() -> PrivateKey {
-        Self(StaticSecret::random())
+        Self::from_secret(StaticSecret::random())
+    }
+
+    /// Generate new a random [`PrivateKey`] using provided `rng`.
+    ///
+    /// Use seeded RNG (f.e. `ChaCha20Rng`) for reproducible keys in tests and demos.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wireguard_conf::prelude::*;
+    /// let key = PrivateKey::random_with_rng(&mut rand::rng());
+    /// ```
+    #[must_use]
+    pub fn random_with_rng<R: CryptoRng + ?Sized>(rng: &mut R) -> PrivateKey {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(bytes.as_mut());
+
+        Self::from(*bytes)
+    }
+
+    fn from_secret(secret: StaticSecret) -> Self {
+        let public = XPublicKey::from(&secret);
+
+        Self { secret, public }
     }
 }
 
@@ -52,21 +123,47 @@ impl PrivateKey {
     #[inline]
     #[must_use]
     pub fn as_bytes(&self) -> &[u8; 32] {
-        self.0.as_bytes()
+        self.secret.as_bytes()
     }
 
     /// Convert private key to a byte array.
     #[inline]
     #[must_use]
     pub fn to_bytes(&self) -> [u8; 32] {
-        self.0.to_bytes()
+        self.secret.to_bytes()
+    }
+
+    /// Get [`PublicKey`] of this private key.
+    ///
+    /// Public key is cached, so this method is cheap.
+    #[inline]
+    #[must_use]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(self.public)
+    }
+
+    /// Get key in base64 format.
+    ///
+    /// Returned string is zeroized on drop.
+    #[must_use]
+    pub fn expose_secret(&self) -> Zeroizing<String> {
+        with_base64(self.as_bytes(), |encoded| {
+            Zeroizing::new(encoded.to_owned())
+        })
+    }
+
+    /// X25519 shared secret with `public_key`.
+    #[cfg(feature = "handshake")]
+    pub(crate) fn diffie_hellman(&self, public_key: &PublicKey) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.secret.diffie_hellman(&public_key.0).to_bytes())
     }
 }
 
+/// Redacted, doesn't expose the key.
 impl fmt::Debug for PrivateKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("PrivateKey")
-            .field(&self.to_string())
+            .field(&format_args!("{REDACTED}"))
             .finish()
     }
 }
@@ -74,27 +171,25 @@ impl fmt::Debug for PrivateKey {
 /// Export key as base64 for Wireguard.
 impl fmt::Display for PrivateKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", BASE64_STANDARD.encode(self.as_bytes()))
+        write_base64(f, self.as_bytes())
     }
 }
 
 impl PartialEq for PrivateKey {
     fn eq(&self, other: &Self) -> bool {
-        self.as_bytes() == other.as_bytes()
+        self.as_bytes().ct_eq(other.as_bytes()).into()
     }
 }
 
+impl Eq for PrivateKey {}
+
 impl TryFrom<&str> for PrivateKey {
     type Error = WireguardError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let bytes: [u8; 32] = BASE64_STANDARD
-            .decode(value)
-            .map_err(|_| WireguardError::InvalidPrivateKey)?
-            .try_into()
-            .map_err(|_| WireguardError::InvalidPrivateKey)?;
+        let bytes = decode_base64(value).ok_or(WireguardError::InvalidPrivateKey)?;
 
-        Ok(Self(StaticSecret::from(bytes)))
+        Ok(Self::from(bytes))
     }
 }
 
@@ -108,7 +203,7 @@ impl TryFrom<String> for PrivateKey {
 
 impl From<[u8; 32]> for PrivateKey {
     fn from(value: [u8; 32]) -> Self {
-        Self(StaticSecret::from(value))
+        Self::from_secret(StaticSecret::from(value))
     }
 }
 
@@ -123,6 +218,7 @@ impl From<[u8; 32]> for PrivateKey {
 /// - Implements [`From<&PrivateKey>`] for converting [`PrivateKey`] to [`PublicKey`].
 /// - Implements [`fmt::Display`] for exporting key in Wireguard's format.
 /// - Implements [`fmt::Debug`].
+/// - Implements constant-time [`PartialEq`].
 ///
 /// # Examples
 ///
@@ -143,7 +239,7 @@ impl From<[u8; 32]> for PrivateKey {
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct PublicKey(XPublicKey);
 
 impl PublicKey {
@@ -162,6 +258,14 @@ impl PublicKey {
     }
 }
 
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes().ct_eq(other.as_bytes()).into()
+    }
+}
+
+impl Eq for PublicKey {}
+
 impl fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("PublicKey").field(&self.to_string()).finish()
@@ -171,7 +275,7 @@ impl fmt::Debug for PublicKey {
 /// Export key in base64 format for Wireguard.
 impl fmt::Display for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", BASE64_STANDARD.encode(self.as_bytes()))
+        write_base64(f, self.as_bytes())
     }
 }
 
@@ -179,11 +283,7 @@ impl TryFrom<&str> for PublicKey {
     type Error = WireguardError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let bytes: [u8; 32] = BASE64_STANDARD
-            .decode(value)
-            .map_err(|_| WireguardError::InvalidPublicKey)?
-            .try_into()
-            .map_err(|_| WireguardError::InvalidPublicKey)?;
+        let bytes = decode_base64(value).ok_or(WireguardError::InvalidPublicKey)?;
 
         Ok(Self(XPublicKey::from(bytes)))
     }
@@ -205,7 +305,7 @@ impl From<[u8; 32]> for PublicKey {
 
 impl From<&PrivateKey> for PublicKey {
     fn from(value: &PrivateKey) -> Self {
-        Self(XPublicKey::from(&value.0))
+        value.public_key()
     }
 }
 
@@ -218,7 +318,9 @@ impl From<&PrivateKey> for PublicKey {
 /// - Implements [`Zeroize`] and [`ZeroizeOnDrop`] for clearing secrets from memory.
 /// - Implements [`TryFrom<&str>`] or [`TryFrom<String>`] for importing key from Base64 format.
 /// - Implements [`fmt::Display`] for exporting key in Wireguard's format.
-/// - Implements [`fmt::Debug`].
+/// - Implements [`fmt::Debug`], which doesn't expose the key. Use [`PresharedKey::expose_secret`]
+///   to get the key explicitly.
+/// - Implements constant-time [`PartialEq`].
 ///
 /// # Examples
 ///
@@ -238,15 +340,23 @@ impl From<&PrivateKey> for PublicKey {
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct PresharedKey([u8; 32]);
 
 impl PresharedKey {
     /// Generate random [`PresharedKey`].
     #[must_use]
     pub fn random() -> Self {
+        Self::random_with_rng(&mut rand::rng())
+    }
+
+    /// Generate random [`PresharedKey`] using provided `rng`.
+    ///
+    /// Use seeded RNG (f.e. `ChaCha20Rng`) for reproducible keys in tests and demos.
+    #[must_use]
+    pub fn random_with_rng<R: CryptoRng + ?Sized>(rng: &mut R) -> Self {
         let mut key = [0u8; 32];
-        rand::rng().fill_bytes(&mut key);
+        rng.fill_bytes(&mut key);
         Self(key)
     }
 }
@@ -265,12 +375,31 @@ impl PresharedKey {
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
+
+    /// Get key in base64 format.
+    ///
+    /// Returned string is zeroized on drop.
+    #[must_use]
+    pub fn expose_secret(&self) -> Zeroizing<String> {
+        with_base64(self.as_bytes(), |encoded| {
+            Zeroizing::new(encoded.to_owned())
+        })
+    }
+}
+
+impl PartialEq for PresharedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes().ct_eq(other.as_bytes()).into()
+    }
 }
 
+impl Eq for PresharedKey {}
+
+/// Redacted, doesn't expose the key.
 impl fmt::Debug for PresharedKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("PresharedKey")
-            .field(&self.to_string())
+            .field(&format_args!("{REDACTED}"))
             .finish()
     }
 }
@@ -278,7 +407,7 @@ impl fmt::Debug for PresharedKey {
 /// Export [`PresharedKey`] as base64 format for Wireguard.
 impl fmt::Display for PresharedKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", BASE64_STANDARD.encode(self.as_bytes()))
+        write_base64(f, self.as_bytes())
     }
 }
 
@@ -292,11 +421,7 @@ impl TryFrom<&str> for PresharedKey {
     type Error = WireguardError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let bytes: [u8; 32] = BASE64_STANDARD
-            .decode(value)
-            .map_err(|_| WireguardError::InvalidPresharedKey)?
-            .try_into()
-            .map_err(|_| WireguardError::InvalidPresharedKey)?;
+        let bytes = decode_base64(value).ok_or(WireguardError::InvalidPresharedKey)?;
 
         Ok(Self(bytes))
     }
@@ -309,3 +434,85 @@ impl TryFrom<String> for PresharedKey {
         Self::try_from(value.as_str())
     }
 }
+
+impl PresharedKey {
+    /// Get [`PresharedKeyHash`] of this key.
+    ///
+    /// Hash can be published safely, and can be used to check, that both sides of tunnel have
+    /// the same preshared key.
+    #[must_use]
+    pub fn hash(&self) -> PresharedKeyHash {
+        let digest = Sha256::new()
+            .chain_update(PresharedKeyHash::DOMAIN)
+            .chain_update(self.as_bytes())
+            .finalize();
+
+        PresharedKeyHash(digest.into())
+    }
+}
+
+/// SHA-256 hash of [`PresharedKey`].
+///
+/// Created by [`PresharedKey::hash()`]. Used in [`PublicPeer`](crate::PublicPeer) instead of
+/// actual preshared key.
+///
+/// Hash is computed as `SHA256("wireguard-conf preshared key hash" || key)`.
+///
+/// # Implements
+///
+/// - Implements [`TryFrom<&str>`] or [`TryFrom<String>`] for importing hash from Base64 format.
+/// - Implements [`fmt::Display`] for exporting hash in Base64 format.
+/// - Implements [`fmt::Debug`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PresharedKeyHash([u8; 32]);
+
+impl PresharedKeyHash {
+    /// Domain separator of hash.
+    const DOMAIN: &[u8] = b"wireguard-conf preshared key hash";
+
+    /// View hash as byte array.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PresharedKeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PresharedKeyHash")
+            .field(&self.to_string())
+            .finish()
+    }
+}
+
+/// Export hash in base64 format.
+impl fmt::Display for PresharedKeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_base64(f, self.as_bytes())
+    }
+}
+
+impl From<[u8; 32]> for PresharedKeyHash {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<&str> for PresharedKeyHash {
+    type Error = WireguardError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let bytes = decode_base64(value).ok_or(WireguardError::InvalidPresharedKeyHash)?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl TryFrom<String> for PresharedKeyHash {
+    type Error = WireguardError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
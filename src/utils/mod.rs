@@ -1,17 +1,42 @@
 #[cfg(feature = "amneziawg")]
 mod amnezia;
+#[cfg(feature = "amneziawg")]
+mod amnezia_builder;
+#[cfg(feature = "amneziawg")]
+mod amnezia_diagnostics;
+#[cfg(feature = "amneziawg")]
+mod amnezia_signature;
 #[cfg(feature = "serde")]
 mod serde;
+#[cfg(feature = "vanity")]
+mod vanity;
 
+mod derive;
+mod encoding;
 mod keys;
+mod provider;
 
 use thiserror::Error;
 
 #[cfg(feature = "amneziawg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
 pub use amnezia::*;
+#[cfg(feature = "amneziawg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+pub use amnezia_builder::*;
+#[cfg(feature = "amneziawg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+pub use amnezia_diagnostics::*;
+#[cfg(feature = "amneziawg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amneziawg")))]
+pub use amnezia_signature::*;
 
 pub use keys::*;
+pub use provider::*;
+
+#[cfg(feature = "vanity")]
+#[cfg_attr(docsrs, doc(cfg(feature = "vanity")))]
+pub use vanity::*;
 
 /// `wireguard-conf` error.
 #[derive(Error, Debug, PartialEq)]
@@ -28,6 +53,37 @@ pub enum WireguardError {
     #[error("invalid preshared key")]
     InvalidPresharedKey,
 
+    /// Error, when preshared key hash is invalid.
+    #[error("invalid preshared key hash")]
+    InvalidPresharedKeyHash,
+
+    /// Error, when key in PEM, DER or hex is malformed.
+    #[error("invalid key encoding: {0}")]
+    InvalidKeyEncoding(String),
+
+    /// Error, when key file can't be read.
+    #[error("can't read key file `{path}`: {reason}")]
+    KeyFile {
+        /// Path to key file.
+        path: String,
+
+        /// Reason of failure.
+        reason: String,
+    },
+
+    /// Error, when [`KeyProvider`] can't load key.
+    #[error("can't load key: {0}")]
+    KeyProvider(String),
+
+    /// Error, when packet isn't valid WireGuard message.
+    #[error("invalid packet: {0}")]
+    InvalidPacket(String),
+
+    /// Error, when pcap/pcapng capture can't be read.
+    #[cfg(feature = "pcap")]
+    #[error("invalid capture: {0}")]
+    InvalidCapture(String),
+
     /// Error, when private key isn't provided.
     #[error("no private key provided")]
     NoPrivateKeyProvided,
@@ -40,6 +96,41 @@ pub enum WireguardError {
     #[cfg(feature = "amneziawg")]
     #[error("invalid amnezia setting: {0}")]
     InvalidAmneziaSetting(String),
+
+    /// Error, when AmneziaVPN `vpn://` import string can't be created or parsed.
+    #[cfg(feature = "amneziavpn")]
+    #[error("invalid AmneziaVPN config: {0}")]
+    InvalidAmneziaVpn(String),
+
+    /// Error, when Clash/mihomo WireGuard proxy can't be parsed.
+    #[cfg(feature = "clash")]
+    #[error("invalid Clash config: {0}")]
+    InvalidClash(String),
+
+    /// Error, when Apple configuration profile can't be created.
+    #[cfg(feature = "mobileconfig")]
+    #[error("invalid mobileconfig: {0}")]
+    InvalidMobileConfig(String),
+
+    /// Error, when `wireguard://` share link can't be created or parsed.
+    #[cfg(feature = "sharelink")]
+    #[error("invalid share link: {0}")]
+    InvalidShareLink(String),
+
+    /// Error, when sing-box WireGuard endpoint can't be parsed.
+    #[cfg(feature = "singbox")]
+    #[error("invalid sing-box config: {0}")]
+    InvalidSingBox(String),
+
+    /// Error, when AmneziaWG signature packet can't be parsed or is invalid.
+    #[cfg(feature = "amneziawg")]
+    #[error("invalid signature packet: {0}")]
+    InvalidSignaturePacket(String),
+
+    /// Error, when vanity pattern can never be matched.
+    #[cfg(feature = "vanity")]
+    #[error("invalid vanity pattern: {0}")]
+    InvalidVanityPattern(String),
 }
 
 /// Result alias.
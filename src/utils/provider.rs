@@ -0,0 +1,238 @@
+use zeroize::Zeroizing;
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::process::Command;
+use std::{env, fs};
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+use serde::{Deserialize, Serialize};
+
+use super::encoding::read_key_file;
+use super::{PresharedKey, PrivateKey, WireguardError, WireguardResult};
+
+/// Source of secret key (either [`PrivateKey`] or [`PresharedKey`]).
+///
+/// Implement it for loading keys from custom places (f.e. secret managers). For built-in
+/// sources see [`KeySource`].
+pub trait KeyProvider {
+    /// Load key in base64 (as produced by `wg genkey`). Surrounding whitespace is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Any error, that prevented loading key.
+    fn load(&self) -> WireguardResult<Zeroizing<String>>;
+
+    /// Load and parse [`PrivateKey`].
+    ///
+    /// # Errors
+    ///
+    /// - Errors of [`KeyProvider::load()`].
+    /// - [`WireguardError::InvalidPrivateKey`] -- loaded key is invalid.
+    fn private_key(&self) -> WireguardResult<PrivateKey> {
+        PrivateKey::try_from(self.load()?.trim())
+    }
+
+    /// Load and parse [`PresharedKey`].
+    ///
+    /// # Errors
+    ///
+    /// - Errors of [`KeyProvider::load()`].
+    /// - [`WireguardError::InvalidPresharedKey`] -- loaded key is invalid.
+    fn preshared_key(&self) -> WireguardResult<PresharedKey> {
+        PresharedKey::try_from(self.load()?.trim())
+    }
+}
+
+/// Built-in [`KeyProvider`]s.
+///
+/// Besides loading keys, it can be referenced in configs instead of embedding keys
+/// (see [`FormatOptions::private_key_source()`](crate::FormatOptions::private_key_source)).
+///
+/// # Examples
+///
+/// ```
+/// # use wireguard_conf::prelude::*;
+/// # fn main() -> WireguardResult<()> {
+/// let key = PrivateKey::random();
+/// # std::env::set_var("WG_DOC_PRIVATE_KEY", key.to_string());
+///
+/// // export WG_DOC_PRIVATE_KEY="$(wg genkey)"
+/// let source = KeySource::Env("WG_DOC_PRIVATE_KEY".to_string());
+///
+/// assert_eq!(source.private_key()?, key);
+/// assert_eq!(source.shell_path(), "<(printenv WG_DOC_PRIVATE_KEY)");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum KeySource {
+    /// Key file (f.e. generated by `wg genkey > private.key`).
+    ///
+    /// On Unix, file must be accessible only by its owner (f.e. `0600`), same as `ssh` does.
+    File(PathBuf),
+
+    /// Environment variable.
+    Env(String),
+
+    /// Output of external command (f.e. `pass show wireguard/wg0`).
+    Command {
+        /// Program to execute.
+        program: String,
+
+        /// Program's arguments.
+        args: Vec<String>,
+    },
+
+    /// `user` key with given description in Linux kernel keyring (f.e. added by
+    /// `keyctl add user wg0 "$(wg genkey)" @s`).
+    ///
+    /// Key is searched in session keyring (and keyrings, linked to it).
+    #[cfg(feature = "keyring")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keyring")))]
+    Keyring(String),
+}
+
+impl KeySource {
+    /// Get shell expression, that evaluates to path of file with the key.
+    ///
+    /// Non-file sources use bash process substitution (`<(...)`), which works in `wg-quick`
+    /// scripts (`PostUp`, etc.). Used for `wg set %i private-key <path>`.
+    #[must_use]
+    pub fn shell_path(&self) -> String {
+        match self {
+            Self::File(path) => shell_quote(&path.to_string_lossy()).into_owned(),
+            Self::Env(var) => format!("<(printenv {})", shell_quote(var)),
+            Self::Command { program, args } => {
+                let mut command = shell_quote(program).into_owned();
+                for arg in args {
+                    command.push(' ');
+                    command.push_str(&shell_quote(arg));
+                }
+                format!("<({command})")
+            }
+            #[cfg(feature = "keyring")]
+            Self::Keyring(description) => {
+                format!(
+                    "<(keyctl pipe {})",
+                    shell_quote(&format!("%user:{description}"))
+                )
+            }
+        }
+    }
+}
+
+impl KeyProvider for KeySource {
+    /// Load key from source.
+    ///
+    /// # Errors
+    ///
+    /// - [`WireguardError::KeyFile`] -- key file can't be read or it's accessible by other users.
+    /// - [`WireguardError::KeyProvider`] -- environment variable isn't set, command failed or
+    ///   key isn't found in keyring.
+    fn load(&self) -> WireguardResult<Zeroizing<String>> {
+        match self {
+            Self::File(path) => {
+                check_permissions(path)?;
+                read_key_file(path)
+            }
+            Self::Env(var) => env::var(var).map(Zeroizing::new).map_err(|err| {
+                WireguardError::KeyProvider(format!("environment variable `{var}`: {err}"))
+            }),
+            Self::Command { program, args } => {
+                let output = Command::new(program).args(args).output().map_err(|err| {
+                    WireguardError::KeyProvider(format!("can't execute `{program}`: {err}"))
+                })?;
+                let stdout = Zeroizing::new(output.stdout);
+
+                if !output.status.success() {
+                    return Err(WireguardError::KeyProvider(format!(
+                        "`{program}` failed ({}): {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+
+                let stdout = core::str::from_utf8(&stdout).map_err(|_| {
+                    WireguardError::KeyProvider(format!("`{program}` output isn't valid UTF-8"))
+                })?;
+                Ok(Zeroizing::new(stdout.trim().to_owned()))
+            }
+            #[cfg(feature = "keyring")]
+            Self::Keyring(description) => load_from_keyring(description),
+        }
+    }
+}
+
+/// Check, that key file isn't accessible by group and other users.
+#[cfg(unix)]
+fn check_permissions(path: &std::path::Path) -> WireguardResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::metadata(path).map_err(|err| WireguardError::KeyFile {
+        path: path.display().to_string(),
+        reason: err.to_string(),
+    })?;
+
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(WireguardError::KeyFile {
+            path: path.display().to_string(),
+            reason: format!(
+                "permissions {mode:04o} are too open, key file must be accessible only by its owner (f.e. 0600)"
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(path: &std::path::Path) -> WireguardResult<()> {
+    fs::metadata(path)
+        .map(|_| ())
+        .map_err(|err| WireguardError::KeyFile {
+            path: path.display().to_string(),
+            reason: err.to_string(),
+        })
+}
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+fn load_from_keyring(description: &str) -> WireguardResult<Zeroizing<String>> {
+    use linux_keyutils::{KeyRing, KeyRingIdentifier};
+
+    let error = |err| WireguardError::KeyProvider(format!("keyring key `{description}`: {err}"));
+
+    let keyring = KeyRing::from_special_id(KeyRingIdentifier::Session, false).map_err(error)?;
+    let key = keyring.search(description).map_err(error)?;
+    let payload = Zeroizing::new(key.read_to_vec().map_err(error)?);
+
+    let payload = core::str::from_utf8(&payload).map_err(|_| {
+        WireguardError::KeyProvider(format!("keyring key `{description}` isn't valid UTF-8"))
+    })?;
+    Ok(Zeroizing::new(payload.trim().to_owned()))
+}
+
+#[cfg(all(feature = "keyring", not(target_os = "linux")))]
+fn load_from_keyring(_description: &str) -> WireguardResult<Zeroizing<String>> {
+    Err(WireguardError::KeyProvider(
+        "kernel keyring is supported only on Linux".to_string(),
+    ))
+}
+
+/// Quote string for POSIX shell, if needed.
+pub(crate) fn shell_quote(value: &str) -> Cow<'_, str> {
+    let is_safe = !value.is_empty()
+        && value.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '_' | '-' | '.' | '/' | ':' | '%' | '@' | '+' | ',' | '=')
+        });
+
+    if is_safe {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroizing;
 
-use super::{PresharedKey, PrivateKey, PublicKey};
+use super::keys::with_base64;
+use super::{PresharedKey, PresharedKeyHash, PrivateKey, PublicKey};
 
 impl Serialize for PrivateKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -8,7 +10,7 @@ impl Serialize for PrivateKey {
         S: Serializer,
     {
         if serializer.is_human_readable() {
-            serializer.serialize_str(&self.to_string())
+            with_base64(self.as_bytes(), |encoded| serializer.serialize_str(encoded))
         } else {
             serializer.serialize_bytes(self.as_bytes())
         }
@@ -21,14 +23,15 @@ impl<'de> Deserialize<'de> for PrivateKey {
         D: Deserializer<'de>,
     {
         if deserializer.is_human_readable() {
-            let data = String::deserialize(deserializer)?;
+            let data = Zeroizing::new(String::deserialize(deserializer)?);
 
-            PrivateKey::try_from(data.as_str())
-                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&data), &"a private key"))
+            PrivateKey::try_from(data.as_str()).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Other("invalid key"), &"a private key")
+            })
         } else {
-            let bytes = <[u8; 32]>::deserialize(deserializer)?;
+            let bytes = Zeroizing::new(<[u8; 32]>::deserialize(deserializer)?);
 
-            Ok(PrivateKey::from(bytes))
+            Ok(PrivateKey::from(*bytes))
         }
     }
 }
@@ -39,7 +42,7 @@ impl Serialize for PublicKey {
         S: Serializer,
     {
         if serializer.is_human_readable() {
-            serializer.serialize_str(&self.to_string())
+            with_base64(self.as_bytes(), |encoded| serializer.serialize_str(encoded))
         } else {
             serializer.serialize_bytes(self.as_bytes())
         }
@@ -70,7 +73,7 @@ impl Serialize for PresharedKey {
         S: Serializer,
     {
         if serializer.is_human_readable() {
-            serializer.serialize_str(&self.to_string())
+            with_base64(self.as_bytes(), |encoded| serializer.serialize_str(encoded))
         } else {
             serializer.serialize_bytes(self.as_bytes())
         }
@@ -83,15 +86,133 @@ impl<'de> Deserialize<'de> for PresharedKey {
         D: Deserializer<'de>,
     {
         if deserializer.is_human_readable() {
-            let data = String::deserialize(deserializer)?;
+            let data = Zeroizing::new(String::deserialize(deserializer)?);
 
             PresharedKey::try_from(data.as_str()).map_err(|_| {
-                de::Error::invalid_value(de::Unexpected::Str(&data), &"a preshared key")
+                de::Error::invalid_value(de::Unexpected::Other("invalid key"), &"a preshared key")
+            })
+        } else {
+            let bytes = Zeroizing::new(<[u8; 32]>::deserialize(deserializer)?);
+
+            Ok(PresharedKey::from(*bytes))
+        }
+    }
+}
+
+impl Serialize for PresharedKeyHash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PresharedKeyHash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let data = String::deserialize(deserializer)?;
+
+            PresharedKeyHash::try_from(data.as_str()).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Str(&data), &"a preshared key hash")
             })
         } else {
             let bytes = <[u8; 32]>::deserialize(deserializer)?;
 
-            Ok(PresharedKey::from(bytes))
+            Ok(PresharedKeyHash::from(bytes))
+        }
+    }
+}
+
+#[cfg(feature = "amneziawg")]
+impl Serialize for super::SignaturePacket {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "amneziawg")]
+impl<'de> Deserialize<'de> for super::SignaturePacket {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = String::deserialize(deserializer)?;
+
+        data.parse().map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&data), &"a signature packet")
+        })
+    }
+}
+
+/// In human-readable formats single values are numbers (as before AmneziaWG 2.0), ranges are
+/// `start-end` strings. Other formats always get `(start, end)` tuple.
+#[cfg(feature = "amneziawg")]
+impl Serialize for super::MagicHeader {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            (self.start, self.end).serialize(serializer)
+        } else if self.is_range() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.start as u64)
+        }
+    }
+}
+
+#[cfg(feature = "amneziawg")]
+impl<'de> Deserialize<'de> for super::MagicHeader {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = super::MagicHeader;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a magic header value or `start-end` range")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                usize::try_from(value)
+                    .map(super::MagicHeader::from)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value
+                    .parse()
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(Visitor)
+        } else {
+            let (start, end) = <(usize, usize)>::deserialize(deserializer)?;
+            if start > end {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Other("range with start after end"),
+                    &"a magic header range",
+                ));
+            }
+
+            Ok(super::MagicHeader { start, end })
         }
     }
 }
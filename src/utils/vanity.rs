@@ -0,0 +1,346 @@
+use regex::Regex;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::keys::with_base64;
+use super::{PrivateKey, PublicKey, WireguardError, WireguardResult};
+
+/// How many keys each thread generates before updating shared counter.
+const BATCH_SIZE: u64 = 256;
+
+/// Length of base64-encoded key, without padding (`=`).
+const MAX_PREFIX_LEN: usize = 43;
+
+/// Possible 42nd characters of base64-encoded public key. Highest bit of 32nd byte (3rd bit of
+/// character) is always zero, as public key is less than `2^255 - 19`.
+const PENULTIMATE_CHARS: &str = "ABCDEFGHQRSTUVWXghijklmnwxyz0123";
+
+/// Possible 43rd (last) characters of base64-encoded key: it encodes 4 low bits of 32nd byte
+/// and 2 zero padding bits.
+const LAST_CHARS: &str = "AEIMQUYcgkosw048";
+
+/// Sets flag on drop.
+struct StopGuard<'a>(&'a AtomicBool);
+
+impl Drop for StopGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Pattern, that vanity [`PublicKey`] should match.
+#[derive(Clone, Debug)]
+pub enum VanityPattern {
+    /// Public key starts with exact prefix (f.e. `srv1/`).
+    Prefix(String),
+
+    /// Public key starts with prefix, ignoring ASCII case (f.e. `alice` matches `AlIcE...`).
+    PrefixCaseInsensitive(String),
+
+    /// Public key (in base64) matches regex.
+    Regex(Regex),
+}
+
+impl VanityPattern {
+    /// Validate pattern.
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidVanityPattern`] -- prefix is too long, contains non-base64
+    /// characters or has impossible 42nd or 43rd character, so it can never be found.
+    pub fn validate(&self) -> WireguardResult<()> {
+        let (Self::Prefix(prefix) | Self::PrefixCaseInsensitive(prefix)) = self else {
+            return Ok(());
+        };
+
+        if prefix.len() > MAX_PREFIX_LEN {
+            return Err(WireguardError::InvalidVanityPattern(format!(
+                "prefix is longer than {MAX_PREFIX_LEN} characters"
+            )));
+        }
+        if let Some(c) = prefix
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '+' || *c == '/'))
+        {
+            return Err(WireguardError::InvalidVanityPattern(format!(
+                "'{c}' isn't a base64 character"
+            )));
+        }
+        for (position, possible) in [
+            (MAX_PREFIX_LEN - 1, PENULTIMATE_CHARS),
+            (MAX_PREFIX_LEN, LAST_CHARS),
+        ] {
+            let Some(c) = prefix.chars().nth(position - 1) else {
+                continue;
+            };
+            let is_possible = match self {
+                Self::PrefixCaseInsensitive(_) => {
+                    possible.contains(c.to_ascii_uppercase())
+                        || possible.contains(c.to_ascii_lowercase())
+                }
+                _ => possible.contains(c),
+            };
+            if !is_possible {
+                return Err(WireguardError::InvalidVanityPattern(format!(
+                    "key can't have '{c}' at position {position} (only one of `{possible}`)"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check, whether base64-encoded public key matches pattern.
+    #[must_use]
+    pub fn matches(&self, public_key: &str) -> bool {
+        match self {
+            Self::Prefix(prefix) => public_key.starts_with(prefix.as_str()),
+            Self::PrefixCaseInsensitive(prefix) => public_key
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+            Self::Regex(regex) => regex.is_match(public_key),
+        }
+    }
+
+    /// Expected number of keys, that have to be generated to find the match.
+    ///
+    /// Returns [`None`] for [`VanityPattern::Regex`], as it can't be estimated.
+    #[must_use]
+    pub fn expected_attempts(&self) -> Option<f64> {
+        match self {
+            Self::Prefix(prefix) => Some(64f64.powi(i32::try_from(prefix.len()).ok()?)),
+            Self::PrefixCaseInsensitive(prefix) => Some(
+                prefix
+                    .chars()
+                    .map(|c| if c.is_ascii_alphabetic() { 32.0 } else { 64.0 })
+                    .product(),
+            ),
+            Self::Regex(_) => None,
+        }
+    }
+}
+
+/// Token for cancelling [`VanitySearch`].
+///
+/// Clone it and call [`CancelToken::cancel()`] from other thread (f.e. Ctrl-C handler).
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create new [`CancelToken`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel search.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Check, whether search was cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress of [`VanitySearch`], reported periodically.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VanityProgress {
+    /// Number of generated keys.
+    pub attempts: u64,
+
+    /// Time since search started.
+    pub elapsed: Duration,
+
+    /// Generated keys per second.
+    pub rate: f64,
+
+    /// Expected number of keys to generate (see [`VanityPattern::expected_attempts()`]).
+    pub expected_attempts: Option<f64>,
+
+    /// Expected time until match is found.
+    ///
+    /// Search is memoryless, so it doesn't decrease with time: every key has the same chance.
+    pub eta: Option<Duration>,
+}
+
+/// Found vanity keypair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VanityMatch {
+    /// Private key.
+    pub private_key: PrivateKey,
+
+    /// Public key, that matches pattern.
+    pub public_key: PublicKey,
+
+    /// Number of generated keys.
+    pub attempts: u64,
+
+    /// Search duration.
+    pub elapsed: Duration,
+}
+
+/// Multi-threaded search of [`PrivateKey`] with recognisable [`PublicKey`] (f.e. starting with
+/// `srv1/`).
+///
+/// Every base64 character of prefix makes search 64 times longer (32 times for case-insensitive
+/// letters), so prefixes longer than 5-6 characters may take days.
+///
+/// # Examples
+///
+/// ```
+/// use wireguard_conf::prelude::*;
+///
+/// # fn main() -> WireguardResult<()> {
+/// let search = VanitySearch::new(VanityPattern::PrefixCaseInsensitive("a".to_string()))?;
+///
+/// let found = search
+///     .run(&CancelToken::new(), |progress| {
+///         println!("{} keys, eta: {:?}", progress.attempts, progress.eta);
+///     })
+///     .expect("search wasn't cancelled");
+///
+/// assert!(found.public_key.to_string().to_lowercase().starts_with('a'));
+/// assert_eq!(found.private_key.public_key(), found.public_key);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VanitySearch {
+    pattern: VanityPattern,
+    threads: usize,
+    progress_interval: Duration,
+}
+
+impl VanitySearch {
+    /// Create new [`VanitySearch`].
+    ///
+    /// By default, it uses all available CPU cores and reports progress every second.
+    ///
+    /// # Errors
+    ///
+    /// [`WireguardError::InvalidVanityPattern`] -- pattern can never be matched.
+    pub fn new(pattern: VanityPattern) -> WireguardResult<Self> {
+        pattern.validate()?;
+
+        Ok(Self {
+            pattern,
+            threads: thread::available_parallelism().map_or(1, usize::from),
+            progress_interval: Duration::from_secs(1),
+        })
+    }
+
+    /// Sets number of threads. `0` is treated as `1`.
+    #[must_use]
+    pub fn threads(mut self, value: usize) -> Self {
+        self.threads = value.max(1);
+        self
+    }
+
+    /// Sets how often progress is reported.
+    #[must_use]
+    pub fn progress_interval(mut self, value: Duration) -> Self {
+        self.progress_interval = value;
+        self
+    }
+
+    /// Run search. Blocks until match is found or search is cancelled via `cancel`.
+    ///
+    /// `on_progress` is called from current thread every
+    /// [`progress_interval`](VanitySearch::progress_interval).
+    ///
+    /// Returns [`None`], if search was cancelled.
+    pub fn run<F>(&self, cancel: &CancelToken, mut on_progress: F) -> Option<VanityMatch>
+    where
+        F: FnMut(&VanityProgress),
+    {
+        let started_at = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let stop = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+
+        let found = thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let sender = sender.clone();
+                let (attempts, stop) = (&attempts, &stop);
+
+                scope.spawn(move || {
+                    if let Some(private_key) = self.search(attempts, stop, cancel) {
+                        _ = sender.send(private_key);
+                    }
+                });
+            }
+            drop(sender);
+
+            // stop workers even if `on_progress` panics, otherwise scope would wait forever.
+            let _stop_guard = StopGuard(&stop);
+
+            loop {
+                match receiver.recv_timeout(self.progress_interval) {
+                    Ok(private_key) => break Some(private_key),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break None,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        on_progress(&self.progress(attempts.load(Ordering::Relaxed), started_at));
+                    }
+                }
+            }
+        })?;
+
+        Some(VanityMatch {
+            public_key: found.public_key(),
+            private_key: found,
+            attempts: attempts.load(Ordering::Relaxed),
+            elapsed: started_at.elapsed(),
+        })
+    }
+
+    /// Worker loop.
+    fn search(
+        &self,
+        attempts: &AtomicU64,
+        stop: &AtomicBool,
+        cancel: &CancelToken,
+    ) -> Option<PrivateKey> {
+        let mut rng = rand::rng();
+
+        while !stop.load(Ordering::Relaxed) && !cancel.is_cancelled() {
+            for i in 1..=BATCH_SIZE {
+                let private_key = PrivateKey::random_with_rng(&mut rng);
+
+                let is_match = with_base64(private_key.public_key().as_bytes(), |public_key| {
+                    self.pattern.matches(public_key)
+                });
+                if is_match {
+                    stop.store(true, Ordering::Relaxed);
+                    attempts.fetch_add(i, Ordering::Relaxed);
+                    return Some(private_key);
+                }
+            }
+            attempts.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+        }
+
+        None
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn progress(&self, attempts: u64, started_at: Instant) -> VanityProgress {
+        let elapsed = started_at.elapsed();
+        let rate = attempts as f64 / elapsed.as_secs_f64();
+        let expected_attempts = self.pattern.expected_attempts();
+
+        VanityProgress {
+            attempts,
+            elapsed,
+            rate,
+            expected_attempts,
+            eta: expected_attempts
+                .filter(|_| rate > 0.0)
+                .and_then(|expected| Duration::try_from_secs_f64(expected / rate).ok()),
+        }
+    }
+}
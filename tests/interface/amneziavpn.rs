@@ -0,0 +1,238 @@
+#![cfg(feature = "amneziavpn")]
+use std::io::{Read, Write};
+
+use base64::prelude::*;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde_json::{json, Value};
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn client() -> Interface {
+    let mut client = super::client(1376, &["1.1.1.1", "8.8.8.8"]);
+    client.amnezia_settings = Some(AmneziaSettings::random());
+
+    client
+}
+
+fn decode(link: &str) -> Value {
+    let data = BASE64_URL_SAFE_NO_PAD
+        .decode(link.strip_prefix("vpn://").unwrap())
+        .unwrap();
+
+    let len = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+    let mut json = Vec::new();
+    ZlibDecoder::new(&data[4..]).read_to_end(&mut json).unwrap();
+    assert_eq!(json.len(), len);
+
+    serde_json::from_slice(&json).unwrap()
+}
+
+fn encode(envelope: &Value) -> String {
+    let json = envelope.to_string();
+
+    let mut data = (json.len() as u32).to_be_bytes().to_vec();
+    let mut encoder = ZlibEncoder::new(&mut data, Compression::default());
+    encoder.write_all(json.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    format!("vpn://{}", BASE64_URL_SAFE_NO_PAD.encode(data))
+}
+
+#[test]
+fn export() {
+    let client = client();
+    let settings = client.amnezia_settings.as_ref().unwrap();
+    let server = &client.peers[0];
+
+    let envelope = decode(&client.to_amnezia_vpn("My server").unwrap());
+    assert_eq!(envelope["description"], "My server");
+    assert_eq!(envelope["hostName"], "vpn.example.com");
+    assert_eq!(envelope["defaultContainer"], "amnezia-awg");
+    assert_eq!(envelope["dns1"], "1.1.1.1");
+    assert_eq!(envelope["dns2"], "8.8.8.8");
+
+    let container = &envelope["containers"][0];
+    assert_eq!(container["container"], "amnezia-awg");
+
+    let awg = &container["awg"];
+    assert_eq!(awg["port"], "51821");
+    assert_eq!(awg["transport_proto"], "udp");
+    assert_eq!(awg["Jc"], settings.jc.to_string());
+    assert_eq!(awg["H1"], settings.h1.to_string());
+
+    let last_config: Value = serde_json::from_str(awg["last_config"].as_str().unwrap()).unwrap();
+    assert_eq!(last_config["S2"], settings.s2.to_string());
+    assert_eq!(last_config["client_ip"], "10.0.0.2");
+    assert_eq!(
+        last_config["client_priv_key"],
+        client.private_key.to_string()
+    );
+    assert_eq!(
+        last_config["server_pub_key"],
+        server.public_key().to_string()
+    );
+    assert_eq!(
+        last_config["psk_key"],
+        server.preshared_key.as_ref().unwrap().to_string()
+    );
+    assert_eq!(last_config["allowed_ips"], json!(["0.0.0.0/0", "::/0"]));
+    assert_eq!(last_config["mtu"], "1376");
+    assert_eq!(last_config["persistent_keep_alive"], "25");
+    assert_eq!(last_config["port"], 51821);
+    assert_eq!(last_config["config"], client.to_string());
+}
+
+#[test]
+fn roundtrip() {
+    let client = client();
+    let imported = Interface::from_amnezia_vpn(&client.to_amnezia_vpn("").unwrap()).unwrap();
+
+    assert_eq!(imported.address, [as_ipnet!("10.0.0.2/32")]);
+    assert_eq!(imported.private_key, client.private_key);
+    assert_eq!(imported.dns, client.dns);
+    assert_eq!(imported.mtu, client.mtu);
+    assert_eq!(imported.amnezia_settings, client.amnezia_settings);
+
+    let (peer, expected) = (&imported.peers[0], &client.peers[0]);
+    assert_eq!(peer.public_key(), expected.public_key());
+    assert_eq!(peer.endpoint, expected.endpoint);
+    assert_eq!(peer.allowed_ips, expected.allowed_ips);
+    assert_eq!(peer.preshared_key, expected.preshared_key);
+    assert_eq!(peer.persistent_keepalive, 25);
+}
+
+#[test]
+fn roundtrip_v2() {
+    let mut client = client();
+    client.amnezia_settings = Some(
+        AmneziaSettings::builder()
+            .version(AmneziaVersion::V2)
+            .header_width(1000)
+            .build()
+            .unwrap(),
+    );
+    client.amnezia_settings.as_mut().unwrap().i1 = Some(SignaturePacket::quic_initial());
+
+    let imported = Interface::from_amnezia_vpn(&client.to_amnezia_vpn("").unwrap()).unwrap();
+    assert_eq!(imported.amnezia_settings, client.amnezia_settings);
+}
+
+#[test]
+fn import_app_config() {
+    let server_key = PrivateKey::random().public_key();
+    let client_key = PrivateKey::random();
+
+    let last_config = json!({
+        "H1": "1020325451",
+        "H2": "3288052141",
+        "H3": "1766607858",
+        "H4": "2528465083",
+        "Jc": "4",
+        "Jmax": "70",
+        "Jmin": "10",
+        "S1": "52",
+        "S2": "76",
+        "allowed_ips": ["0.0.0.0/0", "::/0"],
+        "client_ip": "10.8.1.5",
+        "client_priv_key": client_key.to_string(),
+        "hostName": "203.0.113.7",
+        "mtu": "1376",
+        "persistent_keep_alive": "25",
+        "port": 43567,
+        "psk_key": "",
+        "server_pub_key": server_key.to_string(),
+    });
+    let envelope = json!({
+        "containers": [
+            { "container": "amnezia-openvpn", "openvpn": {} },
+            {
+                "awg": {
+                    "last_config": last_config.to_string(),
+                    "port": "43567",
+                    "transport_proto": "udp",
+                },
+                "container": "amnezia-awg",
+            },
+        ],
+        "defaultContainer": "amnezia-awg",
+        "description": "Server",
+        "dns1": "1.1.1.1",
+        "dns2": "",
+        "hostName": "203.0.113.7",
+    });
+
+    for link in [
+        encode(&envelope),
+        // uncompressed, with padding
+        format!("vpn://{}", BASE64_URL_SAFE.encode(envelope.to_string())),
+    ] {
+        let interface = Interface::from_amnezia_vpn(&link).unwrap();
+
+        assert_eq!(interface.address, [as_ipnet!("10.8.1.5/32")]);
+        assert_eq!(interface.private_key, client_key);
+        assert_eq!(interface.dns, ["1.1.1.1"]);
+        assert_eq!(interface.mtu, Some(1376));
+
+        let settings = interface.amnezia_settings.unwrap();
+        assert_eq!(settings.version, AmneziaVersion::V1_5);
+        assert_eq!((settings.jc, settings.s1, settings.s2), (4, 52, 76));
+        assert_eq!(settings.h2, MagicHeader::from(3_288_052_141));
+
+        let peer = &interface.peers[0];
+        assert_eq!(peer.public_key(), server_key);
+        assert_eq!(peer.endpoint.as_deref(), Some("203.0.113.7:43567"));
+        assert_eq!(peer.preshared_key, None);
+        assert_eq!(peer.allowed_ips.len(), 2);
+    }
+}
+
+#[test]
+fn export_errors() {
+    let mut client = client();
+    client.peers[0].endpoint = None;
+    assert!(matches!(
+        client.to_amnezia_vpn(""),
+        Err(WireguardError::InvalidAmneziaVpn(_))
+    ));
+
+    let mut client = self::client();
+    client.amnezia_settings = None;
+    assert!(matches!(
+        client.to_amnezia_vpn(""),
+        Err(WireguardError::InvalidAmneziaVpn(_))
+    ));
+
+    let mut client = self::client();
+    client.peers.push(client.peers[0].clone());
+    assert!(matches!(
+        client.to_amnezia_vpn(""),
+        Err(WireguardError::InvalidAmneziaVpn(_))
+    ));
+}
+
+#[test]
+fn import_errors() {
+    for link in [
+        "",
+        "https://example.com",
+        "vpn://!!!",
+        "vpn://AAAA",
+        &encode(&json!({})),
+        &encode(&json!({ "containers": [{ "container": "amnezia-openvpn" }] })),
+        &encode(&json!({ "containers": [{ "container": "amnezia-awg", "awg": {} }] })),
+    ] {
+        assert!(
+            matches!(
+                Interface::from_amnezia_vpn(link),
+                Err(WireguardError::InvalidAmneziaVpn(_))
+            ),
+            "{link}"
+        );
+    }
+
+    let mut client = client();
+    client.amnezia_settings.as_mut().unwrap().h2 = client.amnezia_settings.as_ref().unwrap().h1;
+    assert!(matches!(
+        Interface::from_amnezia_vpn(&client.to_amnezia_vpn("").unwrap()),
+        Err(WireguardError::InvalidAmneziaSetting(_))
+    ));
+}
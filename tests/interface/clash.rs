@@ -0,0 +1,219 @@
+#![cfg(feature = "clash")]
+use serde_yaml_ng::Value;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn client() -> Interface {
+    super::client(1380, &["1.1.1.1", "8.8.8.8"])
+}
+
+#[test]
+fn export_proxy() {
+    let client = client();
+    let server = &client.peers[0];
+
+    let yaml = serde_yaml_ng::to_string(&client.to_clash("wg", Some([209, 98, 59]))).unwrap();
+
+    assert_eq!(
+        yaml,
+        format!(
+            "\
+name: wg
+type: wireguard
+private-key: {}
+ip: 10.0.0.2/24
+ipv6: fd00::2/64
+server: vpn.example.com
+port: 51821
+public-key: {}
+pre-shared-key: {}
+allowed-ips:
+- 0.0.0.0/0
+- ::/0
+reserved:
+- 209
+- 98
+- 59
+udp: true
+mtu: 1380
+dns:
+- 1.1.1.1
+- 8.8.8.8
+remote-dns-resolve: true
+persistent-keepalive: 25
+",
+            client.private_key,
+            server.public_key(),
+            server.preshared_key.as_ref().unwrap(),
+        )
+    );
+}
+
+#[test]
+fn round_trip() {
+    let client = client();
+
+    let imported = Interface::from_clash(&client.to_clash("wg", None)).unwrap();
+
+    assert_eq!(imported.address, client.address);
+    assert_eq!(imported.private_key, client.private_key);
+    assert_eq!(imported.dns, client.dns);
+    assert_eq!(imported.mtu, client.mtu);
+
+    let (imported, server) = (&imported.peers[0], &client.peers[0]);
+    assert_eq!(imported.public_key(), server.public_key());
+    assert_eq!(imported.endpoint, server.endpoint);
+    assert_eq!(imported.allowed_ips, server.allowed_ips);
+    assert_eq!(imported.persistent_keepalive, 25);
+    assert_eq!(imported.preshared_key, server.preshared_key);
+}
+
+#[test]
+fn multiple_peers() {
+    let mut client = client();
+    let mut second = PeerBuilder::new()
+        .endpoint("[2001:db8::1]:51820")
+        .allowed_ips([as_ipnet!("192.168.0.0/16")])
+        .persistent_keepalive(60)
+        .build();
+    second.key = either::Either::Right(PrivateKey::random().public_key());
+    client.peers.push(second);
+
+    let proxy = client.to_clash("wg", Some([1, 2, 3]));
+
+    assert!(proxy.get("server").is_none());
+    assert_eq!(proxy["persistent-keepalive"], 60);
+    let peers = proxy["peers"].as_sequence().unwrap();
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers[1]["server"], "2001:db8::1");
+    assert_eq!(peers[1]["port"], 51820);
+    assert_eq!(peers[1]["reserved"][2], 3);
+
+    let imported = Interface::from_clash(&proxy).unwrap();
+    assert_eq!(imported.peers.len(), 2);
+    assert_eq!(
+        imported.peers[1].endpoint.as_deref(),
+        Some("[2001:db8::1]:51820")
+    );
+    assert_eq!(
+        imported.peers[1].allowed_ips,
+        vec![as_ipnet!("192.168.0.0/16")]
+    );
+    assert!(imported
+        .peers
+        .iter()
+        .all(|peer| peer.persistent_keepalive == 60));
+}
+
+#[test]
+fn no_peers() {
+    let mut client = client();
+    client.peers.clear();
+
+    let proxy = client.to_clash("wg", None);
+    assert_eq!(proxy["peers"], Value::Sequence(vec![]));
+
+    let imported = Interface::from_clash(&proxy).unwrap();
+    assert_eq!(imported.address, client.address);
+    assert!(imported.peers.is_empty());
+}
+
+#[test]
+fn import_yaml() {
+    let private_key = PrivateKey::random();
+    let public_key = PrivateKey::random().public_key();
+
+    let proxy: Value = serde_yaml_ng::from_str(&format!(
+        "
+name: warp
+type: wireguard
+private-key: {private_key}
+server: 162.159.192.1
+port: 2408
+ip: 172.16.0.2
+ipv6: 2606:4700:110:8f81::2
+public-key: {public_key}
+reserved: 0WI7
+udp: true
+mtu: 1280
+"
+    ))
+    .unwrap();
+
+    let interface = Interface::from_clash(&proxy).unwrap();
+
+    assert_eq!(
+        interface.address,
+        vec![
+            as_ipnet!("172.16.0.2/32"),
+            as_ipnet!("2606:4700:110:8f81::2/128")
+        ]
+    );
+    assert_eq!(interface.mtu, Some(1280));
+    assert_eq!(interface.peers[0].public_key(), public_key);
+    assert_eq!(
+        interface.peers[0].endpoint.as_deref(),
+        Some("162.159.192.1:2408")
+    );
+    assert_eq!(
+        interface.peers[0].allowed_ips,
+        vec![as_ipnet!("0.0.0.0/0"), as_ipnet!("::/0")]
+    );
+}
+
+#[test]
+fn import_errors() {
+    let proxy = client().to_clash("wg", None);
+
+    let mut wrong_type = proxy.clone();
+    wrong_type["type"] = "vless".into();
+    assert!(matches!(
+        Interface::from_clash(&wrong_type),
+        Err(WireguardError::InvalidClash(_))
+    ));
+
+    let mut bad_reserved = proxy.clone();
+    bad_reserved["reserved"] = vec![1, 2, 300].into();
+    assert!(matches!(
+        Interface::from_clash(&bad_reserved),
+        Err(WireguardError::InvalidClash(_))
+    ));
+
+    let mut bad_ip = proxy.clone();
+    bad_ip["ip"] = "10.0.0".into();
+    assert!(matches!(
+        Interface::from_clash(&bad_ip),
+        Err(WireguardError::InvalidClash(_))
+    ));
+
+    let mut bad_key = proxy;
+    bad_key["public-key"] = "not a key".into();
+    assert!(matches!(
+        Interface::from_clash(&bad_key),
+        Err(WireguardError::InvalidPublicKey)
+    ));
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn amnezia_wg_option() {
+    let mut client = client();
+    let mut settings = AmneziaSettings::builder()
+        .version(AmneziaVersion::V2)
+        .header_width(16)
+        .build()
+        .unwrap();
+    settings.i1 = Some("<b 0xf6ab3267fa><r 10>".parse().unwrap());
+    client.amnezia_settings = Some(settings.clone());
+
+    let proxy = client.to_clash("awg", None);
+    let option = &proxy["amnezia-wg-option"];
+
+    assert_eq!(option["jc"], settings.jc as u64);
+    assert_eq!(option["s4"], settings.s4 as u64);
+    assert_eq!(option["h2"], settings.h2.to_string().as_str());
+    assert_eq!(option["i1"], "<b 0xf6ab3267fa><r 10>");
+
+    let yaml = serde_yaml_ng::to_string(&proxy).unwrap();
+    let imported = Interface::from_clash(&serde_yaml_ng::from_str(&yaml).unwrap()).unwrap();
+    assert_eq!(imported.amnezia_settings, Some(settings));
+}
@@ -0,0 +1,155 @@
+#![cfg(feature = "amneziawg")]
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+/// Server with two clients; second one has only public key.
+fn server(amnezia_settings: Option<AmneziaSettings>) -> Interface {
+    let mut server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .endpoint("vpn.example.com")
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .build(),
+            PeerBuilder::new()
+                .public_key(PrivateKey::random().public_key())
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .build(),
+        ])
+        .build();
+    server.amnezia_settings = amnezia_settings;
+
+    server
+}
+
+fn junk_only() -> AmneziaSettings {
+    AmneziaSettings {
+        s1: 0,
+        s2: 0,
+        h1: 1.into(),
+        h2: 2.into(),
+        h3: 3.into(),
+        h4: 4.into(),
+        ..AmneziaSettings::random()
+    }
+}
+
+#[test]
+fn incompatible_settings() {
+    assert!(junk_only().is_vanilla_compatible());
+
+    let settings = AmneziaSettings {
+        s1: 20,
+        h3: 7.into(),
+        ..junk_only()
+    };
+    assert!(!settings.is_vanilla_compatible());
+    assert_eq!(
+        settings.incompatible_settings().collect::<Vec<_>>(),
+        ["S1", "H3"]
+    );
+}
+
+#[test]
+fn to_vanilla() {
+    let server = server(Some(AmneziaSettings::random()));
+    let conversion = server.to_vanilla(ToInterfaceOptions::new()).unwrap();
+
+    assert_eq!(conversion.interface.amnezia_settings, None);
+    assert_eq!(conversion.interface.peers, server.peers);
+
+    assert_eq!(conversion.clients.len(), 1);
+    assert_eq!(conversion.clients[0].amnezia_settings, None);
+    assert_eq!(
+        conversion.clients[0].peers[0].public_key(),
+        server.private_key.public_key()
+    );
+
+    let ConversionWarning::IncompatibleSettings { settings } = &conversion.warnings[0] else {
+        panic!("expected incompatible settings warning");
+    };
+    assert!(settings.contains(&"S1".to_string()));
+    assert_eq!(
+        conversion.warnings[1],
+        ConversionWarning::ManualClient {
+            public_key: server.peers[1].public_key()
+        }
+    );
+}
+
+#[test]
+fn to_vanilla_compatible() {
+    let conversion = server(Some(junk_only()))
+        .to_vanilla(ToInterfaceOptions::new())
+        .unwrap();
+
+    // only manual client is left
+    assert_eq!(conversion.warnings.len(), 1);
+    assert!(matches!(
+        conversion.warnings[0],
+        ConversionWarning::ManualClient { .. }
+    ));
+
+    // already vanilla
+    let server = server(None);
+    let conversion = server.to_vanilla(ToInterfaceOptions::new()).unwrap();
+    assert_eq!(conversion.interface, server);
+    assert_eq!(conversion.warnings.len(), 1);
+}
+
+#[test]
+fn to_amneziawg() {
+    let server = server(None);
+    let builder = AmneziaSettings::builder().preset(AmneziaPreset::Light);
+
+    let conversion = server
+        .to_amneziawg_with_rng(
+            &builder,
+            ToInterfaceOptions::new(),
+            &mut ChaCha20Rng::seed_from_u64(0),
+        )
+        .unwrap();
+    let settings = builder
+        .build_with_rng(&mut ChaCha20Rng::seed_from_u64(0))
+        .unwrap();
+
+    assert_eq!(
+        conversion.interface.amnezia_settings,
+        Some(settings.clone())
+    );
+    assert_eq!(conversion.clients.len(), 1);
+    assert_eq!(conversion.clients[0].amnezia_settings, Some(settings));
+    assert!(matches!(
+        conversion.warnings[..],
+        [
+            ConversionWarning::IncompatibleSettings { .. },
+            ConversionWarning::ManualClient { .. }
+        ]
+    ));
+
+    // round-trip
+    let vanilla = conversion
+        .interface
+        .to_vanilla(ToInterfaceOptions::new())
+        .unwrap();
+    assert_eq!(vanilla.interface, server);
+}
+
+#[test]
+fn to_amneziawg_errors() {
+    assert_eq!(
+        server(None).to_amneziawg(
+            &AmneziaSettings::builder().jc(0..=0),
+            ToInterfaceOptions::new()
+        ),
+        Err(WireguardError::InvalidAmneziaSetting("Jc".to_string()))
+    );
+
+    let mut server = server(None);
+    server.peers[0].allowed_ips = vec![as_ipnet!("192.168.0.2/32")];
+    assert_eq!(
+        server.to_amneziawg(&AmneziaSettingsBuilder::new(), ToInterfaceOptions::new()),
+        Err(WireguardError::NoAssignedIP)
+    );
+}
@@ -0,0 +1,197 @@
+use indoc::formatdoc;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn interface() -> Interface {
+    InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24"), as_ipnet!("fd00::1/128")])
+        .listen_port(51820)
+        .endpoint("vpn.example.com")
+        .dns(["1.1.1.1".to_string(), "1.0.0.1".to_string()])
+        .post_up(["echo up".to_string()])
+        .peers([PeerBuilder::new()
+            .allowed_ips([as_ipnet!("10.0.0.2/32"), as_ipnet!("fd00::2/128")])
+            .persistent_keepalive(25)
+            .build()])
+        .build()
+}
+
+fn public_key(peer: &Peer) -> PublicKey {
+    peer.key.clone().right_or_else(|key| key.public_key())
+}
+
+#[test]
+fn default_same_as_to_string() {
+    let interface = interface();
+    let options = FormatOptions::default();
+
+    assert_eq!(
+        interface.display_with(&options).to_string(),
+        interface.to_string()
+    );
+    assert_eq!(
+        interface.peers[0].display_with(&options).to_string(),
+        interface.peers[0].to_string()
+    );
+}
+
+#[test]
+fn strip_host_prefix() {
+    let interface = InterfaceBuilder::new()
+        .address([
+            as_ipnet!("10.0.0.1/32"),
+            as_ipnet!("fd00::1/128"),
+            as_ipnet!("fd00::1/32"),
+        ])
+        .build();
+
+    assert!(interface
+        .to_string()
+        .contains("Address = 10.0.0.1,fd00::1,fd00::1/32\n"));
+}
+
+#[test]
+fn custom() {
+    let interface = interface();
+    let options = FormatOptions::new()
+        .list_separator(", ")
+        .key_value_separator("=")
+        .interface_key_order([InterfaceKey::PrivateKey, InterfaceKey::ListenPort])
+        .peer_key_order([PeerKey::PublicKey])
+        .header_comment("Generated config\nDo not edit")
+        .name_comment(false)
+        .strip_host_prefix(false)
+        .section_spacing(false)
+        .blank_line_after_peer(false)
+        .trailing_newline(false);
+
+    assert_eq!(
+        interface.display_with(&options).to_string(),
+        formatdoc! {"
+            # Generated config
+            # Do not edit
+            [Interface]
+            PrivateKey={private_key}
+            ListenPort=51820
+            Address=10.0.0.1/24, fd00::1/128
+            DNS=1.1.1.1, 1.0.0.1
+            PostUp=echo up
+            [Peer]
+            PublicKey={public_key}
+            AllowedIPs=10.0.0.2/32, fd00::2/128
+            PersistentKeepalive=25",
+            private_key = interface.private_key,
+            public_key = public_key(&interface.peers[0]),
+        }
+    );
+}
+
+#[test]
+fn repeated_keys() {
+    let interface = interface();
+    let options = FormatOptions::new()
+        .interface_key_order([InterfaceKey::Dns, InterfaceKey::Dns, InterfaceKey::Address])
+        .peer_key_order([PeerKey::PublicKey, PeerKey::PublicKey]);
+
+    let output = interface.display_with(&options).to_string();
+    assert_eq!(output.matches("DNS = ").count(), 1);
+    assert_eq!(output.matches("Address = ").count(), 1);
+    assert_eq!(output.matches("PublicKey = ").count(), 1);
+    assert!(output.find("DNS = ") < output.find("Address = "));
+}
+
+#[test]
+fn config_writer_trailing_newline() {
+    let interface = interface();
+    let options = FormatOptions::new().trailing_newline(false);
+
+    let mut writer = ConfigWriter::with_options(Vec::new(), options.clone());
+    writer
+        .interface_section(&interface)
+        .and_then(|writer| writer.peers(&interface.peers))
+        .expect("failed to write interface");
+
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert_eq!(output, interface.display_with(&options).to_string());
+    assert_eq!(output, interface.to_string().trim_end_matches('\n'));
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn amnezia_settings() {
+    let interface = InterfaceBuilder::new()
+        .amnezia_settings(AmneziaSettings::random())
+        .build();
+    let options = FormatOptions::new()
+        .key_value_separator("=")
+        .section_spacing(false);
+
+    let output = interface.display_with(&options).to_string();
+
+    assert!(output.contains("\nJc="));
+    assert!(output.ends_with(&format!(
+        "H4={}\n",
+        interface.amnezia_settings.as_ref().unwrap().h4
+    )));
+}
+
+#[test]
+fn redacted() {
+    let mut interface = interface();
+    interface.peers[0].preshared_key = Some(PresharedKey::random());
+
+    let output = interface.display_redacted().to_string();
+
+    assert!(output.contains("PrivateKey = [REDACTED]\n"));
+    assert!(output.contains("PresharedKey = [REDACTED]\n"));
+    assert!(!output.contains(&interface.private_key.to_string()));
+    assert!(!format!("{interface:?}").contains(&interface.private_key.to_string()));
+}
+
+#[test]
+fn zeroizing_string() {
+    let interface = interface();
+
+    let output = interface.to_zeroizing_string();
+
+    assert_eq!(*output, interface.to_string());
+    assert_eq!(output.capacity(), output.len());
+}
+
+#[test]
+fn key_sources() {
+    let mut interface = interface();
+    interface.peers[0].preshared_key = Some(PresharedKey::random());
+    let peer_public_key = public_key(&interface.peers[0]);
+
+    let options = FormatOptions::new()
+        .private_key_source(KeySource::File("/etc/wireguard/%i.key".into()))
+        .preshared_key_source(
+            peer_public_key.clone(),
+            KeySource::Command {
+                program: "pass".to_string(),
+                args: vec!["show".to_string(), "wireguard/peer 1".to_string()],
+            },
+        );
+
+    assert_eq!(
+        interface.display_with(&options).to_string(),
+        formatdoc! {"
+            [Interface]
+            # Name = vpn.example.com
+            Address = 10.0.0.1/24,fd00::1
+            ListenPort = 51820
+            DNS = 1.1.1.1,1.0.0.1
+
+            PostUp = wg set %i private-key /etc/wireguard/%i.key
+            PostUp = wg set %i peer {peer_public_key} preshared-key <(pass show 'wireguard/peer 1')
+            PostUp = echo up
+
+            [Peer]
+            AllowedIPs = 10.0.0.2/32,fd00::2/128
+            PublicKey = {peer_public_key}
+            PersistentKeepalive = 25
+
+        "}
+    );
+}
@@ -0,0 +1,250 @@
+#![cfg(feature = "handshake")]
+
+use claims::assert_matches;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+/// Server with one client, sharing preshared key. Returns server and client's configs.
+fn network() -> (Interface, Interface) {
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .endpoint("vpn.example.com")
+        .listen_port(51820)
+        .peers([PeerBuilder::new()
+            .allowed_ips([as_ipnet!("10.0.0.2/32")])
+            .preshared_key(PresharedKey::random())
+            .build()])
+        .build();
+
+    let mut client = server.peers[0]
+        .to_interface(&server, ToInterfaceOptions::new())
+        .unwrap();
+    client.peers[0].preshared_key = server.peers[0].preshared_key.clone();
+
+    (server, client)
+}
+
+/// Decode hex string.
+fn hex(value: &str) -> Vec<u8> {
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn handshake() {
+    let (server, client) = network();
+
+    let transcript = client.simulate_handshake(&server).unwrap();
+
+    assert!(transcript.keys_match());
+    assert_eq!(
+        transcript.client_keys.sending(),
+        transcript.server_keys.receiving()
+    );
+    assert_ne!(
+        transcript.client_keys.sending(),
+        transcript.client_keys.receiving()
+    );
+}
+
+#[test]
+fn messages() {
+    let (server, client) = network();
+
+    let transcript = client.simulate_handshake(&server).unwrap();
+    let server_public_key = server.private_key.public_key();
+    let client_public_key = client.private_key.public_key();
+
+    assert_eq!(transcript.initiation.len(), HANDSHAKE_INITIATION_LEN);
+    assert_eq!(&transcript.initiation[..4], &[1, 0, 0, 0]);
+    assert_eq!(transcript.response.len(), HANDSHAKE_RESPONSE_LEN);
+    assert_eq!(&transcript.response[..4], &[2, 0, 0, 0]);
+
+    // response's receiver index is initiation's sender index
+    assert_eq!(transcript.response[8..12], transcript.initiation[4..8]);
+
+    // mac1 is keyed by receiver's public key, mac2 is empty without cookie
+    assert!(verify_mac1(&server_public_key, &transcript.initiation));
+    assert!(!verify_mac1(&client_public_key, &transcript.initiation));
+    assert!(verify_mac1(&client_public_key, &transcript.response));
+    assert_eq!(transcript.initiation[132..], [0; 16]);
+    assert_eq!(transcript.response[76..], [0; 16]);
+
+    let mut tampered = transcript.initiation;
+    tampered[10] ^= 1;
+    assert!(!verify_mac1(&server_public_key, &tampered));
+}
+
+#[test]
+fn without_preshared_key() {
+    let (mut server, mut client) = network();
+    server.peers[0].preshared_key = None;
+    client.peers[0].preshared_key = None;
+
+    assert!(client.simulate_handshake(&server).unwrap().keys_match());
+}
+
+#[test]
+fn preshared_key_mismatch() {
+    let (server, mut client) = network();
+
+    client.peers[0].preshared_key = Some(PresharedKey::random());
+    assert_matches!(
+        client.simulate_handshake(&server),
+        Err(HandshakeMismatch::PresharedKeyMismatch { client: Some(client_hash), server: Some(server_hash) })
+            if client_hash != server_hash
+    );
+
+    client.peers[0].preshared_key = None;
+    assert_matches!(
+        client.simulate_handshake(&server),
+        Err(HandshakeMismatch::PresharedKeyMismatch {
+            client: None,
+            server: Some(_)
+        })
+    );
+}
+
+#[test]
+fn decryption_failed_display() {
+    assert_eq!(
+        HandshakeMismatch::DecryptionFailed {
+            field: HandshakeField::Timestamp
+        }
+        .to_string(),
+        "can't decrypt `timestamp` field of handshake message"
+    );
+}
+
+#[test]
+fn server_public_key_mismatch() {
+    let (server, mut client) = network();
+    let configured = PrivateKey::random().public_key();
+    client.peers[0].key = either::Either::Right(configured.clone());
+
+    assert_eq!(
+        client.simulate_handshake(&server).unwrap_err(),
+        HandshakeMismatch::ServerPublicKeyMismatch {
+            configured,
+            actual: server.private_key.public_key(),
+        }
+    );
+}
+
+#[test]
+fn unknown_client() {
+    let (server, mut client) = network();
+    client.private_key = PrivateKey::random();
+
+    assert_eq!(
+        client.simulate_handshake(&server).unwrap_err(),
+        HandshakeMismatch::UnknownClient {
+            client_public_key: client.private_key.public_key()
+        }
+    );
+}
+
+#[test]
+fn no_server_peer() {
+    let (server, mut client) = network();
+    client.peers = vec![];
+
+    assert_eq!(
+        client.simulate_handshake(&server).unwrap_err(),
+        HandshakeMismatch::NoServerPeer {
+            server_public_key: server.private_key.public_key()
+        }
+    );
+
+    // with several peers, server's one is searched by public key
+    client.peers = vec![
+        PeerBuilder::new().build(),
+        server.peers[0]
+            .to_interface(&server, ToInterfaceOptions::new())
+            .unwrap()
+            .peers[0]
+            .clone(),
+        PeerBuilder::new().build(),
+    ];
+    client.peers[1].preshared_key = server.peers[0].preshared_key.clone();
+    assert!(client.simulate_handshake(&server).is_ok());
+}
+
+#[test]
+fn deterministic() {
+    let (server, client) = network();
+
+    let first = client
+        .simulate_handshake_with_rng(&server, &mut ChaCha20Rng::seed_from_u64(0))
+        .unwrap();
+    let second = client
+        .simulate_handshake_with_rng(&server, &mut ChaCha20Rng::seed_from_u64(0))
+        .unwrap();
+
+    // ephemeral keys and indices are the same, timestamps are not
+    assert_eq!(first.initiation[..40], second.initiation[..40]);
+    assert_eq!(first.response[..44], second.response[..44]);
+}
+
+#[test]
+fn transport_keys_debug_redacted() {
+    let (server, client) = network();
+
+    let transcript = client.simulate_handshake(&server).unwrap();
+    let debug = format!("{:?}", transcript.client_keys);
+
+    assert!(debug.contains("REDACTED"));
+}
+
+/// Expected values are computed by independent implementation of the whitepaper (Python's
+/// `hashlib` and `cryptography`), given initiator's ephemeral public key.
+#[test]
+fn known_answer() {
+    let server_private_key = PrivateKey::from([1; 32]);
+    let client_private_key = PrivateKey::from([2; 32]);
+    let preshared_key = PresharedKey::from([3; 32]);
+
+    let server = InterfaceBuilder::new()
+        .private_key(server_private_key.clone())
+        .peers([PeerBuilder::new()
+            .key(either::Either::Right(client_private_key.public_key()))
+            .preshared_key(preshared_key.clone())
+            .build()])
+        .build();
+    let client = InterfaceBuilder::new()
+        .private_key(client_private_key)
+        .peers([PeerBuilder::new()
+            .key(either::Either::Right(server_private_key.public_key()))
+            .preshared_key(preshared_key)
+            .build()])
+        .build();
+
+    let transcript = client
+        .simulate_handshake_with_rng(&server, &mut ChaCha20Rng::seed_from_u64(0))
+        .unwrap();
+
+    // type, sender index, ephemeral public key and encrypted static public key
+    assert_eq!(
+        transcript.initiation[..88],
+        hex(concat!(
+            "01000000b2f7f581",
+            "101d4536ac7ca089db5becf59c76896ee7420a50464a86dd1a69779258d5803f",
+            "5ae6657252d1755f9381e05d819a74e60bd59f4716a494ff66c46bda60638c91",
+            "6cb89f82b76ff92aae9683e5b84bbae7",
+        ))
+    );
+
+    // encrypted timestamp differs between runs, so `mac1` is checked on recorded one
+    let message = [
+        &transcript.initiation[..88],
+        &hex("b964aedf4d3bb85dd63aec14f430fb7416426bf74393e33185980d97"),
+    ]
+    .concat();
+    assert_eq!(
+        compute_mac1(&server.private_key.public_key(), &message),
+        *hex("a9714bffd846263ae8ef9b1cdf0711de")
+    );
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "mobileconfig")]
+use plist::{Dictionary, Value};
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn client() -> Interface {
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .endpoint("vpn.example.com")
+        .listen_port(51821)
+        .dns(["1.1.1.1".to_string()])
+        .peers([PeerBuilder::new()
+            .allowed_ips([as_ipnet!("10.0.0.2/32")])
+            .build()])
+        .build();
+
+    server.peers[0]
+        .to_interface(&server, ToInterfaceOptions::new().default_gateway(true))
+        .unwrap()
+}
+
+fn parse(xml: &str) -> Dictionary {
+    Value::from_reader_xml(xml.as_bytes())
+        .unwrap()
+        .into_dictionary()
+        .unwrap()
+}
+
+fn vpn_payload(profile: &Dictionary) -> &Dictionary {
+    let content = profile["PayloadContent"].as_array().unwrap();
+    assert_eq!(content.len(), 1);
+
+    content[0].as_dictionary().unwrap()
+}
+
+fn is_uuid(value: &Value) -> bool {
+    let value = value.as_string().unwrap();
+    let parts: Vec<usize> = value.split('-').map(str::len).collect();
+
+    parts == [8, 4, 4, 4, 12] && value.chars().all(|c| c == '-' || c.is_ascii_hexdigit())
+}
+
+#[test]
+fn profile_structure() {
+    let client = client();
+    let options = MobileConfigOptions::new("com.example.vpn").display_name("Example VPN");
+
+    let profile = parse(&client.to_mobileconfig(&options).unwrap());
+
+    assert_eq!(profile["PayloadType"].as_string(), Some("Configuration"));
+    assert_eq!(profile["PayloadVersion"].as_unsigned_integer(), Some(1));
+    assert_eq!(
+        profile["PayloadIdentifier"].as_string(),
+        Some("com.example.vpn")
+    );
+    assert_eq!(
+        profile["PayloadDisplayName"].as_string(),
+        Some("Example VPN")
+    );
+    assert!(is_uuid(&profile["PayloadUUID"]));
+
+    let payload = vpn_payload(&profile);
+    assert_eq!(
+        payload["PayloadType"].as_string(),
+        Some("com.apple.vpn.managed")
+    );
+    assert_eq!(
+        payload["PayloadIdentifier"].as_string(),
+        Some("com.example.vpn.wireguard")
+    );
+    assert!(is_uuid(&payload["PayloadUUID"]));
+    assert_ne!(payload["PayloadUUID"], profile["PayloadUUID"]);
+    assert_eq!(payload["UserDefinedName"].as_string(), Some("Example VPN"));
+    assert_eq!(payload["VPNType"].as_string(), Some("VPN"));
+    assert_eq!(payload["VPNSubType"].as_string(), Some("com.wireguard.ios"));
+
+    let vendor_config = payload["VendorConfig"].as_dictionary().unwrap();
+    assert_eq!(
+        vendor_config["WgQuickConfig"].as_string(),
+        Some(client.to_string().as_str())
+    );
+
+    let vpn = payload["VPN"].as_dictionary().unwrap();
+    assert_eq!(
+        vpn["RemoteAddress"].as_string(),
+        Some("vpn.example.com:51821")
+    );
+    assert_eq!(vpn["AuthenticationMethod"].as_string(), Some("Password"));
+    assert!(vpn.get("OnDemandEnabled").is_none());
+    assert!(vpn.get("OnDemandRules").is_none());
+}
+
+#[test]
+fn on_demand_rules() {
+    let options = MobileConfigOptions::new("com.example.vpn")
+        .platform(ApplePlatform::MacOs)
+        .on_demand_rules([
+            OnDemandRule::exclude_ssids(["Home", "Office <5GHz>"]),
+            OnDemandRule::connect_on(NetworkInterfaceType::Cellular),
+            OnDemandRule::disconnect_on(NetworkInterfaceType::Ethernet),
+            OnDemandRule::new(OnDemandAction::Connect),
+        ]);
+
+    let profile = parse(&client().to_mobileconfig(&options).unwrap());
+    let payload = vpn_payload(&profile);
+
+    assert_eq!(
+        payload["VPNSubType"].as_string(),
+        Some("com.wireguard.macos")
+    );
+
+    let vpn = payload["VPN"].as_dictionary().unwrap();
+    assert_eq!(vpn["OnDemandEnabled"].as_unsigned_integer(), Some(1));
+
+    let mut exclude = Dictionary::new();
+    exclude.insert("Action".to_string(), "Disconnect".into());
+    exclude.insert("InterfaceTypeMatch".to_string(), "WiFi".into());
+    exclude.insert(
+        "SSIDMatch".to_string(),
+        Value::Array(vec!["Home".into(), "Office <5GHz>".into()]),
+    );
+    let mut cellular = Dictionary::new();
+    cellular.insert("Action".to_string(), "Connect".into());
+    cellular.insert("InterfaceTypeMatch".to_string(), "Cellular".into());
+    let mut ethernet = Dictionary::new();
+    ethernet.insert("Action".to_string(), "Disconnect".into());
+    ethernet.insert("InterfaceTypeMatch".to_string(), "Ethernet".into());
+    let mut fallback = Dictionary::new();
+    fallback.insert("Action".to_string(), "Connect".into());
+
+    assert_eq!(
+        vpn["OnDemandRules"],
+        Value::Array(vec![
+            exclude.into(),
+            cellular.into(),
+            ethernet.into(),
+            fallback.into()
+        ])
+    );
+}
+
+#[test]
+fn escapes_config() {
+    let mut client = client();
+    client.dns = vec!["1.1.1.1".to_string(), "corp&home<done>".to_string()];
+
+    let xml = client
+        .to_mobileconfig(&MobileConfigOptions::new("com.example.vpn"))
+        .unwrap();
+    assert!(!xml.contains("<done>"));
+
+    let profile = parse(&xml);
+    let vendor_config = vpn_payload(&profile)["VendorConfig"]
+        .as_dictionary()
+        .unwrap();
+    assert_eq!(
+        vendor_config["WgQuickConfig"].as_string(),
+        Some(client.to_string().as_str())
+    );
+}
+
+#[test]
+fn rejects_unsupported_keys() {
+    let mut client = client();
+    client.table = Some(Table::Off);
+    client.post_up = vec!["echo up".to_string()];
+
+    assert!(matches!(
+        client.to_mobileconfig(&MobileConfigOptions::new("com.example.vpn")),
+        Err(WireguardError::InvalidMobileConfig(reason)) if reason.contains("Table, PostUp")
+    ));
+}
+
+#[test]
+fn requires_endpoint() {
+    let mut client = client();
+    client.peers[0].endpoint = None;
+
+    assert!(matches!(
+        client.to_mobileconfig(&MobileConfigOptions::new("com.example.vpn")),
+        Err(WireguardError::InvalidMobileConfig(_))
+    ));
+}
+
+#[test]
+fn reproducible_uuids() {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    let client = client();
+    let options = MobileConfigOptions::new("com.example.vpn");
+    let profile = |seed| {
+        client
+            .to_mobileconfig_with_rng(&options, &mut ChaCha20Rng::seed_from_u64(seed))
+            .unwrap()
+    };
+
+    assert_eq!(profile(1), profile(1));
+    assert_ne!(profile(1), profile(2));
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn rejects_amnezia_settings() {
+    let mut client = client();
+    client.amnezia_settings = Some(AmneziaSettings::random());
+
+    assert!(matches!(
+        client.to_mobileconfig(&MobileConfigOptions::new("com.example.vpn")),
+        Err(WireguardError::InvalidMobileConfig(_))
+    ));
+}
@@ -2,7 +2,51 @@ use wireguard_conf::as_ipaddr;
 use wireguard_conf::as_ipnet;
 use wireguard_conf::prelude::*;
 
+mod amneziavpn;
+mod clash;
+mod conversion;
+mod format;
+mod handshake;
+mod mobileconfig;
+mod public;
+mod rosenpass;
+mod rotation;
+mod share_link;
+mod sing_box;
 mod to_string;
+mod write_to;
+
+/// Client of dual-stack server with default gateway, keepalive and preshared key.
+#[cfg(any(
+    feature = "amneziavpn",
+    feature = "clash",
+    feature = "sharelink",
+    feature = "singbox"
+))]
+fn client(mtu: usize, dns: &[&str]) -> Interface {
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24"), as_ipnet!("fd00::1/64")])
+        .endpoint("vpn.example.com")
+        .listen_port(51821)
+        .dns(dns.iter().map(ToString::to_string).collect::<Vec<_>>())
+        .peers([PeerBuilder::new()
+            .allowed_ips([as_ipnet!("10.0.0.2/32"), as_ipnet!("fd00::2/128")])
+            .build()])
+        .build();
+
+    let mut client = server.peers[0]
+        .to_interface(
+            &server,
+            ToInterfaceOptions::new()
+                .default_gateway(true)
+                .persistent_keepalive(25),
+        )
+        .unwrap();
+    client.mtu = Some(mtu);
+    client.peers[0].preshared_key = Some(PresharedKey::random());
+
+    client
+}
 
 #[test]
 fn empty_interface() {
@@ -0,0 +1,93 @@
+use indoc::formatdoc;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn interface() -> Interface {
+    InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .listen_port(51820)
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .preshared_key(PresharedKey::random())
+                .build(),
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .public_key(PrivateKey::random().public_key())
+                .build(),
+        ])
+        .build()
+}
+
+#[test]
+fn omit_preshared_keys() {
+    let interface = interface();
+
+    let public = interface.to_public(ToPublicOptions::new());
+
+    assert_eq!(public.public_key, interface.private_key.public_key());
+    assert_eq!(public.peers[0].preshared_key_hash, None);
+    assert_eq!(
+        public.peers[0].public_key,
+        interface.peers[0]
+            .key
+            .clone()
+            .right_or_else(|key| key.public_key())
+    );
+    assert_eq!(
+        public.peers[1].public_key,
+        interface.peers[1].key.clone().unwrap_right()
+    );
+}
+
+#[test]
+fn hash_preshared_keys() {
+    let interface = interface();
+    let preshared_key = interface.peers[0].preshared_key.clone().unwrap();
+
+    let public = interface.to_public(ToPublicOptions::new().hash_preshared_keys(true));
+
+    assert_eq!(
+        public.peers[0].preshared_key_hash,
+        Some(preshared_key.hash())
+    );
+    assert_ne!(
+        preshared_key.hash().as_bytes(),
+        preshared_key.as_bytes(),
+        "hash must not be the key itself"
+    );
+}
+
+#[test]
+fn to_string() {
+    let interface = interface();
+    let preshared_key = interface.peers[0].preshared_key.clone().unwrap();
+
+    let public = interface.to_public(ToPublicOptions::new().hash_preshared_keys(true));
+
+    assert_eq!(
+        public.to_string(),
+        formatdoc! {"
+            # Public view of Wireguard config. Private keys are removed.
+            [Interface]
+            Address = 10.0.0.1/24
+            ListenPort = 51820
+            PublicKey = {public_key}
+
+            [Peer]
+            AllowedIPs = 10.0.0.2/32
+            PublicKey = {peer1_public_key}
+            PresharedKeyHash = {preshared_key_hash}
+
+
+            [Peer]
+            AllowedIPs = 10.0.0.3/32
+            PublicKey = {peer2_public_key}
+
+            ",
+            public_key = interface.private_key.public_key(),
+            peer1_public_key = public.peers[0].public_key,
+            peer2_public_key = public.peers[1].public_key,
+            preshared_key_hash = preshared_key.hash(),
+        }
+    );
+}
@@ -0,0 +1,141 @@
+#![cfg(feature = "rosenpass")]
+use indoc::formatdoc;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+use std::fs;
+use std::path::Path;
+
+fn server() -> Interface {
+    InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .listen_port(51820)
+        .endpoint("vpn.example.com")
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .preshared_key(PresharedKey::random())
+                .build(),
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .build(),
+        ])
+        .build()
+}
+
+#[test]
+fn server_config() {
+    let server = server();
+    let alice = server.peers[0].public_key();
+    let bob = server.peers[1].public_key();
+
+    let options = RosenpassOptions::new()
+        .key_dir("/var/lib/rosenpass")
+        .device("wg-vpn")
+        .verbosity(RosenpassVerbosity::Verbose)
+        .peer_name(alice.clone(), "alice");
+    let rosenpass = server.to_rosenpass("server", &options);
+
+    assert_eq!(
+        rosenpass.config.to_string(),
+        formatdoc! {r#"
+            public_key = "/var/lib/rosenpass/server.rosenpass-public/pqpk"
+            secret_key = "/var/lib/rosenpass/server.rosenpass-secret/pqsk"
+            listen = ["[::]:9999"]
+            verbosity = "Verbose"
+
+            [[peers]]
+            public_key = "/var/lib/rosenpass/alice.rosenpass-public/pqpk"
+            device = "wg-vpn"
+            peer = "{alice}"
+
+            [[peers]]
+            public_key = "/var/lib/rosenpass/peer-{bob_hex}.rosenpass-public/pqpk"
+            device = "wg-vpn"
+            peer = "{bob}"
+        "#,
+            bob_hex = &bob.to_hex()[..8],
+        }
+    );
+
+    // preshared keys are managed by Rosenpass
+    for peer in &rosenpass.interface.peers {
+        assert!(peer.external_preshared_key);
+        assert_eq!(peer.preshared_key, None);
+    }
+    assert!(!rosenpass.interface.to_string().contains("PresharedKey"));
+    assert_eq!(rosenpass.interface.private_key, server.private_key);
+}
+
+#[test]
+fn client_config() {
+    let server = server();
+    let client = server.peers[0]
+        .to_interface(&server, ToInterfaceOptions::new())
+        .unwrap();
+
+    let options = RosenpassOptions::new()
+        .port(7777)
+        .peer_name(server.private_key.public_key(), "server");
+    let rosenpass = client.to_rosenpass("alice", &options);
+
+    // client only initiates key exchange, so it doesn't listen
+    assert!(rosenpass.config.listen.is_empty());
+    assert_eq!(
+        rosenpass.config.peers,
+        [RosenpassPeer {
+            public_key: "/etc/rosenpass/server.rosenpass-public/pqpk".into(),
+            endpoint: Some("vpn.example.com:7777".to_string()),
+            device: "wg0".to_string(),
+            peer: server.private_key.public_key(),
+        }]
+    );
+    assert!(rosenpass
+        .config
+        .to_string()
+        .contains("endpoint = \"vpn.example.com:7777\"\n"));
+}
+
+#[test]
+fn key_files() {
+    let key_dir =
+        std::env::temp_dir().join(format!("wireguard-conf-rosenpass-{}", std::process::id()));
+    let server = server();
+
+    let keys = server
+        .to_rosenpass("server", &RosenpassOptions::new().key_dir(&key_dir))
+        .keys;
+    keys.write_wireguard_keys(&server.private_key).unwrap();
+
+    assert_eq!(
+        keys.wireguard_private_key(),
+        key_dir.join("server.rosenpass-secret/wgsk")
+    );
+    assert_eq!(
+        PrivateKey::from_file(keys.wireguard_private_key()).unwrap(),
+        server.private_key
+    );
+    assert_eq!(
+        PublicKey::from_file(keys.wireguard_public_key()).unwrap(),
+        server.private_key.public_key()
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&keys.secret_dir), 0o700);
+        assert_eq!(mode(&keys.wireguard_private_key()), 0o600);
+    }
+
+    assert_eq!(
+        keys.gen_keys_command(),
+        format!(
+            "rosenpass gen-keys --secret-key {} --public-key {}",
+            keys.secret_key().display(),
+            keys.public_key().display()
+        )
+    );
+
+    fs::remove_dir_all(&key_dir).unwrap();
+}
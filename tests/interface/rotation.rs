@@ -0,0 +1,163 @@
+use either::Either;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+use std::time::{Duration, SystemTime};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Server with two clients. Returns server and clients' configs.
+fn network() -> (Interface, Vec<Interface>) {
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .endpoint("vpn.example.com")
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .build(),
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .build(),
+        ])
+        .build();
+
+    let clients = server
+        .peers
+        .iter()
+        .map(|peer| {
+            peer.to_interface(&server, ToInterfaceOptions::new())
+                .unwrap()
+        })
+        .collect();
+
+    (server, clients)
+}
+
+#[test]
+fn rotate_server_key() {
+    let (mut server, clients) = network();
+    let old_public_key = server.private_key.public_key();
+
+    let rotation = server.rotate_key();
+
+    assert_eq!(rotation.old_public_key, old_public_key);
+    assert_eq!(rotation.new_private_key, server.private_key);
+    assert_ne!(rotation.new_public_key(), old_public_key);
+
+    // both clients have server as peer
+    let updates = rotation.affected_configs(&clients);
+    assert_eq!(
+        updates
+            .iter()
+            .map(|update| update.index)
+            .collect::<Vec<_>>(),
+        [0, 1]
+    );
+    for update in updates {
+        let client = &clients[update.index];
+
+        assert_eq!(update.config.private_key, client.private_key);
+        assert_eq!(
+            update.config.peers[0].public_key(),
+            rotation.new_public_key()
+        );
+        assert_eq!(update.config.peers[0].endpoint, client.peers[0].endpoint);
+    }
+}
+
+#[test]
+fn rotate_client_key() {
+    let (mut server, clients) = network();
+    let old_client = clients[1].clone();
+
+    let rotation = server.peers[1].rotate_key();
+    assert_eq!(rotation.old_public_key, old_client.private_key.public_key());
+    assert_eq!(
+        server.peers[1].key,
+        Either::Left(rotation.new_private_key.clone())
+    );
+
+    // only the client's own config is affected
+    let updates = rotation.affected_configs(&clients);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].index, 1);
+    assert_eq!(updates[0].config.private_key, rotation.new_private_key);
+    assert_eq!(updates[0].config.peers, old_client.peers);
+
+    // updated client config is same as regenerated one
+    assert_eq!(
+        updates[0].config,
+        server.peers[1]
+            .to_interface(&server, ToInterfaceOptions::new())
+            .unwrap()
+    );
+}
+
+#[test]
+fn rotate_public_key_peer() {
+    let mut server = InterfaceBuilder::new()
+        .peers([PeerBuilder::new()
+            .public_key(PrivateKey::random().public_key())
+            .build()])
+        .build();
+    let old_server = server.clone();
+
+    let rotation = server.peers[0].rotate_key();
+
+    // peer in other config, that had only public key, gets only new public key
+    let updated = rotation.apply(&old_server).unwrap();
+    assert_eq!(
+        updated.peers[0].key,
+        Either::Right(rotation.new_public_key())
+    );
+
+    assert_eq!(rotation.apply(&InterfaceBuilder::new().build()), None);
+}
+
+#[test]
+fn rotate_with_rng() {
+    let (mut first, _) = network();
+    let mut second = first.clone();
+
+    let first_rotation = first.rotate_key_with_rng(&mut ChaCha20Rng::seed_from_u64(1));
+    let second_rotation = second.rotate_key_with_rng(&mut ChaCha20Rng::seed_from_u64(1));
+
+    assert_eq!(
+        first_rotation.new_private_key,
+        second_rotation.new_private_key
+    );
+}
+
+#[test]
+fn metadata_max_age() {
+    let (mut server, _) = network();
+    let created_at = SystemTime::UNIX_EPOCH + 1000 * DAY;
+    let max_age = 90 * DAY;
+
+    let mut metadata = KeyMetadata::new();
+    let public_key = server.private_key.public_key();
+    metadata.track(public_key.clone(), created_at);
+
+    assert_eq!(metadata.age(&public_key, created_at + DAY), Some(DAY));
+    assert!(!metadata.needs_rotation(&public_key, max_age, created_at + 30 * DAY));
+    assert!(metadata.needs_rotation(&public_key, max_age, created_at + 91 * DAY));
+    assert_eq!(metadata.expired(max_age, created_at + 91 * DAY).len(), 1);
+
+    // untracked keys always need rotation
+    let untracked = PrivateKey::random().public_key();
+    assert!(metadata.needs_rotation(&untracked, max_age, created_at));
+
+    let rotation = server.rotate_key();
+    metadata.record(&rotation);
+
+    assert_eq!(metadata.records().len(), 1);
+    assert_eq!(metadata.get(&public_key), None);
+
+    let record = metadata.get(&rotation.new_public_key()).unwrap();
+    assert_eq!(record.created_at, rotation.rotated_at);
+    assert_eq!(record.previous_public_key, Some(public_key));
+    assert!(metadata
+        .expired(max_age, rotation.rotated_at + 30 * DAY)
+        .is_empty());
+}
@@ -0,0 +1,150 @@
+#![cfg(feature = "sharelink")]
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn client() -> Interface {
+    super::client(1280, &[])
+}
+
+/// Percent-encode base64 key by hand.
+fn encoded(key: &impl ToString) -> String {
+    key.to_string()
+        .replace('+', "%2B")
+        .replace('/', "%2F")
+        .replace('=', "%3D")
+}
+
+#[test]
+fn export_link() {
+    let client = client();
+    let server = &client.peers[0];
+
+    let link = client.to_share_link("Home VPN", Some([1, 2, 3])).unwrap();
+
+    assert_eq!(
+        link,
+        format!(
+            "wireguard://{}@vpn.example.com:51821?publickey={}&presharedkey={}\
+             &address=10.0.0.2%2F24,fd00%3A%3A2%2F64&allowedips=0.0.0.0%2F0,%3A%3A%2F0\
+             &mtu=1280&keepalive=25&reserved=1,2,3#Home%20VPN",
+            encoded(&client.private_key),
+            encoded(&server.public_key()),
+            encoded(server.preshared_key.as_ref().unwrap()),
+        )
+    );
+}
+
+#[test]
+fn round_trip() {
+    let client = client();
+
+    let link = client.to_share_link("wg", Some([0, 0, 0])).unwrap();
+    let imported = Interface::from_share_link(&link).unwrap();
+
+    assert_eq!(imported.address, client.address);
+    assert_eq!(imported.private_key, client.private_key);
+    assert_eq!(imported.mtu, client.mtu);
+
+    let (imported, server) = (&imported.peers[0], &client.peers[0]);
+    assert_eq!(imported.public_key(), server.public_key());
+    assert_eq!(imported.endpoint, server.endpoint);
+    assert_eq!(imported.allowed_ips, server.allowed_ips);
+    assert_eq!(imported.persistent_keepalive, 25);
+    assert_eq!(imported.preshared_key, server.preshared_key);
+}
+
+#[test]
+fn ipv6_endpoint() {
+    let mut client = client();
+    client.peers[0].endpoint = Some("[2001:db8::1]:51820".to_string());
+
+    let link = client.to_share_link("wg", None).unwrap();
+    assert!(link.contains("@[2001:db8::1]:51820?"));
+
+    let imported = Interface::from_share_link(&link).unwrap();
+    assert_eq!(
+        imported.peers[0].endpoint.as_deref(),
+        Some("[2001:db8::1]:51820")
+    );
+}
+
+#[test]
+fn import_v2rayn_link() {
+    let private_key = PrivateKey::random();
+    let public_key = PrivateKey::random().public_key();
+
+    // v2rayN adds trailing slash and unknown parameters, and keeps some characters unencoded
+    let link = format!(
+        "wireguard://{}@162.159.192.1:2408/?publickey={}&reserved=0,0,0\
+         &address=172.16.0.2,2606:4700:110:8f81::2&mtu=1280&security=none#warp",
+        encoded(&private_key),
+        public_key,
+    );
+
+    let interface = Interface::from_share_link(&link).unwrap();
+
+    assert_eq!(interface.private_key, private_key);
+    assert_eq!(
+        interface.address,
+        vec![
+            as_ipnet!("172.16.0.2/32"),
+            as_ipnet!("2606:4700:110:8f81::2/128")
+        ]
+    );
+    assert_eq!(interface.mtu, Some(1280));
+    assert_eq!(interface.peers[0].public_key(), public_key);
+    assert_eq!(
+        interface.peers[0].endpoint.as_deref(),
+        Some("162.159.192.1:2408")
+    );
+    assert_eq!(
+        interface.peers[0].allowed_ips,
+        vec![as_ipnet!("0.0.0.0/0"), as_ipnet!("::/0")]
+    );
+}
+
+#[test]
+fn export_errors() {
+    let mut no_endpoint = client();
+    no_endpoint.peers[0].endpoint = None;
+    assert!(matches!(
+        no_endpoint.to_share_link("wg", None),
+        Err(WireguardError::InvalidShareLink(_))
+    ));
+
+    let mut two_peers = client();
+    two_peers.peers.push(PeerBuilder::new().build());
+    assert!(matches!(
+        two_peers.to_share_link("wg", None),
+        Err(WireguardError::InvalidShareLink(_))
+    ));
+}
+
+#[test]
+fn import_errors() {
+    let link = client().to_share_link("wg", None).unwrap();
+
+    for link in [
+        link.replacen("wireguard://", "vless://", 1),
+        link.replacen(":51821?", "?", 1),
+        link.replacen("mtu=1280", "mtu=big", 1),
+        link.replacen("address=", "address=10.0.0,", 1),
+        link.replacen("#wg", "&reserved=1,2#wg", 1),
+    ] {
+        assert!(
+            matches!(
+                Interface::from_share_link(&link),
+                Err(WireguardError::InvalidShareLink(_))
+            ),
+            "{link}"
+        );
+    }
+
+    let mut bad_key = client();
+    bad_key.peers[0].preshared_key = None;
+    let link = bad_key.to_share_link("wg", None).unwrap();
+    let (prefix, rest) = link.split_once("publickey=").unwrap();
+    assert!(matches!(
+        Interface::from_share_link(&format!("{prefix}publickey=AAAA{rest}")),
+        Err(WireguardError::InvalidPublicKey)
+    ));
+}
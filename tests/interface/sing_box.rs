@@ -0,0 +1,181 @@
+#![cfg(feature = "singbox")]
+use serde_json::json;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn client() -> Interface {
+    super::client(1380, &[])
+}
+
+#[test]
+fn export_endpoint() {
+    let client = client();
+    let server = &client.peers[0];
+
+    assert_eq!(
+        client.to_sing_box("wg-out"),
+        json!({
+            "type": "wireguard",
+            "tag": "wg-out",
+            "address": ["10.0.0.2/24", "fd00::2/64"],
+            "private_key": client.private_key.to_string(),
+            "mtu": 1380,
+            "peers": [{
+                "address": "vpn.example.com",
+                "port": 51821,
+                "public_key": server.public_key().to_string(),
+                "pre_shared_key": server.preshared_key.as_ref().unwrap().to_string(),
+                "allowed_ips": server
+                    .allowed_ips
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                "persistent_keepalive_interval": 25,
+            }],
+        })
+    );
+}
+
+#[cfg(feature = "rosenpass")]
+#[test]
+fn export_skips_external_preshared_key() {
+    let mut client = client();
+    client.peers[0].external_preshared_key = true;
+
+    let endpoint = client.to_sing_box("wg-out");
+
+    assert!(endpoint["peers"][0].get("pre_shared_key").is_none());
+}
+
+#[test]
+fn round_trip() {
+    let client = client();
+
+    let imported = Interface::from_sing_box(&client.to_sing_box("wg-out")).unwrap();
+
+    assert_eq!(imported.address, client.address);
+    assert_eq!(imported.private_key, client.private_key);
+    assert_eq!(imported.mtu, client.mtu);
+
+    let (imported, server) = (&imported.peers[0], &client.peers[0]);
+    assert_eq!(imported.public_key(), server.public_key());
+    assert_eq!(imported.endpoint, server.endpoint);
+    assert_eq!(imported.allowed_ips, server.allowed_ips);
+    assert_eq!(imported.persistent_keepalive, 25);
+    assert_eq!(imported.preshared_key, server.preshared_key);
+}
+
+#[test]
+fn ipv6_endpoint() {
+    let mut client = client();
+    client.peers[0].endpoint = Some("[2001:db8::1]:51821".to_string());
+
+    let endpoint = client.to_sing_box("wg-out");
+    assert_eq!(endpoint["peers"][0]["address"], "2001:db8::1");
+    assert_eq!(endpoint["peers"][0]["port"], 51821);
+
+    let imported = Interface::from_sing_box(&endpoint).unwrap();
+    assert_eq!(
+        imported.peers[0].endpoint.as_deref(),
+        Some("[2001:db8::1]:51821")
+    );
+}
+
+#[test]
+fn import_legacy_outbound() {
+    let private_key = PrivateKey::random();
+    let public_key = PrivateKey::random().public_key();
+
+    let interface = Interface::from_sing_box(&json!({
+        "type": "wireguard",
+        "tag": "wg-out",
+        "server": "vpn.example.com",
+        "server_port": 51820,
+        "local_address": ["10.0.0.2/32", "fd00::2"],
+        "private_key": private_key.to_string(),
+        "peer_public_key": public_key.to_string(),
+        "mtu": 1408,
+    }))
+    .unwrap();
+
+    assert_eq!(interface.private_key, private_key);
+    assert_eq!(
+        interface.address,
+        vec![as_ipnet!("10.0.0.2/32"), as_ipnet!("fd00::2/128")]
+    );
+    assert_eq!(interface.mtu, Some(1408));
+    assert_eq!(interface.peers.len(), 1);
+    assert_eq!(interface.peers[0].public_key(), public_key);
+    assert_eq!(
+        interface.peers[0].endpoint.as_deref(),
+        Some("vpn.example.com:51820")
+    );
+    assert_eq!(
+        interface.peers[0].allowed_ips,
+        vec![as_ipnet!("0.0.0.0/0"), as_ipnet!("::/0")]
+    );
+}
+
+#[test]
+fn import_errors() {
+    let endpoint = client().to_sing_box("wg-out");
+
+    let mut wrong_type = endpoint.clone();
+    wrong_type["type"] = json!("vless");
+    assert!(matches!(
+        Interface::from_sing_box(&wrong_type),
+        Err(WireguardError::InvalidSingBox(_))
+    ));
+
+    let mut no_peers = endpoint.clone();
+    no_peers.as_object_mut().unwrap().remove("peers");
+    assert!(matches!(
+        Interface::from_sing_box(&no_peers),
+        Err(WireguardError::InvalidSingBox(_))
+    ));
+
+    let mut bad_port = endpoint.clone();
+    bad_port["peers"][0]["port"] = json!(70000);
+    assert!(matches!(
+        Interface::from_sing_box(&bad_port),
+        Err(WireguardError::InvalidSingBox(_))
+    ));
+
+    let mut bad_key = endpoint;
+    bad_key["private_key"] = json!("not a key");
+    assert!(matches!(
+        Interface::from_sing_box(&bad_key),
+        Err(WireguardError::InvalidPrivateKey)
+    ));
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn amnezia_fields() {
+    let mut client = client();
+    let mut settings = AmneziaSettings::builder()
+        .version(AmneziaVersion::V2)
+        .header_width(16)
+        .build()
+        .unwrap();
+    settings.i1 = Some("<b 0xf6ab3267fa><r 10>".parse().unwrap());
+    client.amnezia_settings = Some(settings.clone());
+
+    let endpoint = client.to_sing_box("awg-out");
+    let amnezia = &endpoint["amnezia"];
+
+    assert_eq!(amnezia["jc"], json!(settings.jc));
+    assert_eq!(amnezia["s3"], json!(settings.s3));
+    assert_eq!(amnezia["h1"], json!(settings.h1.to_string()));
+    assert_eq!(amnezia["i1"], json!("<b 0xf6ab3267fa><r 10>"));
+
+    let imported = Interface::from_sing_box(&endpoint).unwrap();
+    assert_eq!(imported.amnezia_settings, Some(settings));
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn vanilla_has_no_amnezia_fields() {
+    let endpoint = client().to_sing_box("wg-out");
+
+    assert!(endpoint.get("amnezia").is_none());
+}
@@ -0,0 +1,84 @@
+use indoc::formatdoc;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn interface_with_peers() -> Interface {
+    InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .listen_port(51820)
+        .post_up(["echo up".to_string()])
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .build(),
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .persistent_keepalive(25)
+                .build(),
+        ])
+        .build()
+}
+
+#[test]
+fn same_as_to_string() {
+    let interface = interface_with_peers();
+
+    let mut output = Vec::new();
+    interface
+        .write_to(&mut output)
+        .expect("failed to write interface");
+
+    assert_eq!(String::from_utf8(output).unwrap(), interface.to_string());
+}
+
+#[test]
+fn peer_same_as_to_string() {
+    let peer = &interface_with_peers().peers[1];
+
+    let mut output = Vec::new();
+    peer.write_to(&mut output).expect("failed to write peer");
+
+    assert_eq!(String::from_utf8(output).unwrap(), peer.to_string());
+}
+
+#[test]
+fn config_writer_subset_of_peers() {
+    let interface = interface_with_peers();
+
+    let mut writer = ConfigWriter::new(Vec::new());
+    writer
+        .interface_section(&interface)
+        .and_then(|writer| writer.peers(interface.peers.iter().skip(1)))
+        .expect("failed to write interface");
+
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        formatdoc! {"
+            [Interface]
+            Address = 10.0.0.1/24
+            ListenPort = 51820
+            PrivateKey = {private_key}
+
+            PostUp = echo up
+
+            [Peer]
+            AllowedIPs = 10.0.0.3/32
+            PublicKey = {public_key}
+            PersistentKeepalive = 25
+
+            ",
+            private_key = interface.private_key,
+            public_key = interface.peers[1].key.clone().right_or_else(|key| key.public_key()),
+        }
+    );
+}
+
+#[test]
+fn ipv6_slash_32_is_kept() {
+    let interface = InterfaceBuilder::new()
+        .address([as_ipnet!("fd00::1/32"), as_ipnet!("fd00::1/128")])
+        .build();
+
+    assert!(interface
+        .to_string()
+        .contains("Address = fd00::1/32,fd00::1\n"));
+}
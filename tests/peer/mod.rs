@@ -19,6 +19,8 @@ fn empty_peer() {
             persistent_keepalive: 0,
             key,
             preshared_key: None,
+            #[cfg(feature = "rosenpass")]
+            external_preshared_key: false,
         }
     );
 }
@@ -72,3 +74,15 @@ fn preshared_key() {
 
     assert_eq!(peer.preshared_key, Some(preshared_key));
 }
+
+#[cfg(feature = "rosenpass")]
+#[test]
+fn external_preshared_key() {
+    let peer = PeerBuilder::new()
+        .preshared_key(PresharedKey::random())
+        .external_preshared_key(true)
+        .build();
+
+    assert!(peer.external_preshared_key);
+    assert!(!peer.to_string().contains("PresharedKey"));
+}
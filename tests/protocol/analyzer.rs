@@ -0,0 +1,472 @@
+#![cfg(feature = "pcap")]
+
+use claims::assert_matches;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+use std::net::{IpAddr, SocketAddr};
+
+const SERVER: &str = "192.0.2.1:51820";
+const CLIENT: &str = "198.51.100.7:40000";
+
+fn addr(addr: &str) -> SocketAddr {
+    addr.parse().unwrap()
+}
+
+/// Build IPv4 (or IPv6) UDP packet.
+fn udp(source: SocketAddr, destination: SocketAddr, payload: &[u8]) -> Vec<u8> {
+    let mut udp = Vec::new();
+    udp.extend_from_slice(&source.port().to_be_bytes());
+    udp.extend_from_slice(&destination.port().to_be_bytes());
+    udp.extend_from_slice(&u16::try_from(8 + payload.len()).unwrap().to_be_bytes());
+    udp.extend_from_slice(&[0, 0]);
+    udp.extend_from_slice(payload);
+
+    let mut ip = Vec::new();
+    match (source.ip(), destination.ip()) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            ip.extend_from_slice(&[0x45, 0]);
+            ip.extend_from_slice(&u16::try_from(20 + udp.len()).unwrap().to_be_bytes());
+            ip.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]);
+            ip.extend_from_slice(&source.octets());
+            ip.extend_from_slice(&destination.octets());
+        }
+        (IpAddr::V6(source), IpAddr::V6(destination)) => {
+            ip.extend_from_slice(&[0x60, 0, 0, 0]);
+            ip.extend_from_slice(&u16::try_from(udp.len()).unwrap().to_be_bytes());
+            ip.extend_from_slice(&[17, 64]);
+            ip.extend_from_slice(&source.octets());
+            ip.extend_from_slice(&destination.octets());
+        }
+        _ => unreachable!(),
+    }
+    ip.extend_from_slice(&udp);
+    ip
+}
+
+/// Build pcap file with Ethernet frames.
+fn pcap(datagrams: &[(SocketAddr, SocketAddr, Vec<u8>)]) -> Vec<u8> {
+    let mut file = Vec::new();
+    file.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    file.extend_from_slice(&[2, 0, 4, 0]);
+    file.extend_from_slice(&[0; 8]);
+    file.extend_from_slice(&65535u32.to_le_bytes());
+    file.extend_from_slice(&1u32.to_le_bytes());
+
+    for (i, (source, destination, payload)) in datagrams.iter().enumerate() {
+        let mut frame = vec![0xff; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(&udp(*source, *destination, payload));
+
+        let len = u32::try_from(frame.len()).unwrap();
+        file.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        file.extend_from_slice(&u32::try_from(i).unwrap().to_le_bytes());
+        file.extend_from_slice(&len.to_le_bytes());
+        file.extend_from_slice(&len.to_le_bytes());
+        file.extend_from_slice(&frame);
+    }
+
+    file
+}
+
+/// Build big-endian pcapng file with raw IP packets and nanosecond timestamps.
+fn pcapng(datagrams: &[(SocketAddr, SocketAddr, Vec<u8>)]) -> Vec<u8> {
+    fn block(file: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+        let mut body = body.to_vec();
+        body.resize(body.len().next_multiple_of(4), 0);
+        let len = u32::try_from(body.len() + 12).unwrap().to_be_bytes();
+
+        file.extend_from_slice(&block_type.to_be_bytes());
+        file.extend_from_slice(&len);
+        file.extend_from_slice(&body);
+        file.extend_from_slice(&len);
+    }
+
+    let mut file = Vec::new();
+    block(
+        &mut file,
+        0x0a0d_0d0a,
+        &[
+            0x1a, 0x2b, 0x3c, 0x4d, 0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ],
+    );
+    // link type 101 (raw), if_tsresol = 9
+    block(
+        &mut file,
+        1,
+        &[0, 101, 0, 0, 0, 0, 0, 0, 0, 9, 0, 1, 9, 0, 0, 0, 0, 0, 0, 0],
+    );
+    // unknown block is skipped
+    block(&mut file, 0x0bad, &[1, 2, 3, 4]);
+
+    for (source, destination, payload) in datagrams {
+        let packet = udp(*source, *destination, payload);
+        let units: u64 = 1_700_000_000_500_000_000;
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes());
+        body.extend_from_slice(&u32::try_from(units >> 32).unwrap().to_be_bytes());
+        body.extend_from_slice(&u32::try_from(units & 0xffff_ffff).unwrap().to_be_bytes());
+        body.extend_from_slice(&u32::try_from(packet.len()).unwrap().to_be_bytes());
+        body.extend_from_slice(&u32::try_from(packet.len()).unwrap().to_be_bytes());
+        body.extend_from_slice(&packet);
+        block(&mut file, 6, &body);
+    }
+
+    file
+}
+
+/// Server with two clients. Returns server and first client's configs.
+fn network() -> (Interface, Interface) {
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .endpoint("192.0.2.1")
+        .listen_port(51820)
+        .peers([
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.2/32")])
+                .build(),
+            PeerBuilder::new()
+                .allowed_ips([as_ipnet!("10.0.0.3/32")])
+                .build(),
+        ])
+        .build();
+
+    let client = server.peers[0]
+        .to_interface(&server, ToInterfaceOptions::new())
+        .unwrap();
+
+    (server, client)
+}
+
+/// Handshake and one transport data packet in both directions.
+fn session(
+    server: &Interface,
+    client: &Interface,
+    client_addr: &str,
+) -> Vec<(SocketAddr, SocketAddr, Vec<u8>)> {
+    let transcript = client.simulate_handshake(server).unwrap();
+    let codec = PacketCodec::default();
+
+    let Ok(Packet::HandshakeInitiation(initiation)) = codec.decode(&transcript.initiation) else {
+        unreachable!()
+    };
+    let Ok(Packet::HandshakeResponse(response)) = codec.decode(&transcript.response) else {
+        unreachable!()
+    };
+    let payload = [0; 48];
+    let to_server = codec.encode(&Packet::TransportData(TransportData {
+        receiver: response.sender,
+        counter: 0,
+        encrypted_packet: &payload,
+    }));
+    let to_client = codec.encode(&Packet::TransportData(TransportData {
+        receiver: initiation.sender,
+        counter: 0,
+        encrypted_packet: &payload,
+    }));
+
+    let (client_addr, server_addr) = (addr(client_addr), addr(SERVER));
+    vec![
+        (client_addr, server_addr, transcript.initiation.to_vec()),
+        (server_addr, client_addr, transcript.response.to_vec()),
+        (client_addr, server_addr, to_server),
+        (server_addr, client_addr, to_client),
+    ]
+}
+
+#[test]
+fn server_side() {
+    let (server, client) = network();
+
+    let mut datagrams = session(&server, &client, CLIENT);
+    // other traffic is ignored
+    datagrams.push((addr("192.0.2.1:53"), addr("198.51.100.7:5353"), vec![1; 40]));
+
+    let report = server.analyze_capture(&pcap(&datagrams)).unwrap();
+    let client_key = client.private_key.public_key();
+
+    assert_eq!(report.packets.len(), 4);
+    assert_eq!(
+        report
+            .packets
+            .iter()
+            .map(|packet| packet.kind)
+            .collect::<Vec<_>>(),
+        [
+            PacketKind::Message(MessageType::HandshakeInitiation),
+            PacketKind::Message(MessageType::HandshakeResponse),
+            PacketKind::Message(MessageType::TransportData),
+            PacketKind::Message(MessageType::TransportData),
+        ]
+    );
+    assert!(report
+        .packets
+        .iter()
+        .all(|packet| packet.peer.as_ref() == Some(&client_key)));
+    assert_eq!(report.packets[0].direction, Some(Direction::Incoming));
+    assert_eq!(report.packets[1].direction, Some(Direction::Outgoing));
+    assert_eq!(report.packets[2].len, 64);
+
+    let peer = report.peer(&client_key).unwrap();
+    assert_eq!(peer.initiations_received, 1);
+    assert_eq!(peer.responses_sent, 1);
+    assert_eq!(peer.initiations_sent, 0);
+    assert_eq!(peer.attempts(), 1);
+    assert_eq!(peer.completed, 1);
+    assert_eq!(peer.endpoints, [addr(CLIENT)]);
+    assert_eq!(
+        peer.last_handshake.unwrap().as_micros(),
+        1_700_000_000_000_001
+    );
+
+    assert_eq!(report.peers[1].attempts(), 0);
+    assert_eq!(report.unmatched_handshakes, 0);
+    assert!(report.is_consistent());
+}
+
+#[test]
+fn client_side() {
+    let (server, client) = network();
+
+    let datagrams = session(&server, &client, CLIENT);
+    let report = client.analyze_capture(&pcap(&datagrams)).unwrap();
+    let server_key = server.private_key.public_key();
+
+    assert_eq!(report.packets.len(), 4);
+    assert_eq!(report.packets[0].direction, Some(Direction::Outgoing));
+    assert_eq!(report.packets[1].direction, Some(Direction::Incoming));
+
+    let peer = report.peer(&server_key).unwrap();
+    assert_eq!(peer.initiations_sent, 1);
+    assert_eq!(peer.responses_received, 1);
+    assert_eq!(peer.completed, 1);
+    assert_eq!(peer.endpoints, [addr(SERVER)]);
+}
+
+#[test]
+fn pcapng_ipv6() {
+    let (server, client) = network();
+
+    let datagrams = session(&server, &client, "[2001:db8::7]:40000")
+        .into_iter()
+        .map(|(source, destination, payload)| {
+            let v6 = |addr: SocketAddr| match addr.ip() {
+                IpAddr::V4(ip) => SocketAddr::new(ip.to_ipv6_mapped().into(), addr.port()),
+                IpAddr::V6(_) => addr,
+            };
+            (v6(source), v6(destination), payload)
+        })
+        .collect::<Vec<_>>();
+    let report = server.analyze_capture(&pcapng(&datagrams)).unwrap();
+
+    let peer = report.peer(&client.private_key.public_key()).unwrap();
+    assert_eq!(peer.completed, 1);
+    assert_eq!(peer.endpoints, [addr("[2001:db8::7]:40000")]);
+    assert_eq!(report.packets[0].timestamp.as_millis(), 1_700_000_000_500);
+}
+
+#[test]
+fn unknown_client() {
+    let (server, client) = network();
+    let datagrams = session(&server, &client, CLIENT);
+
+    // server without client's peer
+    let mut other_server = server.clone();
+    other_server.peers.truncate(0);
+    other_server.peers.push(server.peers[1].clone());
+    let report = other_server.analyze_capture(&pcap(&datagrams)).unwrap();
+
+    assert_eq!(report.unmatched_handshakes, 2);
+    assert!(report.peers.iter().all(|peer| peer.attempts() == 0));
+
+    // initiation, that never got response
+    let report = client.analyze_capture(&pcap(&datagrams[..1])).unwrap();
+    let peer = &report.peers[0];
+    assert_eq!((peer.initiations_sent, peer.completed), (1, 0));
+}
+
+#[test]
+fn unknown_packets() {
+    let (server, client) = network();
+
+    let mut datagrams = session(&server, &client, CLIENT);
+    datagrams.push((addr(CLIENT), addr(SERVER), vec![0xaa; 150]));
+    let report = server.analyze_capture(&pcap(&datagrams)).unwrap();
+
+    assert_eq!(report.count(PacketKind::Unknown), 1);
+    assert_eq!(
+        report.issues,
+        [ObfuscationIssue::UnknownPackets { count: 1 }]
+    );
+}
+
+#[test]
+fn invalid_capture() {
+    let server = InterfaceBuilder::new().build();
+
+    assert_matches!(
+        server.analyze_capture(&[]),
+        Err(WireguardError::InvalidCapture(_))
+    );
+    assert_matches!(
+        server.analyze_capture(b"not a capture file"),
+        Err(WireguardError::InvalidCapture(_))
+    );
+
+    let mut truncated = pcap(&[(addr(CLIENT), addr(SERVER), vec![0; 32])]);
+    truncated.pop();
+    assert_matches!(
+        server.analyze_capture(&truncated),
+        Err(WireguardError::InvalidCapture(_))
+    );
+
+    // frame is cut inside UDP header (f.e. by snaplen), so it's skipped
+    let mut truncated = pcap(&[(addr(CLIENT), addr(SERVER), vec![])]);
+    truncated.truncate(truncated.len() - 2);
+    let captured_len = u32::try_from(truncated.len() - 40).unwrap();
+    truncated[32..36].copy_from_slice(&captured_len.to_le_bytes());
+    let report = server.analyze_capture(&truncated).unwrap();
+    assert!(report.packets.is_empty());
+
+    // simple packet block without original length
+    let mut malformed = pcapng(&[]);
+    for word in [3u32, 12, 12] {
+        malformed.extend_from_slice(&word.to_be_bytes());
+    }
+    assert_matches!(
+        server.analyze_capture(&malformed),
+        Err(WireguardError::InvalidCapture(_))
+    );
+
+    assert_matches!(
+        server.analyze_capture_file("/nonexistent/capture.pcap"),
+        Err(WireguardError::InvalidCapture(_))
+    );
+}
+
+#[cfg(feature = "amneziawg")]
+mod amneziawg {
+    use super::*;
+
+    fn settings() -> AmneziaSettings {
+        AmneziaSettings {
+            jc: 3,
+            jmin: 40,
+            jmax: 70,
+            s1: 15,
+            s2: 25,
+            h1: 1_111_111.into(),
+            h2: 2_222_222.into(),
+            h3: 3_333_333.into(),
+            h4: 4_444_444.into(),
+            ..AmneziaSettings::random()
+        }
+    }
+
+    /// Re-encode vanilla session with AmneziaWG codec (recomputing `mac1`) and prepend `jc`
+    /// junk packets to handshake initiation.
+    fn obfuscate(
+        datagrams: &[(SocketAddr, SocketAddr, Vec<u8>)],
+        receivers: [&PublicKey; 2],
+        jc: usize,
+    ) -> Vec<(SocketAddr, SocketAddr, Vec<u8>)> {
+        let codec = PacketCodec::amneziawg(&settings()).unwrap();
+        let vanilla = PacketCodec::default();
+
+        let mut obfuscated = Vec::new();
+        for (source, destination, payload) in datagrams {
+            let packet = vanilla.decode(payload).unwrap();
+            let mut bytes = codec.encode(&packet);
+
+            let (padding, mac1_offset, receiver) = match packet.message_type() {
+                MessageType::HandshakeInitiation => {
+                    for len in (40..).step_by(10).take(jc) {
+                        obfuscated.push((*source, *destination, vec![0x5a; len]));
+                    }
+                    (15, 116, receivers[0])
+                }
+                MessageType::HandshakeResponse => (25, 60, receivers[1]),
+                _ => {
+                    obfuscated.push((*source, *destination, bytes));
+                    continue;
+                }
+            };
+            let mac1 = compute_mac1(receiver, &bytes[padding..padding + mac1_offset]);
+            bytes[padding + mac1_offset..padding + mac1_offset + 16].copy_from_slice(&mac1);
+
+            obfuscated.push((*source, *destination, bytes));
+        }
+
+        obfuscated
+    }
+
+    #[test]
+    fn consistent() {
+        let (mut server, mut client) = network();
+        server.amnezia_settings = Some(settings());
+        client.amnezia_settings = Some(settings());
+
+        let datagrams = obfuscate(
+            &session(&server, &client, CLIENT),
+            [
+                &server.private_key.public_key(),
+                &client.private_key.public_key(),
+            ],
+            3,
+        );
+        let report = server.analyze_capture(&pcap(&datagrams)).unwrap();
+
+        assert_eq!(report.count(PacketKind::Junk), 3);
+        assert_eq!(
+            report.count(PacketKind::Message(MessageType::HandshakeInitiation)),
+            1
+        );
+        assert_eq!(report.peers[0].completed, 1);
+        assert!(report.is_consistent(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn junk_count_mismatch() {
+        let (mut server, client) = network();
+        server.amnezia_settings = Some(settings());
+
+        let datagrams = obfuscate(
+            &session(&server, &client, CLIENT),
+            [
+                &server.private_key.public_key(),
+                &client.private_key.public_key(),
+            ],
+            1,
+        );
+        let report = server.analyze_capture(&pcap(&datagrams)).unwrap();
+
+        assert_eq!(report.peers[0].completed, 1);
+        assert_eq!(
+            report.issues,
+            [ObfuscationIssue::JunkCount {
+                expected: 3,
+                observed: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn vanilla_peer() {
+        let (mut server, client) = network();
+        server.amnezia_settings = Some(settings());
+
+        let datagrams = session(&server, &client, CLIENT);
+        let report = server.analyze_capture(&pcap(&datagrams)).unwrap();
+
+        assert_eq!(
+            report.count(PacketKind::VanillaMessage(MessageType::HandshakeInitiation)),
+            1
+        );
+        // handshake is still matched to peer
+        assert_eq!(report.peers[0].initiations_received, 1);
+        assert_eq!(
+            report.issues,
+            [ObfuscationIssue::VanillaMessages { count: 4 }]
+        );
+    }
+}
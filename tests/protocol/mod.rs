@@ -0,0 +1,2 @@
+mod analyzer;
+mod packet;
@@ -0,0 +1,334 @@
+use claims::{assert_err, assert_matches};
+use wireguard_conf::prelude::*;
+
+fn initiation() -> Packet<'static> {
+    Packet::HandshakeInitiation(HandshakeInitiation {
+        sender: 0x1234_5678,
+        ephemeral: PrivateKey::random().public_key(),
+        encrypted_static: [1; 48],
+        encrypted_timestamp: [2; 28],
+        mac1: [3; 16],
+        mac2: [0; 16],
+    })
+}
+
+fn response() -> Packet<'static> {
+    Packet::HandshakeResponse(HandshakeResponse {
+        sender: 42,
+        receiver: 0x1234_5678,
+        ephemeral: PrivateKey::random().public_key(),
+        encrypted_nothing: [4; 16],
+        mac1: [5; 16],
+        mac2: [0; 16],
+    })
+}
+
+fn cookie_reply() -> Packet<'static> {
+    Packet::CookieReply(CookieReply {
+        receiver: 7,
+        nonce: [6; 24],
+        encrypted_cookie: [7; 32],
+    })
+}
+
+fn transport_data(encrypted_packet: &[u8]) -> Packet<'_> {
+    Packet::TransportData(TransportData {
+        receiver: 42,
+        counter: 1_000_000,
+        encrypted_packet,
+    })
+}
+
+#[test]
+fn wireguard_layout() {
+    let codec = PacketCodec::default();
+
+    let bytes = codec.encode(&initiation());
+    assert_eq!(bytes.len(), HANDSHAKE_INITIATION_LEN);
+    assert_eq!(bytes[..8], [1, 0, 0, 0, 0x78, 0x56, 0x34, 0x12]);
+    assert_eq!(bytes[40..88], [1; 48]);
+
+    let bytes = codec.encode(&response());
+    assert_eq!(bytes.len(), HANDSHAKE_RESPONSE_LEN);
+    assert_eq!(
+        bytes[..12],
+        [2, 0, 0, 0, 42, 0, 0, 0, 0x78, 0x56, 0x34, 0x12]
+    );
+
+    let bytes = codec.encode(&cookie_reply());
+    assert_eq!(bytes.len(), COOKIE_REPLY_LEN);
+    assert_eq!(bytes[..4], [3, 0, 0, 0]);
+
+    let bytes = codec.encode(&transport_data(&[8; 16]));
+    assert_eq!(bytes.len(), TRANSPORT_DATA_MIN_LEN);
+    assert_eq!(bytes[..8], [4, 0, 0, 0, 42, 0, 0, 0]);
+    assert_eq!(bytes[8..16], 1_000_000u64.to_le_bytes());
+}
+
+#[test]
+fn wireguard_roundtrip() {
+    let codec = PacketCodec::default();
+    let payload = [9; 80];
+
+    for packet in [
+        initiation(),
+        response(),
+        cookie_reply(),
+        transport_data(&[8; 16]),
+        transport_data(&payload),
+    ] {
+        let bytes = codec.encode(&packet);
+
+        assert_eq!(codec.classify(&bytes), Some(packet.message_type()));
+        assert_eq!(codec.decode(&bytes).unwrap(), packet);
+    }
+}
+
+#[test]
+fn keepalive() {
+    let codec = PacketCodec::default();
+    let payload = [9; 32];
+
+    let keepalive = codec.encode(&transport_data(&[8; 16]));
+    let data = codec.encode(&transport_data(&payload));
+
+    assert_matches!(codec.decode(&keepalive), Ok(Packet::TransportData(data)) if data.is_keepalive());
+    assert_matches!(codec.decode(&data), Ok(Packet::TransportData(data)) if !data.is_keepalive());
+}
+
+#[test]
+fn invalid_packets() {
+    let codec = PacketCodec::default();
+
+    assert_eq!(codec.classify(&[]), None);
+    assert_err!(codec.decode(&[]));
+
+    // wrong length for message type
+    let mut bytes = codec.encode(&initiation());
+    bytes.pop();
+    assert_eq!(codec.classify(&bytes), None);
+
+    // too short transport data
+    let bytes = codec.encode(&transport_data(&[8; 15]));
+    assert_eq!(codec.classify(&bytes), None);
+
+    // unknown message type
+    let mut bytes = codec.encode(&cookie_reply());
+    bytes[0] = 5;
+    assert_matches!(codec.decode(&bytes), Err(WireguardError::InvalidPacket(_)));
+
+    // reserved bytes are part of message type
+    let mut bytes = codec.encode(&cookie_reply());
+    bytes[1] = 1;
+    assert_eq!(codec.classify(&bytes), None);
+}
+
+#[test]
+fn receiver() {
+    assert_eq!(initiation().receiver(), None);
+    assert_eq!(response().receiver(), Some(0x1234_5678));
+    assert_eq!(cookie_reply().receiver(), Some(7));
+    assert_eq!(transport_data(&[0; 16]).receiver(), Some(42));
+}
+
+#[test]
+fn interface_codec() {
+    let interface = InterfaceBuilder::new().build();
+
+    assert_eq!(interface.packet_codec().unwrap(), PacketCodec::default());
+}
+
+#[cfg(feature = "amneziawg")]
+mod amneziawg {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn settings() -> AmneziaSettings {
+        AmneziaSettings {
+            jc: 4,
+            jmin: 8,
+            jmax: 80,
+            s1: 20,
+            s2: 30,
+            h1: 0x0a0b_0c0d.into(),
+            h2: 1_000_000.into(),
+            h3: 2_000_000.into(),
+            h4: 3_000_000.into(),
+            ..AmneziaSettings::random()
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let codec = PacketCodec::amneziawg(&settings()).unwrap();
+
+        assert_eq!(
+            codec.message_type_range(MessageType::HandshakeInitiation),
+            0x0a0b_0c0d..=0x0a0b_0c0d
+        );
+        assert_eq!(codec.padding(MessageType::HandshakeInitiation), 20);
+        assert_eq!(codec.padding(MessageType::HandshakeResponse), 30);
+        assert_eq!(codec.padding(MessageType::CookieReply), 0);
+        assert_eq!(codec.padding(MessageType::TransportData), 0);
+
+        let bytes = codec.encode(&initiation());
+        assert_eq!(bytes.len(), 20 + HANDSHAKE_INITIATION_LEN);
+        assert_eq!(bytes[20..24], [0x0d, 0x0c, 0x0b, 0x0a]);
+
+        let bytes = codec.encode(&response());
+        assert_eq!(bytes.len(), 30 + HANDSHAKE_RESPONSE_LEN);
+        assert_eq!(bytes[30..34], 1_000_000u32.to_le_bytes());
+
+        let bytes = codec.encode(&transport_data(&[0; 16]));
+        assert_eq!(bytes[..4], 3_000_000u32.to_le_bytes());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let codec = PacketCodec::amneziawg(&settings()).unwrap();
+        let wireguard = PacketCodec::default();
+
+        for packet in [
+            initiation(),
+            response(),
+            cookie_reply(),
+            transport_data(&[8; 64]),
+        ] {
+            let bytes = codec.encode(&packet);
+
+            assert_eq!(codec.decode(&bytes).unwrap(), packet);
+            assert_eq!(wireguard.classify(&bytes), None);
+        }
+
+        // vanilla packets aren't recognised by AmneziaWG codec
+        assert_eq!(codec.classify(&wireguard.encode(&initiation())), None);
+    }
+
+    #[test]
+    fn junk_prefix() {
+        let codec = PacketCodec::amneziawg(&settings()).unwrap();
+
+        let first = codec.encode_with_rng(&cookie_reply(), &mut ChaCha20Rng::seed_from_u64(1));
+        let second = codec.encode_with_rng(&cookie_reply(), &mut ChaCha20Rng::seed_from_u64(2));
+        assert_eq!(first, second);
+
+        let packet = initiation();
+        let first = codec.encode_with_rng(&packet, &mut ChaCha20Rng::seed_from_u64(1));
+        let second = codec.encode_with_rng(&packet, &mut ChaCha20Rng::seed_from_u64(2));
+        assert_ne!(first[..20], second[..20]);
+        assert_eq!(first[20..], second[20..]);
+    }
+
+    #[test]
+    fn amneziawg_2_0() {
+        let settings = AmneziaSettings {
+            version: AmneziaVersion::V2,
+            s3: 10,
+            s4: 6,
+            h1: (100..=199).into(),
+            h2: (200..=299).into(),
+            h3: (300..=399).into(),
+            h4: (400..=499).into(),
+            ..settings()
+        };
+        assert!(settings.validate().is_ok());
+
+        let codec = PacketCodec::amneziawg(&settings).unwrap();
+        assert_eq!(
+            codec.message_type_range(MessageType::TransportData),
+            400..=499
+        );
+        assert_eq!(codec.padding(MessageType::CookieReply), 10);
+        assert_eq!(codec.padding(MessageType::TransportData), 6);
+
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for packet in [
+            initiation(),
+            response(),
+            cookie_reply(),
+            transport_data(&[8; 64]),
+        ] {
+            let message_type = packet.message_type();
+            let padding = codec.padding(message_type);
+            let bytes = codec.encode_with_rng(&packet, &mut rng);
+
+            let field = u32::from_le_bytes(bytes[padding..padding + 4].try_into().unwrap());
+            assert!(codec.message_type_range(message_type).contains(&field));
+            assert_eq!(codec.classify(&bytes), Some(message_type));
+            assert_eq!(codec.decode(&bytes).unwrap(), packet);
+        }
+
+        let bytes = codec.encode(&cookie_reply());
+        assert_eq!(bytes.len(), 10 + COOKIE_REPLY_LEN);
+        assert_eq!(codec.classify(&bytes[10..]), None);
+    }
+
+    #[test]
+    fn interface_codec() {
+        let interface = InterfaceBuilder::new().amnezia_settings(settings()).build();
+        assert_eq!(
+            interface.packet_codec().unwrap(),
+            PacketCodec::amneziawg(&settings()).unwrap()
+        );
+
+        let mut settings = settings();
+        settings.h3 = usize::MAX.into();
+        assert_matches!(
+            PacketCodec::amneziawg(&settings),
+            Err(WireguardError::InvalidAmneziaSetting(name)) if name == "H3"
+        );
+
+        let mut settings = self::settings();
+        settings.version = AmneziaVersion::V2;
+        settings.h2 = MagicHeader {
+            start: 299,
+            end: 200,
+        };
+        assert_matches!(
+            PacketCodec::amneziawg(&settings),
+            Err(WireguardError::InvalidAmneziaSetting(name)) if name == "H2"
+        );
+
+        let mut settings = self::settings();
+        settings.s1 = usize::MAX;
+        assert_matches!(
+            PacketCodec::amneziawg(&settings),
+            Err(WireguardError::InvalidAmneziaSetting(name)) if name == "S1"
+        );
+    }
+}
+
+#[cfg(feature = "handshake")]
+#[test]
+fn handshake_messages() {
+    use claims::assert_ok;
+    use wireguard_conf::as_ipnet;
+
+    let server = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .peers([PeerBuilder::new()
+            .allowed_ips([as_ipnet!("10.0.0.2/32")])
+            .build()])
+        .build();
+    let client = server.peers[0]
+        .to_interface(&server, ToInterfaceOptions::new())
+        .unwrap();
+
+    let transcript = client.simulate_handshake(&server).unwrap();
+    let codec = PacketCodec::default();
+
+    let Packet::HandshakeInitiation(initiation) = assert_ok!(codec.decode(&transcript.initiation))
+    else {
+        panic!("expected handshake initiation");
+    };
+    let Packet::HandshakeResponse(response) = assert_ok!(codec.decode(&transcript.response)) else {
+        panic!("expected handshake response");
+    };
+
+    assert_eq!(response.receiver, initiation.sender);
+    assert_eq!(
+        codec.encode(&Packet::HandshakeInitiation(initiation)),
+        transcript.initiation
+    );
+}
@@ -1,3 +1,4 @@
 mod interface;
 mod peer;
+mod protocol;
 mod utils;
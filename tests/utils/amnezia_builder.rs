@@ -0,0 +1,216 @@
+#![cfg(feature = "amneziawg")]
+use std::ops::RangeInclusive;
+
+use claims::assert_matches;
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::prelude::*;
+
+const PRESETS: [AmneziaPreset; 3] = [
+    AmneziaPreset::Light,
+    AmneziaPreset::Balanced,
+    AmneziaPreset::Aggressive,
+];
+
+const VERSIONS: [AmneziaVersion; 3] =
+    [AmneziaVersion::V1, AmneziaVersion::V1_5, AmneziaVersion::V2];
+
+#[test]
+fn presets() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+    for preset in PRESETS {
+        for version in VERSIONS {
+            for _ in 0..100 {
+                let settings = AmneziaSettings::builder()
+                    .preset(preset)
+                    .version(version)
+                    .build_with_rng(&mut rng)
+                    .unwrap();
+
+                assert_eq!(settings.version, version);
+                assert!(settings.validate().is_ok(), "{preset:?}: {settings:?}");
+
+                let report = settings.diagnose(None);
+                assert!(report.is_empty(), "{preset:?}: {report}");
+            }
+        }
+    }
+
+    let light = AmneziaSettingsBuilder::from(AmneziaPreset::Light)
+        .build_with_rng(&mut rng)
+        .unwrap();
+    let aggressive = AmneziaSettingsBuilder::from(AmneziaPreset::Aggressive)
+        .build_with_rng(&mut rng)
+        .unwrap();
+    assert!(light.jc < aggressive.jc);
+    assert!(light.jmax < aggressive.jmin);
+}
+
+#[test]
+fn reproducible() {
+    let builder = AmneziaSettings::builder().version(AmneziaVersion::V2);
+
+    assert_eq!(
+        builder.build_with_rng(&mut ChaCha20Rng::seed_from_u64(42)),
+        builder.build_with_rng(&mut ChaCha20Rng::seed_from_u64(42))
+    );
+    assert_eq!(
+        AmneziaSettings::random_with_rng(&mut ChaCha20Rng::seed_from_u64(42)),
+        AmneziaSettingsBuilder::new()
+            .build_with_rng(&mut ChaCha20Rng::seed_from_u64(42))
+            .unwrap()
+    );
+}
+
+#[test]
+fn ranges() {
+    let settings = AmneziaSettings::builder()
+        .version(AmneziaVersion::V2)
+        .jc(5..=5)
+        .jmin(100..=100)
+        .jmax(200..=200)
+        .s1(10..=10)
+        .s2(20..=20)
+        .s3(30..=30)
+        .s4(40..=40)
+        .headers(1000..=1999)
+        .header_width(100)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        (
+            settings.jc,
+            settings.jmin,
+            settings.jmax,
+            settings.s1,
+            settings.s2,
+            settings.s3,
+            settings.s4
+        ),
+        (5, 100, 200, 10, 20, 30, 40)
+    );
+    for (_, header) in settings.headers() {
+        assert_eq!(header.end - header.start, 99);
+        assert!(header.start >= 1000 && header.end <= 1999);
+    }
+
+    // S3/S4 and header ranges are ignored before AmneziaWG 2.0
+    let settings = AmneziaSettings::builder()
+        .version(AmneziaVersion::V1_5)
+        .s3(30..=30)
+        .header_width(100)
+        .build()
+        .unwrap();
+    assert_eq!(settings.s3, 0);
+    assert!(settings.headers().iter().all(|(_, h)| !h.is_range()));
+}
+
+#[test]
+fn excluded_values() {
+    // only value of S2 range is forbidden by S1 + 56 != S2
+    assert_eq!(
+        AmneziaSettings::builder().s1(10..=10).s2(66..=66).build(),
+        Err(WireguardError::InvalidAmneziaSetting("S2".to_string()))
+    );
+
+    // S2 is picked around forbidden value
+    for seed in 0..20 {
+        let settings = AmneziaSettings::builder()
+            .s1(10..=10)
+            .s2(65..=67)
+            .build_with_rng(&mut ChaCha20Rng::seed_from_u64(seed))
+            .unwrap();
+        assert_ne!(settings.s2, 66);
+    }
+}
+
+#[test]
+fn invalid_ranges() {
+    let builder = AmneziaSettings::builder;
+
+    for (builder, name) in [
+        (builder().jc(0..=0), "Jc"),
+        (builder().jc(200..=300), "Jc"),
+        (builder().jmin(100..=100).jmax(50..=100), "Jmax"),
+        (builder().jmax(2000..=3000), "Jmax"),
+        (builder().s1(1280..=2000), "S1"),
+        #[allow(clippy::reversed_empty_ranges)]
+        (builder().s2(10..=5), "S2"),
+        (builder().version(AmneziaVersion::V2).s4(5000..=6000), "S4"),
+        (builder().headers(1..=3), "H1/H2/H3/H4"),
+        (
+            builder()
+                .version(AmneziaVersion::V2)
+                .headers(0..=99)
+                .header_width(26),
+            "H1/H2/H3/H4",
+        ),
+    ] {
+        assert_matches!(
+            builder.build(),
+            Err(WireguardError::InvalidAmneziaSetting(n)) if n == name,
+            "{name}"
+        );
+    }
+}
+
+fn range(max: usize) -> impl Strategy<Value = RangeInclusive<usize>> {
+    (0..=max, 0..=max).prop_map(|(a, b)| a.min(b)..=a.max(b))
+}
+
+proptest! {
+    #[test]
+    fn built_settings_are_valid(
+        version in prop::sample::select(VERSIONS.to_vec()),
+        jc in range(200),
+        jmin in range(1500),
+        jmax in range(1500),
+        s1 in range(1500),
+        s2 in range(1500),
+        s3 in range(1500),
+        s4 in range(1500),
+        headers in range(u32::MAX as usize + 1000),
+        header_width in 0usize..1_000_000,
+        seed: u64,
+    ) {
+        let result = AmneziaSettings::builder()
+            .version(version)
+            .jc(jc.clone())
+            .jmin(jmin)
+            .jmax(jmax)
+            .s1(s1.clone())
+            .s2(s2.clone())
+            .s3(s3)
+            .s4(s4)
+            .headers(headers)
+            .header_width(header_width)
+            .build_with_rng(&mut ChaCha20Rng::seed_from_u64(seed));
+
+        if let Ok(settings) = result {
+            prop_assert_eq!(settings.validate(), Ok(()));
+            prop_assert!(jc.contains(&settings.jc));
+            prop_assert!(s1.contains(&settings.s1));
+            prop_assert!(s2.contains(&settings.s2));
+        }
+    }
+
+    #[test]
+    fn presets_are_valid(
+        preset in prop::sample::select(PRESETS.to_vec()),
+        version in prop::sample::select(VERSIONS.to_vec()),
+        header_width in 0usize..100_000,
+        seed: u64,
+    ) {
+        let settings = AmneziaSettings::builder()
+            .preset(preset)
+            .version(version)
+            .header_width(header_width)
+            .build_with_rng(&mut ChaCha20Rng::seed_from_u64(seed))
+            .unwrap();
+
+        prop_assert_eq!(settings.validate(), Ok(()));
+    }
+}
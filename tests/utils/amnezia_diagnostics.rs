@@ -0,0 +1,222 @@
+#![cfg(feature = "amneziawg")]
+use wireguard_conf::prelude::*;
+
+fn settings() -> AmneziaSettings {
+    AmneziaSettings {
+        version: AmneziaVersion::V1_5,
+        jc: 4,
+        jmin: 40,
+        jmax: 70,
+        s1: 20,
+        s2: 30,
+        s3: 0,
+        s4: 0,
+        h1: 100.into(),
+        h2: 200.into(),
+        h3: 300.into(),
+        h4: 400.into(),
+        ..AmneziaSettings::random()
+    }
+}
+
+fn diagnostic(severity: Severity, setting: &str, value: &str, expected: &str) -> AmneziaDiagnostic {
+    AmneziaDiagnostic {
+        severity,
+        setting: setting.to_string(),
+        value: value.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+#[test]
+fn clean() {
+    let report = settings().diagnose(None);
+
+    assert!(report.is_empty());
+    assert!(report.is_valid());
+    assert_eq!(report.to_string(), "");
+}
+
+#[test]
+fn recommendations() {
+    let mut settings = settings();
+    settings.jc = 11;
+    settings.s1 = 5;
+    settings.h2 = 3.into();
+
+    let report = settings.diagnose(None);
+    assert!(report.is_valid());
+    assert_eq!(
+        report.diagnostics,
+        [
+            diagnostic(Severity::Warning, "Jc", "11", "recommended 3-10"),
+            diagnostic(Severity::Warning, "S1", "5", "recommended 15-150"),
+            diagnostic(Severity::Warning, "H2", "3", "recommended 5-2147483647"),
+        ]
+    );
+    assert_eq!(
+        report.to_string(),
+        "warning: Jc = 11 (recommended 3-10)\n\
+         warning: S1 = 5 (recommended 15-150)\n\
+         warning: H2 = 3 (recommended 5-2147483647)\n"
+    );
+}
+
+#[test]
+fn every_error() {
+    let mut settings = settings();
+    settings.jc = 0;
+    settings.jmin = 100;
+    settings.jmax = 90;
+    settings.s2 = settings.s1 + 56;
+    settings.s3 = 10;
+    settings.h4 = 300.into();
+
+    let report = settings.diagnose(None);
+    assert!(!report.is_valid());
+    assert_eq!(report.warnings().count(), 0);
+    assert_eq!(
+        report.errors().cloned().collect::<Vec<_>>(),
+        [
+            diagnostic(Severity::Error, "Jc", "0", "allowed 1-128"),
+            diagnostic(
+                Severity::Error,
+                "Jmin",
+                "100",
+                "allowed less than Jmax (90)"
+            ),
+            diagnostic(
+                Severity::Error,
+                "S1",
+                "20",
+                "S1 + 56 must differ from S2 (76)"
+            ),
+            diagnostic(Severity::Error, "S3", "10", "requires AmneziaWG 2.0"),
+            diagnostic(
+                Severity::Error,
+                "H1/H2/H3/H4",
+                "H3 = 300, H4 = 300",
+                "headers must not overlap"
+            ),
+        ]
+    );
+
+    // `validate()` reports first error
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("Jc".to_string()))
+    );
+}
+
+#[test]
+fn versions() {
+    let mut settings = settings();
+    settings.version = AmneziaVersion::V1;
+    settings.h1 = (100..=150).into();
+    settings.i1 = Some("<r 10>".parse().unwrap());
+    settings.itime = Some(60);
+
+    let report = settings.diagnose(None);
+    assert_eq!(
+        report
+            .errors()
+            .map(|diagnostic| (diagnostic.setting.as_str(), diagnostic.expected.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("H1", "ranges require AmneziaWG 2.0"),
+            ("I1", "requires AmneziaWG 1.5"),
+            ("Itime", "requires AmneziaWG 1.5"),
+        ]
+    );
+
+    settings.version = AmneziaVersion::V2;
+    assert!(settings.diagnose(None).is_empty());
+}
+
+#[test]
+fn mtu() {
+    let mut settings = settings();
+    settings.jmax = 1200;
+    settings.s1 = 150;
+
+    assert!(settings.diagnose(None).is_empty());
+
+    // 1100 + 32 bytes of UDP payload fit into link MTU
+    let report = settings.diagnose(Some(1100));
+    assert_eq!(
+        report.diagnostics,
+        [diagnostic(
+            Severity::Warning,
+            "Jmax",
+            "1200 bytes packet",
+            "at most 1132 bytes for MTU 1100"
+        )]
+    );
+
+    let report = settings.diagnose(Some(200));
+    assert_eq!(
+        report
+            .warnings()
+            .map(|diagnostic| diagnostic.setting.as_str())
+            .collect::<Vec<_>>(),
+        ["Jmax", "S1"]
+    );
+}
+
+#[test]
+fn huge_values() {
+    let mut settings = settings();
+    settings.version = AmneziaVersion::V2;
+    settings.s1 = usize::MAX;
+    settings.s2 = usize::MAX;
+    settings.s3 = usize::MAX;
+    settings.s4 = usize::MAX;
+
+    let report = settings.diagnose(Some(usize::MAX));
+    assert_eq!(
+        report
+            .errors()
+            .map(|diagnostic| diagnostic.setting.as_str())
+            .collect::<Vec<_>>(),
+        ["S1", "S2", "S3", "S4"]
+    );
+    assert!(report.warnings().next().is_none());
+
+    let mut settings = self::settings();
+    settings.version = AmneziaVersion::V2;
+    settings.s3 = usize::MAX;
+    assert!(matches!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting(setting)) if setting == "S3"
+    ));
+
+    let mut settings = self::settings();
+    settings.s1 = usize::MAX;
+    assert!(matches!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting(setting)) if setting == "S1"
+    ));
+}
+
+#[test]
+fn interface() {
+    let mut settings = settings();
+    settings.jmax = 1200;
+
+    let interface = InterfaceBuilder::new()
+        .mtu(1000)
+        .amnezia_settings(settings)
+        .build();
+    assert_eq!(
+        interface
+            .amnezia_diagnostics()
+            .unwrap()
+            .warnings()
+            .next()
+            .unwrap()
+            .setting,
+        "Jmax"
+    );
+
+    assert_eq!(InterfaceBuilder::new().build().amnezia_diagnostics(), None);
+}
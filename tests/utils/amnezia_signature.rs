@@ -0,0 +1,197 @@
+#![cfg(feature = "amneziawg")]
+use claims::{assert_err, assert_matches};
+use indoc::formatdoc;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::prelude::*;
+
+#[cfg(feature = "serde")]
+use claims::assert_ok_eq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_assert::{Deserializer, Serializer, Token};
+
+#[test]
+fn parse() {
+    let packet: SignaturePacket = "<b 0xC0FFee><r 16> <rc 4>\t<rd 2><t><c>".parse().unwrap();
+
+    assert_eq!(
+        packet.tags,
+        [
+            SignatureTag::Bytes(vec![0xc0, 0xff, 0xee]),
+            SignatureTag::Random(16),
+            SignatureTag::RandomChars(4),
+            SignatureTag::RandomDigits(2),
+            SignatureTag::Timestamp,
+            SignatureTag::Counter,
+        ]
+    );
+    assert_eq!(packet.len(), 3 + 16 + 4 + 2 + 4 + 4);
+    assert_eq!(packet.to_string(), "<b 0xc0ffee><r 16><rc 4><rd 2><t><c>");
+    assert_eq!(packet.to_string().parse::<SignaturePacket>(), Ok(packet));
+}
+
+#[test]
+fn parse_invalid() {
+    for input in [
+        "",
+        "   ",
+        "<b c0ffee>",
+        "<b 0xc0f>",
+        "<b 0x>",
+        "<b 0xzz>",
+        "<r>",
+        "<r 0>",
+        "<r -1>",
+        "<r 1 2>",
+        "<t 4>",
+        "<x 1>",
+        "<>",
+        "<r 16",
+        "r 16>",
+        "<r 16>junk",
+        "<r 65508>",
+    ] {
+        assert_matches!(
+            input.parse::<SignaturePacket>(),
+            Err(WireguardError::InvalidSignaturePacket(_)),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn generate() {
+    let packet: SignaturePacket = "<b 0x0102><r 4><rc 8><rd 8><t><c>".parse().unwrap();
+
+    let bytes = packet.generate_with_rng(&mut ChaCha20Rng::seed_from_u64(0), 0x0a0b_0c0d);
+    assert_eq!(bytes.len(), packet.len());
+    assert_eq!(bytes[..2], [1, 2]);
+    assert!(bytes[6..14].iter().all(u8::is_ascii_alphabetic));
+    assert!(bytes[14..22].iter().all(u8::is_ascii_digit));
+    assert_eq!(bytes[26..], [0x0a, 0x0b, 0x0c, 0x0d]);
+
+    // reproducible with seeded rng, except timestamp
+    let other = packet.generate_with_rng(&mut ChaCha20Rng::seed_from_u64(0), 0x0a0b_0c0d);
+    assert_eq!(bytes[..22], other[..22]);
+}
+
+#[test]
+fn validate() {
+    assert!(SignaturePacket::new([SignatureTag::Counter])
+        .validate()
+        .is_ok());
+    assert_err!(SignaturePacket::new([]).validate());
+    assert_err!(SignaturePacket::new([SignatureTag::Bytes(vec![])]).validate());
+    assert_err!(SignaturePacket::new([SignatureTag::Random(70_000)]).validate());
+
+    // size overflow
+    let packet = SignaturePacket::new([SignatureTag::Random(usize::MAX), SignatureTag::Random(1)]);
+    assert_eq!(packet.len(), usize::MAX);
+    assert_matches!(
+        packet.validate(),
+        Err(WireguardError::InvalidSignaturePacket(_))
+    );
+    assert_matches!(
+        format!("<r {}><r 1>", usize::MAX).parse::<SignaturePacket>(),
+        Err(WireguardError::InvalidSignaturePacket(_))
+    );
+}
+
+#[test]
+fn templates() {
+    let quic = SignaturePacket::quic_initial();
+    assert!(quic.validate().is_ok());
+    assert_eq!(quic.len(), 1200);
+    let bytes = quic.generate(0);
+    assert_eq!(bytes[..6], [0xc3, 0, 0, 0, 1, 8]);
+
+    let dns = SignaturePacket::dns_query("example.com.").unwrap();
+    assert!(dns.validate().is_ok());
+    let bytes = dns.generate(0);
+    assert_eq!(
+        bytes[2..],
+        *b"\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x07example\x03com\x00\x00\x01\x00\x01"
+    );
+    assert_err!(SignaturePacket::dns_query(""));
+    assert_err!(SignaturePacket::dns_query("a..b"));
+    assert_err!(SignaturePacket::dns_query(&"a".repeat(64)));
+
+    let sip = SignaturePacket::sip_options("sip.example.com");
+    assert!(sip.validate().is_ok());
+    let text = String::from_utf8(sip.generate(0)).unwrap();
+    assert!(text.starts_with("OPTIONS sip:sip.example.com SIP/2.0\r\n"));
+    assert!(text.ends_with("\r\n\r\n"));
+
+    // templates survive round-trip through config syntax
+    for packet in [quic, dns, sip] {
+        assert_eq!(packet.to_string().parse::<SignaturePacket>(), Ok(packet));
+    }
+}
+
+#[test]
+fn settings() {
+    let mut settings = AmneziaSettings::random();
+    settings.i1 = Some(SignaturePacket::quic_initial());
+    settings.i2 = Some("<b 0x01><c>".parse().unwrap());
+    settings.j1 = Some("<r 10>".parse().unwrap());
+    settings.itime = Some(120);
+
+    assert!(settings.validate().is_ok());
+    assert_eq!(
+        settings
+            .signature_packets()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        ["I1", "I2", "J1"]
+    );
+
+    let quic = SignaturePacket::quic_initial();
+    assert_eq!(
+        settings.to_string(),
+        formatdoc! {"
+            Jc = {}
+            Jmin = {}
+            Jmax = {}
+            S1 = {}
+            S2 = {}
+            H1 = {}
+            H2 = {}
+            H3 = {}
+            H4 = {}
+            I1 = {quic}
+            I2 = <b 0x01><c>
+            J1 = <r 10>
+            Itime = 120
+        ", settings.jc, settings.jmin, settings.jmax, settings.s1, settings.s2,
+        settings.h1, settings.h2, settings.h3, settings.h4}
+    );
+
+    settings.i3 = Some(SignaturePacket::new([SignatureTag::Random(0)]));
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("I3".to_string()))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let packet: SignaturePacket = "<b 0xff><r 4>".parse().unwrap();
+
+    let serializer = Serializer::builder().build();
+    assert_ok_eq!(
+        packet.serialize(&serializer),
+        [Token::Str("<b 0xff><r 4>".to_string())]
+    );
+
+    let mut deserializer = Deserializer::builder([Token::Str("<b 0xff><r 4>".to_string())]).build();
+    assert_eq!(
+        SignaturePacket::deserialize(&mut deserializer).unwrap(),
+        packet
+    );
+
+    let mut deserializer = Deserializer::builder([Token::Str("<r>".to_string())]).build();
+    assert_err!(SignaturePacket::deserialize(&mut deserializer));
+}
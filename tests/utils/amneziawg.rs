@@ -79,10 +79,10 @@ fn validate_s2() {
 fn validate_h1_h2_h3_h4() {
     let mut settings = AmneziaSettings::random();
 
-    settings.h1 = 1111; // same
-    settings.h2 = 1111; // same
-    settings.h3 = 3333;
-    settings.h4 = 4444;
+    settings.h1 = 1111.into(); // same
+    settings.h2 = 1111.into(); // same
+    settings.h3 = 3333.into();
+    settings.h4 = 4444.into();
     assert_eq!(
         settings.validate(),
         Err(WireguardError::InvalidAmneziaSetting(
@@ -90,3 +90,166 @@ fn validate_h1_h2_h3_h4() {
         ))
     );
 }
+
+#[test]
+fn magic_header() {
+    let header: MagicHeader = "100-200".parse().unwrap();
+    assert_eq!(header, MagicHeader::from(100..=200));
+    assert!(header.is_range());
+    assert!(header.contains(150));
+    assert!(!header.contains(201));
+    assert_eq!(header.to_string(), "100-200");
+
+    let single: MagicHeader = "200".parse().unwrap();
+    assert!(!single.is_range());
+    assert_eq!(single.to_string(), "200");
+    assert!(header.overlaps(&single));
+    assert!(!header.overlaps(&MagicHeader::from(201)));
+
+    for input in ["", "a", "1-", "-1", "1-2-3"] {
+        assert!(input.parse::<MagicHeader>().is_err(), "{input:?}");
+    }
+}
+
+#[test]
+fn validate_version() {
+    let mut settings = AmneziaSettings::random();
+    settings.h1 = (100..=200).into();
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("H1".to_string()))
+    );
+
+    settings.version = AmneziaVersion::V2;
+    assert!(settings.validate().is_ok());
+
+    settings.h2 = 150.into();
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting(
+            "H1/H2/H3/H4".to_string()
+        ))
+    );
+
+    let mut settings = AmneziaSettings::random();
+    settings.s3 = 10;
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("S3".to_string()))
+    );
+
+    let mut settings = AmneziaSettings::random();
+    settings.s4 = 10;
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("S4".to_string()))
+    );
+
+    let mut settings = AmneziaSettings::random();
+    settings.version = AmneziaVersion::V1;
+    settings.itime = Some(60);
+    assert_eq!(
+        settings.validate(),
+        Err(WireguardError::InvalidAmneziaSetting("Itime".to_string()))
+    );
+}
+
+#[test]
+fn display_v2() {
+    let settings = AmneziaSettings {
+        version: AmneziaVersion::V2,
+        jc: 4,
+        jmin: 40,
+        jmax: 70,
+        s1: 10,
+        s2: 20,
+        s3: 30,
+        s4: 5,
+        h1: (100..=199).into(),
+        h2: (200..=299).into(),
+        h3: 300.into(),
+        h4: (400..=499).into(),
+        ..AmneziaSettings::random()
+    };
+    assert!(settings.validate().is_ok());
+
+    assert_eq!(
+        settings.to_string(),
+        "Jc = 4\nJmin = 40\nJmax = 70\nS1 = 10\nS2 = 20\nS3 = 30\nS4 = 5\n\
+         H1 = 100-199\nH2 = 200-299\nH3 = 300\nH4 = 400-499\n"
+    );
+
+    // S3/S4 aren't rendered before AmneziaWG 2.0
+    let settings = AmneziaSettings {
+        version: AmneziaVersion::V1_5,
+        s3: 0,
+        s4: 0,
+        h1: 100.into(),
+        h2: 200.into(),
+        h3: 300.into(),
+        h4: 400.into(),
+        ..settings
+    };
+    assert!(!settings.to_string().contains("S3"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_magic_header() {
+    use serde::{Deserialize, Serialize};
+    use serde_assert::{Deserializer, Serializer, Token};
+
+    let serializer = Serializer::builder().build();
+    assert_eq!(
+        MagicHeader::from(5).serialize(&serializer).unwrap(),
+        [Token::U64(5)]
+    );
+    assert_eq!(
+        MagicHeader::from(5..=9).serialize(&serializer).unwrap(),
+        [Token::Str("5-9".to_string())]
+    );
+
+    let mut deserializer = Deserializer::builder([Token::Str("5-9".to_string())])
+        .self_describing(true)
+        .build();
+    assert_eq!(
+        MagicHeader::deserialize(&mut deserializer).unwrap(),
+        MagicHeader::from(5..=9)
+    );
+    let mut deserializer = Deserializer::builder([Token::U64(5)])
+        .self_describing(true)
+        .build();
+    assert_eq!(
+        MagicHeader::deserialize(&mut deserializer).unwrap(),
+        MagicHeader::from(5)
+    );
+
+    // non-self-describing formats get fixed representation
+    let tokens = [
+        Token::Tuple { len: 2 },
+        Token::U64(5),
+        Token::U64(9),
+        Token::TupleEnd,
+    ];
+    let serializer = Serializer::builder().is_human_readable(false).build();
+    assert_eq!(
+        MagicHeader::from(5..=9).serialize(&serializer).unwrap(),
+        tokens
+    );
+    let mut deserializer = Deserializer::builder(tokens)
+        .is_human_readable(false)
+        .build();
+    assert_eq!(
+        MagicHeader::deserialize(&mut deserializer).unwrap(),
+        MagicHeader::from(5..=9)
+    );
+    let mut deserializer = Deserializer::builder([
+        Token::Tuple { len: 2 },
+        Token::U64(9),
+        Token::U64(5),
+        Token::TupleEnd,
+    ])
+    .is_human_readable(false)
+    .build();
+    assert!(MagicHeader::deserialize(&mut deserializer).is_err());
+}
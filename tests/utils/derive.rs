@@ -0,0 +1,72 @@
+use wireguard_conf::prelude::*;
+
+/// Master secret for test vectors: `00 01 02 ... 1f`.
+fn master() -> [u8; 32] {
+    core::array::from_fn(|i| i as u8)
+}
+
+// Test vectors were computed independently of this crate. They must never change.
+
+#[test]
+fn private_key_test_vectors() {
+    let vectors = [
+        ("server", "ICtaiySijNVhsAKRrYHdsOq6EOomVJh+Ug14StbKBkw="),
+        ("client-1", "EGs12o6brOtikoVF/r3y/7sGQMkI0PLg+w0nya9Djno="),
+        ("", "INGQdH3C2dYkh62KpVO9YQs0HI/AFsATmGObO7xtvkM="),
+    ];
+
+    for (label, expected) in vectors {
+        assert_eq!(
+            PrivateKey::derive(master(), label).to_string(),
+            expected,
+            "label: {label:?}"
+        );
+    }
+
+    assert_eq!(
+        PrivateKey::derive(master(), "server")
+            .public_key()
+            .to_string(),
+        "5+cS/LfzBLO8LIgLTPvVnxyG5wasNhHXkCDabXzEkQw="
+    );
+}
+
+#[test]
+fn preshared_key_test_vectors() {
+    let vectors = [
+        (
+            "server",
+            "client-1",
+            "DQKiGvfL5fvllaRsM4/13EA17ZCkGm4aSq1KtCjg22I=",
+        ),
+        (
+            "alice",
+            "bob",
+            "RdrRgCVSFYRfFqJ33PADotvgRq/tC5tPQEY/QcTz3sk=",
+        ),
+    ];
+
+    for (peer_a, peer_b, expected) in vectors {
+        assert_eq!(
+            PresharedKey::derive(master(), peer_a, peer_b).to_string(),
+            expected
+        );
+        assert_eq!(
+            PresharedKey::derive(master(), peer_b, peer_a).to_string(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn different_inputs() {
+    assert_ne!(
+        PrivateKey::derive(master(), "a"),
+        PrivateKey::derive([0u8; 32], "a")
+    );
+    // labels are length-prefixed, so concatenation is not ambiguous
+    assert_ne!(
+        PresharedKey::derive(master(), "ab", "c"),
+        PresharedKey::derive(master(), "a", "bc")
+    );
+}
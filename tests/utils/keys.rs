@@ -31,6 +31,28 @@ pub fn private_key() {
     );
 }
 
+#[test]
+pub fn private_key_redacted() {
+    let private_key = PrivateKey::random();
+
+    assert_eq!(format!("{private_key:?}"), "PrivateKey([REDACTED])");
+    assert_eq!(*private_key.expose_secret(), private_key.to_string());
+}
+
+#[test]
+pub fn invalid_length() {
+    // 31 bytes
+    assert_eq!(
+        PrivateKey::try_from("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=="),
+        Err(WireguardError::InvalidPrivateKey)
+    );
+    // 33 bytes
+    assert_eq!(
+        PublicKey::try_from("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+        Err(WireguardError::InvalidPublicKey)
+    );
+}
+
 #[cfg(feature = "serde")]
 #[test]
 pub fn private_key_serde() {
@@ -65,6 +87,17 @@ pub fn public_key() {
     );
 }
 
+#[test]
+pub fn public_key_cached() {
+    let private_key = PrivateKey::random();
+    let imported_key = PrivateKey::try_from(private_key.to_string()).unwrap();
+    let bytes_key = PrivateKey::from(private_key.to_bytes());
+
+    assert_eq!(private_key.public_key(), PublicKey::from(&private_key));
+    assert_eq!(imported_key.public_key(), private_key.public_key());
+    assert_eq!(bytes_key.public_key(), private_key.public_key());
+}
+
 #[cfg(feature = "serde")]
 #[test]
 pub fn public_key_serde() {
@@ -99,6 +132,26 @@ pub fn preshared_key() {
     );
 }
 
+#[test]
+pub fn preshared_key_redacted() {
+    let preshared_key = PresharedKey::random();
+
+    assert_eq!(format!("{preshared_key:?}"), "PresharedKey([REDACTED])");
+    assert_eq!(*preshared_key.expose_secret(), preshared_key.to_string());
+    assert_ne!(preshared_key, PresharedKey::random());
+}
+
+#[test]
+pub fn preshared_key_hash() {
+    let preshared_key =
+        PresharedKey::try_from("MA4zR0tvQpZ4CQ7gs/KcAVMNMGIFBtDcfpjBr+0GwHY=").unwrap();
+    let hash = preshared_key.hash();
+
+    assert_eq!(hash, preshared_key.clone().hash());
+    assert_ne!(hash, PresharedKey::random().hash());
+    assert_eq!(PresharedKeyHash::try_from(hash.to_string()), Ok(hash));
+}
+
 #[cfg(feature = "serde")]
 #[test]
 pub fn preshared_key_serde() {
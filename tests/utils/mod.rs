@@ -1,2 +1,10 @@
+mod amnezia_builder;
+mod amnezia_diagnostics;
+mod amnezia_signature;
 mod amneziawg;
+mod derive;
+mod encoding;
 mod keys;
+mod provider;
+mod rng;
+mod vanity;
@@ -0,0 +1,139 @@
+use claims::assert_matches;
+use wireguard_conf::prelude::*;
+
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_key_file(name: &str, content: &str, mode: u32) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "wireguard-conf-provider-{}-{name}",
+        std::process::id()
+    ));
+    fs::write(&path, content).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    path
+}
+
+#[test]
+fn file() {
+    let key = PrivateKey::random();
+    let path = temp_key_file("private.key", &format!("{key}\n"), 0o600);
+
+    assert_eq!(KeySource::File(path.clone()).private_key().unwrap(), key);
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn file_permissions() {
+    let key = PresharedKey::random();
+    let path = temp_key_file("preshared.key", &format!("{key}\n"), 0o644);
+
+    assert_matches!(
+        KeySource::File(path.clone()).preshared_key(),
+        Err(WireguardError::KeyFile { reason, .. }) if reason.contains("0644")
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn env() {
+    let key = PresharedKey::random();
+    std::env::set_var("WIREGUARD_CONF_TEST_PSK", key.to_string());
+
+    assert_eq!(
+        KeySource::Env("WIREGUARD_CONF_TEST_PSK".to_string())
+            .preshared_key()
+            .unwrap(),
+        key
+    );
+    assert_matches!(
+        KeySource::Env("WIREGUARD_CONF_TEST_MISSING".to_string()).private_key(),
+        Err(WireguardError::KeyProvider(_))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn command() {
+    let key = PrivateKey::random();
+
+    let source = KeySource::Command {
+        program: "echo".to_string(),
+        args: vec![key.to_string()],
+    };
+    assert_eq!(source.private_key().unwrap(), key);
+
+    let source = KeySource::Command {
+        program: "sh".to_string(),
+        args: vec!["-c".to_string(), "echo oops >&2; exit 3".to_string()],
+    };
+    assert_matches!(
+        source.private_key(),
+        Err(WireguardError::KeyProvider(reason)) if reason.contains("oops")
+    );
+}
+
+#[test]
+fn custom_provider() {
+    struct Static(PrivateKey);
+
+    impl KeyProvider for Static {
+        fn load(&self) -> WireguardResult<zeroize::Zeroizing<String>> {
+            Ok(self.0.expose_secret())
+        }
+    }
+
+    let key = PrivateKey::random();
+    assert_eq!(Static(key.clone()).private_key().unwrap(), key);
+}
+
+#[test]
+fn shell_path() {
+    assert_eq!(
+        KeySource::File("/etc/wireguard/wg0.key".into()).shell_path(),
+        "/etc/wireguard/wg0.key"
+    );
+    assert_eq!(
+        KeySource::File("/root/my keys/it's.key".into()).shell_path(),
+        r"'/root/my keys/it'\''s.key'"
+    );
+    assert_eq!(
+        KeySource::Env("WG_KEY".to_string()).shell_path(),
+        "<(printenv WG_KEY)"
+    );
+}
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+#[test]
+fn keyring() {
+    use linux_keyutils::{KeyRing, KeyRingIdentifier};
+
+    let Ok(session) = KeyRing::from_special_id(KeyRingIdentifier::Session, false) else {
+        // kernel keyring isn't available (f.e. in containers)
+        return;
+    };
+
+    let key = PrivateKey::random();
+    let description = format!("wireguard-conf-test-{}", std::process::id());
+    let Ok(stored) = session.add_key(&description, key.to_string().as_bytes()) else {
+        return;
+    };
+
+    let source = KeySource::Keyring(description.clone());
+    assert_eq!(source.private_key().unwrap(), key);
+    assert_eq!(
+        source.shell_path(),
+        format!("<(keyctl pipe %user:{description})")
+    );
+
+    stored.invalidate().unwrap();
+}
@@ -0,0 +1,84 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use wireguard_conf::{as_ipnet, prelude::*};
+
+fn network(seed: u64) -> (Interface, Interface) {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+
+    let client_peer = PeerBuilder::new()
+        .allowed_ips([as_ipnet!("10.0.0.2/32")])
+        .preshared_key(PresharedKey::random_with_rng(&mut rng))
+        .build_with_rng(&mut rng);
+
+    let server_interface = InterfaceBuilder::new()
+        .address([as_ipnet!("10.0.0.1/24")])
+        .peers([client_peer.clone()])
+        .build_with_rng(&mut rng);
+
+    let client_interface = client_peer
+        .to_interface(&server_interface, ToInterfaceOptions::new())
+        .expect("failed to generate client interface");
+
+    (server_interface, client_interface)
+}
+
+#[test]
+fn private_key() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+    assert_eq!(
+        PrivateKey::random_with_rng(&mut rng),
+        PrivateKey::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0))
+    );
+    assert_ne!(
+        PrivateKey::random_with_rng(&mut rng),
+        PrivateKey::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0))
+    );
+}
+
+#[test]
+fn preshared_key() {
+    assert_eq!(
+        PresharedKey::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0)),
+        PresharedKey::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0))
+    );
+}
+
+#[cfg(feature = "amneziawg")]
+#[test]
+fn amnezia_settings() {
+    let settings = AmneziaSettings::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0));
+
+    assert_eq!(
+        settings,
+        AmneziaSettings::random_with_rng(&mut ChaCha20Rng::seed_from_u64(0))
+    );
+    assert!(settings.validate().is_ok());
+}
+
+#[test]
+fn builders() {
+    let private_key = PrivateKey::random();
+
+    // explicitly set keys aren't replaced
+    let interface = InterfaceBuilder::new()
+        .private_key(private_key.clone())
+        .build_with_rng(&mut ChaCha20Rng::seed_from_u64(0));
+    let peer = PeerBuilder::new()
+        .private_key(private_key.clone())
+        .build_with_rng(&mut ChaCha20Rng::seed_from_u64(0));
+
+    assert_eq!(interface.private_key, private_key);
+    assert_eq!(peer.key.unwrap_left(), private_key);
+}
+
+#[test]
+fn whole_network() {
+    let (server, client) = network(42);
+
+    assert_eq!(network(42), (server.clone(), client.clone()));
+    assert_eq!(server.to_string(), network(42).0.to_string());
+    assert_eq!(client.to_string(), network(42).1.to_string());
+
+    assert_ne!(network(43).0, server);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "vanity")]
+use std::time::Duration;
+
+use regex::Regex;
+use wireguard_conf::prelude::*;
+
+#[test]
+fn prefix() {
+    let found = VanitySearch::new(VanityPattern::Prefix("A".to_string()))
+        .unwrap()
+        .threads(2)
+        .run(&CancelToken::new(), |_| {})
+        .expect("search was cancelled");
+
+    assert!(found.public_key.to_string().starts_with('A'));
+    assert_eq!(found.private_key.public_key(), found.public_key);
+    assert!(found.attempts >= 1);
+}
+
+#[test]
+fn regex() {
+    let pattern = VanityPattern::Regex(Regex::new("^[0-9]").unwrap());
+
+    let found = VanitySearch::new(pattern)
+        .unwrap()
+        .run(&CancelToken::new(), |_| {})
+        .expect("search was cancelled");
+
+    assert!(found
+        .public_key
+        .to_string()
+        .starts_with(|c: char| c.is_ascii_digit()));
+}
+
+#[test]
+fn matches() {
+    let pattern = VanityPattern::PrefixCaseInsensitive("srv1/".to_string());
+
+    assert!(pattern.matches("SrV1/abc="));
+    assert!(!pattern.matches("srv2/abc="));
+    assert!(!pattern.matches("srv"));
+    assert!(VanityPattern::Prefix("srv1/".to_string()).matches("srv1/abc="));
+    assert!(!VanityPattern::Prefix("srv1/".to_string()).matches("SRV1/abc="));
+}
+
+#[test]
+fn expected_attempts() {
+    assert_eq!(
+        VanityPattern::Prefix("ab".to_string()).expected_attempts(),
+        Some(4096.0)
+    );
+    assert_eq!(
+        VanityPattern::PrefixCaseInsensitive("a1".to_string()).expected_attempts(),
+        Some(2048.0)
+    );
+    assert_eq!(
+        VanityPattern::Regex(Regex::new("a").unwrap()).expected_attempts(),
+        None
+    );
+}
+
+#[test]
+fn invalid_pattern() {
+    assert!(matches!(
+        VanitySearch::new(VanityPattern::Prefix("srv-1".to_string())),
+        Err(WireguardError::InvalidVanityPattern(_))
+    ));
+    assert!(matches!(
+        VanitySearch::new(VanityPattern::Prefix("A".repeat(44))),
+        Err(WireguardError::InvalidVanityPattern(_))
+    ));
+
+    // last character of key encodes only 4 bits, highest bit of key is in 42nd one
+    assert!(VanitySearch::new(VanityPattern::Prefix("A".repeat(43))).is_ok());
+    assert!(matches!(
+        VanitySearch::new(VanityPattern::Prefix("A".repeat(41) + "I")),
+        Err(WireguardError::InvalidVanityPattern(_))
+    ));
+    assert!(matches!(
+        VanitySearch::new(VanityPattern::PrefixCaseInsensitive("A".repeat(41) + "o")),
+        Err(WireguardError::InvalidVanityPattern(_))
+    ));
+    assert!(matches!(
+        VanitySearch::new(VanityPattern::Prefix("A".repeat(42) + "B")),
+        Err(WireguardError::InvalidVanityPattern(_))
+    ));
+    assert!(VanitySearch::new(VanityPattern::PrefixCaseInsensitive("A".repeat(42) + "G")).is_ok());
+}
+
+#[test]
+fn cancel_and_progress() {
+    let cancel = CancelToken::new();
+    let mut reports = 0;
+
+    // practically impossible to find
+    let found = VanitySearch::new(VanityPattern::Prefix("wireguardconf".to_string()))
+        .unwrap()
+        .threads(2)
+        .progress_interval(Duration::from_millis(10))
+        .run(&cancel, |progress| {
+            reports += 1;
+            assert_eq!(progress.expected_attempts, Some(64f64.powi(13)));
+            if reports == 3 {
+                cancel.cancel();
+            }
+        });
+
+    assert_eq!(found, None);
+    assert!(cancel.is_cancelled());
+    assert!(reports >= 3);
+}